@@ -1,15 +1,43 @@
 use std::sync::Arc;
 
 use alloy::primitives::Address;
+use anyhow::{anyhow, Result};
 
-use crate::genesis::Genesis;
+use crate::{genesis::Genesis, types::network::Network};
 
 #[derive(Debug, Clone)]
 
 pub struct Config {
     pub chain_id: u64,
     pub owner: Option<Address>,
-    pub owshen: Address,
     pub genesis: Arc<Genesis>,
-    pub provider_address: reqwest::Url,
+    /// Networks the bridge accepts deposits from and withdrawals to
+    pub networks: Vec<Network>,
+}
+
+impl Config {
+    pub fn network(&self, chain_id: u64) -> Result<&Network> {
+        self.networks
+            .iter()
+            .find(|n| n.chain_id == chain_id)
+            .ok_or(anyhow!("Network {} is not supported!", chain_id))
+    }
+}
+
+#[cfg(test)]
+impl Config {
+    /// Chain 1387 without an owner, bridged to a single network with chain id 1
+    pub fn for_tests() -> Self {
+        Config {
+            chain_id: 1387,
+            owner: None,
+            genesis: crate::genesis::GENESIS.clone(),
+            networks: vec![Network {
+                chain_id: 1,
+                owshen: crate::config::OWSHEN_CONTRACT,
+                provider_address: "http://127.0.0.1:8888".parse().expect("failed to parse"),
+                confirmations: 1,
+            }],
+        }
+    }
 }
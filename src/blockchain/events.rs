@@ -0,0 +1,24 @@
+use alloy::primitives::FixedBytes;
+use tokio::sync::broadcast;
+
+use super::logs::LogEntry;
+use crate::types::Block;
+
+/// Events kept for subscribers that fall behind, older ones are dropped
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// Changes of the chain and its queue, published for the RPC subscriptions
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum ChainEvent {
+    /// A block was added to the chain, along with the logs it emitted
+    Block { block: Block, logs: Vec<LogEntry> },
+    /// A transaction entered the queue
+    PendingTransaction(FixedBytes<32>),
+}
+
+pub type EventSender = broadcast::Sender<ChainEvent>;
+
+pub fn channel() -> EventSender {
+    broadcast::channel(EVENT_CAPACITY).0
+}
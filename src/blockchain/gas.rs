@@ -0,0 +1,284 @@
+use alloy::{
+    consensus::Transaction,
+    primitives::{Address, U256},
+    rpc::types::AccessList,
+};
+use anyhow::{anyhow, Result};
+use evm::Config;
+
+use crate::{
+    db::{Key, Value},
+    services::ContextKvStore,
+    types::{Block, OwshenTransaction, Token},
+};
+
+use super::{Blockchain, Owshenchain};
+
+/// Gas schedule of the OVM and the intrinsic transaction costs
+pub static GAS_CONFIG: Config = Config::shanghai();
+
+pub const BLOCK_GAS_LIMIT: u64 = 30_000_000;
+
+/// Largest block accepted, counted as the encoded size of its transactions
+pub const MAX_BLOCK_SIZE: u64 = 2 * 1024 * 1024;
+
+/// How much gas and space the blocks this node produces may take, within
+/// the limits every block is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockLimits {
+    pub gas: u64,
+    pub size: u64,
+}
+
+impl Default for BlockLimits {
+    fn default() -> Self {
+        Self {
+            gas: BLOCK_GAS_LIMIT,
+            size: MAX_BLOCK_SIZE,
+        }
+    }
+}
+
+/// EIP-1559 fee market parameters
+pub const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Tip suggested to wallets when recent blocks carry no priced transactions
+pub const DEFAULT_PRIORITY_FEE: u64 = 1_000_000_000;
+/// Number of recent blocks the suggested tip is sampled from
+pub const PRIORITY_FEE_BLOCKS: usize = 20;
+
+/// Built-in ERC-20 operations are charged on top of the intrinsic cost, about
+/// what a Solidity token spends on the same storage writes
+pub const ERC20_TRANSFER_GAS: u64 = 30_000;
+pub const ERC20_TRANSFER_FROM_GAS: u64 = 35_000;
+pub const ERC20_APPROVE_GAS: u64 = 25_000;
+
+/// Mints and burns carry no gas price, their gas only counts towards the block
+pub const CUSTOM_TX_GAS: u64 = 50_000;
+
+/// Gas paid before any execution: the base cost, calldata, access list and
+/// the EIP-3860 init code words of contract creations
+pub fn intrinsic_gas(input: &[u8], is_create: bool, access_list: &AccessList) -> u64 {
+    let zero_bytes = input.iter().filter(|b| **b == 0).count() as u64;
+    let non_zero_bytes = input.len() as u64 - zero_bytes;
+    let storage_keys = access_list
+        .iter()
+        .map(|item| item.storage_keys.len() as u64)
+        .sum::<u64>();
+
+    let mut gas = if is_create {
+        GAS_CONFIG.gas_transaction_create + 2 * ((input.len() as u64).div_ceil(32))
+    } else {
+        GAS_CONFIG.gas_transaction_call
+    };
+    gas += zero_bytes * GAS_CONFIG.gas_transaction_zero_data;
+    gas += non_zero_bytes * GAS_CONFIG.gas_transaction_non_zero_data;
+    gas += access_list.len() as u64 * GAS_CONFIG.gas_access_list_address;
+    gas += storage_keys * GAS_CONFIG.gas_access_list_storage_key;
+    gas
+}
+
+/// Base fee of the block following one with the given base fee and gas usage
+pub fn next_base_fee(parent_base_fee: u64, parent_gas_used: u64) -> u64 {
+    let target = (BLOCK_GAS_LIMIT / ELASTICITY_MULTIPLIER) as u128;
+    let base_fee = parent_base_fee as u128;
+    let gas_used = parent_gas_used as u128;
+    let denominator = BASE_FEE_MAX_CHANGE_DENOMINATOR as u128;
+    if gas_used > target {
+        let delta = (base_fee * (gas_used - target) / target / denominator).max(1);
+        (base_fee + delta).min(u64::MAX as u128) as u64
+    } else {
+        let delta = base_fee * (target - gas_used) / target / denominator;
+        (base_fee - delta) as u64
+    }
+}
+
+/// Moves `gas_used * gas_price` of the native token from `payer` to the
+/// block producer
+pub fn charge_fee<K: ContextKvStore>(
+    chain: &mut Owshenchain<K>,
+    payer: Address,
+    coinbase: Address,
+    gas_used: u64,
+    gas_price: U256,
+) -> Result<()> {
+    let fee = U256::from(gas_used) * gas_price;
+    if fee.is_zero() {
+        return Ok(());
+    }
+    let payer_balance = chain.get_balance(Token::Native, payer)?;
+    if payer_balance < fee {
+        return Err(anyhow!("Insufficient balance to pay the fee!"));
+    }
+    chain.db.put(
+        Key::Balance(payer, Token::Native),
+        Some(Value::U256(payer_balance - fee)),
+    )?;
+    let coinbase_balance = chain.get_balance(Token::Native, coinbase)?;
+    chain.db.put(
+        Key::Balance(coinbase, Token::Native),
+        Some(Value::U256(coinbase_balance + fee)),
+    )?;
+    Ok(())
+}
+
+/// Fee data of a range of blocks, as returned by `eth_feeHistory`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FeeHistory {
+    pub oldest_block: u64,
+    /// One entry per block, followed by the base fee of the block after them
+    pub base_fee_per_gas: Vec<u64>,
+    pub gas_used_ratio: Vec<f64>,
+    /// Tips paid at the requested percentiles of each block's gas
+    pub reward: Vec<Vec<u128>>,
+}
+
+/// Tips paid by the priced transactions of `block` along with their gas,
+/// cheapest first
+fn block_tips<B: Blockchain>(chain: &B, block: &Block) -> Result<Vec<(u128, u64)>> {
+    let mut tips = Vec::new();
+    for tx in block.txs.iter() {
+        let tx: OwshenTransaction = tx.try_into()?;
+        if let OwshenTransaction::Eth(eth_tx) = &tx {
+            let tip = eth_tx
+                .effective_tip_per_gas(block.base_fee_per_gas)
+                .unwrap_or_default();
+            tips.push((tip, chain.get_gas_used(tx.hash()?)?));
+        }
+    }
+    tips.sort();
+    Ok(tips)
+}
+
+/// Tip that covers `percentile` percent of the gas of the given transactions
+fn tip_at_percentile(tips: &[(u128, u64)], percentile: f64) -> u128 {
+    let total_gas = tips.iter().map(|(_, gas)| gas).sum::<u64>();
+    let threshold = total_gas as f64 * percentile / 100.0;
+    let mut cumulative_gas = 0;
+    for (tip, gas) in tips {
+        cumulative_gas += gas;
+        if cumulative_gas as f64 >= threshold {
+            return *tip;
+        }
+    }
+    tips.last().map(|(tip, _)| *tip).unwrap_or_default()
+}
+
+/// Base fees, gas usage and tips of the `block_count` blocks ending with
+/// `newest_block`
+pub fn fee_history<B: Blockchain>(
+    chain: &B,
+    block_count: u64,
+    newest_block: u64,
+    percentiles: &[f64],
+) -> Result<FeeHistory> {
+    if newest_block >= chain.get_height()? as u64 {
+        return Err(anyhow!("Block doesn't exist!"));
+    }
+    if percentiles.windows(2).any(|w| w[0] > w[1])
+        || percentiles.iter().any(|p| !(0.0..=100.0).contains(p))
+    {
+        return Err(anyhow!("Invalid reward percentiles!"));
+    }
+    let block_count = block_count.min(newest_block + 1);
+    let oldest_block = newest_block + 1 - block_count;
+
+    let mut history = FeeHistory {
+        oldest_block,
+        ..Default::default()
+    };
+    for index in oldest_block..=newest_block {
+        let block = chain.get_block(index as usize)?;
+        history.base_fee_per_gas.push(block.base_fee_per_gas);
+        history
+            .gas_used_ratio
+            .push(block.gas_used as f64 / BLOCK_GAS_LIMIT as f64);
+        if !percentiles.is_empty() {
+            let tips = block_tips(chain, &block)?;
+            history.reward.push(
+                percentiles
+                    .iter()
+                    .map(|p| tip_at_percentile(&tips, *p))
+                    .collect(),
+            );
+        }
+        if index == newest_block {
+            history
+                .base_fee_per_gas
+                .push(next_base_fee(block.base_fee_per_gas, block.gas_used));
+        }
+    }
+    Ok(history)
+}
+
+/// Median tip paid in recent blocks, for wallets to quote
+pub fn suggest_priority_fee<B: Blockchain>(chain: &B) -> Result<u128> {
+    let height = chain.get_height()?;
+    let mut medians = Vec::new();
+    for index in height.saturating_sub(PRIORITY_FEE_BLOCKS)..height {
+        let tips = block_tips(chain, &chain.get_block(index)?)?;
+        if !tips.is_empty() {
+            medians.push(tip_at_percentile(&tips, 50.0));
+        }
+    }
+    if medians.is_empty() {
+        return Ok(DEFAULT_PRIORITY_FEE as u128);
+    }
+    medians.sort();
+    Ok(medians[medians.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{primitives::B256, rpc::types::AccessListItem};
+
+    #[test]
+    fn test_intrinsic_gas() {
+        assert_eq!(intrinsic_gas(&[], false, &AccessList::default()), 21_000);
+        assert_eq!(
+            intrinsic_gas(&[0, 1], false, &AccessList::default()),
+            21_020
+        );
+        assert_eq!(
+            intrinsic_gas(&[1; 33], true, &AccessList::default()),
+            53_000 + 33 * 16 + 2 * 2
+        );
+        let access_list = AccessList(vec![AccessListItem {
+            address: Address::ZERO,
+            storage_keys: vec![B256::ZERO, B256::ZERO],
+        }]);
+        assert_eq!(
+            intrinsic_gas(&[], false, &access_list),
+            21_000 + 2400 + 2 * 1900
+        );
+    }
+
+    #[test]
+    fn test_next_base_fee() {
+        let target = BLOCK_GAS_LIMIT / ELASTICITY_MULTIPLIER;
+        assert_eq!(next_base_fee(1_000_000_000, target), 1_000_000_000);
+        assert_eq!(next_base_fee(1_000_000_000, BLOCK_GAS_LIMIT), 1_125_000_000);
+        assert_eq!(next_base_fee(1_000_000_000, 0), 875_000_000);
+        assert_eq!(
+            next_base_fee(1_000_000_000, target + target / 2),
+            1_062_500_000
+        );
+        // Always moves up when above the target
+        assert_eq!(next_base_fee(1, target + 1), 2);
+        assert_eq!(next_base_fee(7, 0), 7);
+    }
+
+    #[test]
+    fn test_tip_at_percentile() {
+        let tips = [(1, 21_000), (5, 21_000), (10, 42_000)];
+        assert_eq!(tip_at_percentile(&tips, 0.0), 1);
+        assert_eq!(tip_at_percentile(&tips, 25.0), 1);
+        assert_eq!(tip_at_percentile(&tips, 50.0), 5);
+        assert_eq!(tip_at_percentile(&tips, 75.0), 10);
+        assert_eq!(tip_at_percentile(&tips, 100.0), 10);
+        assert_eq!(tip_at_percentile(&[], 50.0), 0);
+    }
+}
@@ -0,0 +1,222 @@
+use alloy::primitives::{keccak256, Address, FixedBytes};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::db::{Key, KvStore, Value};
+use crate::types::{Log, Receipt};
+
+/// The log index maps every emitting address and every (position, topic) pair
+/// to the blocks containing a matching log. Blocks are grouped in buckets of
+/// this size so a range query only reads the buckets it overlaps.
+pub const LOG_INDEX_BUCKET: usize = 1024;
+
+/// Most blocks a single log query may span
+pub const MAX_LOG_BLOCK_RANGE: usize = 10_000;
+
+/// Most logs a single log query may return
+pub const MAX_LOG_RESULTS: usize = 10_000;
+
+/// A log along with where it was emitted, `log_index` counting from the first
+/// log of the block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub log: Log,
+    pub block_number: usize,
+    pub block_hash: FixedBytes<32>,
+    pub tx_hash: FixedBytes<32>,
+    pub transaction_index: usize,
+    pub log_index: usize,
+}
+
+/// Logs of the blocks `from_block..=to_block` matching the given addresses and
+/// topics, following the `eth_getLogs` semantics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogFilter {
+    pub from_block: usize,
+    pub to_block: usize,
+    /// Accepted emitters, anyone if empty
+    pub addresses: Vec<Address>,
+    /// Accepted values of each topic position, `None` accepting anything
+    pub topics: Vec<Option<Vec<FixedBytes<32>>>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &Log) -> bool {
+        (self.addresses.is_empty() || self.addresses.contains(&log.address))
+            && self.topics.len() <= log.topics.len()
+            && self
+                .topics
+                .iter()
+                .zip(log.topics.iter())
+                .all(|(accepted, topic)| match accepted {
+                    Some(accepted) => accepted.contains(topic),
+                    None => true,
+                })
+    }
+
+    /// Index terms the filter requires, a log has to match at least one term
+    /// of every group
+    fn term_groups(&self) -> Vec<Vec<FixedBytes<32>>> {
+        let mut groups = Vec::new();
+        if !self.addresses.is_empty() {
+            groups.push(self.addresses.iter().map(|a| address_term(*a)).collect());
+        }
+        for (position, accepted) in self.topics.iter().enumerate() {
+            if let Some(accepted) = accepted {
+                groups.push(accepted.iter().map(|t| topic_term(position, *t)).collect());
+            }
+        }
+        groups
+    }
+}
+
+fn address_term(address: Address) -> FixedBytes<32> {
+    keccak256(address)
+}
+
+fn topic_term(position: usize, topic: FixedBytes<32>) -> FixedBytes<32> {
+    keccak256([&[position as u8], topic.as_slice()].concat())
+}
+
+fn indexed_blocks<K: KvStore>(db: &K, term: FixedBytes<32>, bucket: usize) -> Result<Vec<usize>> {
+    Ok(match db.get(Key::LogIndex(term, bucket))? {
+        Some(v) => v.as_vec_usize()?,
+        None => Vec::new(),
+    })
+}
+
+/// Adds the logs of a block to the index, blocks being indexed in order
+pub fn index_logs<K: KvStore>(db: &mut K, block_number: usize, receipts: &[Receipt]) -> Result<()> {
+    let mut terms = BTreeSet::new();
+    for log in receipts.iter().flat_map(|r| r.logs.iter()) {
+        terms.insert(address_term(log.address));
+        for (position, topic) in log.topics.iter().enumerate() {
+            terms.insert(topic_term(position, *topic));
+        }
+    }
+    let bucket = block_number / LOG_INDEX_BUCKET;
+    for term in terms {
+        let mut blocks = indexed_blocks(db, term, bucket)?;
+        blocks.push(block_number);
+        db.put(Key::LogIndex(term, bucket), Some(Value::VecUsize(blocks)))?;
+    }
+    Ok(())
+}
+
+/// Blocks of the filter's range that may contain matching logs. Filters
+/// without addresses or topics can't use the index, so every block is a
+/// candidate.
+pub fn candidate_blocks<K: KvStore>(db: &K, filter: &LogFilter) -> Result<Vec<usize>> {
+    let groups = filter.term_groups();
+    if groups.is_empty() {
+        return Ok((filter.from_block..=filter.to_block).collect());
+    }
+
+    let mut candidates = Vec::new();
+    for bucket in filter.from_block / LOG_INDEX_BUCKET..=filter.to_block / LOG_INDEX_BUCKET {
+        let mut matching: Option<BTreeSet<usize>> = None;
+        for group in groups.iter() {
+            let mut any = BTreeSet::new();
+            for term in group {
+                any.extend(indexed_blocks(db, *term, bucket)?);
+            }
+            matching = Some(match matching {
+                Some(all) => all.intersection(&any).cloned().collect(),
+                None => any,
+            });
+        }
+        candidates.extend(
+            matching
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|b| (filter.from_block..=filter.to_block).contains(b)),
+        );
+    }
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::RamKvStore;
+
+    fn log(address: u8, topics: &[u8]) -> Log {
+        Log {
+            address: Address::from([address; 20]),
+            topics: topics.iter().map(|t| FixedBytes::from([*t; 32])).collect(),
+            data: Vec::new(),
+        }
+    }
+
+    fn receipt(logs: Vec<Log>) -> Receipt {
+        Receipt {
+            tx_hash: FixedBytes::ZERO,
+            success: true,
+            gas_used: 0,
+            cumulative_gas_used: 0,
+            logs,
+        }
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let filter = LogFilter {
+            addresses: vec![Address::from([1; 20]), Address::from([2; 20])],
+            topics: vec![None, Some(vec![FixedBytes::from([5; 32])])],
+            ..Default::default()
+        };
+        assert!(filter.matches(&log(1, &[3, 5])));
+        assert!(filter.matches(&log(2, &[4, 5, 6])));
+        assert!(!filter.matches(&log(3, &[3, 5])));
+        assert!(!filter.matches(&log(1, &[5, 3])));
+        assert!(!filter.matches(&log(1, &[3])));
+        assert!(LogFilter::default().matches(&log(7, &[])));
+    }
+
+    #[test]
+    fn test_candidate_blocks() {
+        let mut db = RamKvStore::new();
+        index_logs(&mut db, 3, &[receipt(vec![log(1, &[5])])]).unwrap();
+        index_logs(&mut db, 7, &[receipt(vec![log(2, &[5]), log(1, &[6])])]).unwrap();
+        index_logs(
+            &mut db,
+            LOG_INDEX_BUCKET + 1,
+            &[receipt(vec![log(1, &[5])])],
+        )
+        .unwrap();
+
+        let filter = |from_block, to_block, addresses: &[u8], topic: Option<u8>| LogFilter {
+            from_block,
+            to_block,
+            addresses: addresses.iter().map(|a| Address::from([*a; 20])).collect(),
+            topics: vec![topic.map(|t| vec![FixedBytes::from([t; 32])])],
+        };
+        let all = 2 * LOG_INDEX_BUCKET;
+        assert_eq!(
+            candidate_blocks(&db, &filter(0, all, &[1], None)).unwrap(),
+            vec![3, 7, LOG_INDEX_BUCKET + 1]
+        );
+        assert_eq!(
+            candidate_blocks(&db, &filter(0, all, &[2], Some(5))).unwrap(),
+            vec![7]
+        );
+        assert_eq!(
+            candidate_blocks(&db, &filter(0, all, &[1, 2], Some(6))).unwrap(),
+            vec![7]
+        );
+        // Terms are matched per block, not per log, so block 7 is a candidate
+        // even though none of its logs match
+        assert_eq!(
+            candidate_blocks(&db, &filter(4, all, &[1], Some(5))).unwrap(),
+            vec![7, LOG_INDEX_BUCKET + 1]
+        );
+        assert!(candidate_blocks(&db, &filter(0, all, &[3], None))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            candidate_blocks(&db, &filter(2, 4, &[], None)).unwrap(),
+            vec![2, 3, 4]
+        );
+    }
+}
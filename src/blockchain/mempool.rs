@@ -0,0 +1,549 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+
+use alloy::consensus::{Transaction, TxEnvelope};
+use alloy::primitives::{Address, FixedBytes, U256};
+use anyhow::{anyhow, Result};
+
+use super::events::{ChainEvent, EventSender};
+use super::gas::{intrinsic_gas, CUSTOM_TX_GAS};
+use super::tx::{check_minter, check_nonce, check_tx_type, effective_gas_price, NonceError};
+use super::Blockchain;
+use crate::types::{BincodableOwshenTransaction, CustomTxMsg, OwshenTransaction, Token};
+
+/// Number of recently rejected transactions kept around for status queries
+const MAX_REJECTED_TXS: usize = 1024;
+
+/// Most transactions the queue holds, the cheapest ones are evicted beyond that
+pub const MAX_QUEUED_TXS: usize = 4096;
+
+/// Most transactions a single sender may have waiting in the queue
+pub const MAX_QUEUED_TXS_PER_SENDER: usize = 64;
+
+/// Largest transaction accepted, in bytes
+pub const MAX_TX_SIZE: u64 = 128 * 1024;
+
+/// A replacement has to raise the fees of the transaction it replaces by at
+/// least this percentage
+pub const REPLACEMENT_FEE_BUMP: u128 = 10;
+
+/// A transaction the queue refuses to admit, as opposed to a failure of the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTransaction(pub String);
+
+impl fmt::Display for InvalidTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidTransaction {}
+
+/// Space `tx` takes in a block
+pub fn encoded_size(tx: &OwshenTransaction) -> Result<u64> {
+    let bin: BincodableOwshenTransaction = tx.try_into()?;
+    Ok(bincode::serialized_size(&bin)?)
+}
+
+/// Most gas `tx` can take from a block
+pub fn gas_limit(tx: &OwshenTransaction) -> u64 {
+    match tx {
+        OwshenTransaction::Eth(eth_tx) => eth_tx.gas_limit() as u64,
+        OwshenTransaction::Custom(_) => CUSTOM_TX_GAS,
+    }
+}
+
+/// What the block producer earns per gas of `tx`. Custom transactions pay
+/// nothing but come from the bridge, so they go first.
+pub fn effective_tip(tx: &OwshenTransaction, base_fee: u64) -> u128 {
+    match tx {
+        OwshenTransaction::Eth(eth_tx) => {
+            effective_gas_price(eth_tx, base_fee).saturating_sub(base_fee as u128)
+        }
+        OwshenTransaction::Custom(_) => u128::MAX,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct QueuedTransaction {
+    tx: OwshenTransaction,
+    hash: FixedBytes<32>,
+    sender: Address,
+    /// Custom transactions have a nonce of their own
+    custom: bool,
+    nonce: U256,
+}
+
+impl QueuedTransaction {
+    fn new(tx: OwshenTransaction) -> Result<Self> {
+        let (custom, nonce) = match &tx {
+            OwshenTransaction::Eth(eth_tx) => (false, U256::from(eth_tx.nonce())),
+            OwshenTransaction::Custom(custom_tx) => (true, custom_tx.msg()?.nonce()),
+        };
+        Ok(Self {
+            hash: tx.hash()?,
+            sender: tx.signer()?,
+            custom,
+            nonce,
+            tx,
+        })
+    }
+
+    /// Fee per gas paid at the given base fee. Custom transactions pay no fee
+    /// but come from the bridge, so they are never the cheapest.
+    fn gas_price(&self, base_fee: u64) -> u128 {
+        match &self.tx {
+            OwshenTransaction::Eth(eth_tx) => effective_gas_price(eth_tx, base_fee),
+            OwshenTransaction::Custom(_) => u128::MAX,
+        }
+    }
+
+    /// Most native tokens the transaction may take from its sender
+    fn max_cost(&self, base_fee: u64) -> U256 {
+        match &self.tx {
+            OwshenTransaction::Eth(eth_tx) => {
+                U256::from(eth_tx.gas_limit()) * U256::from(self.gas_price(base_fee))
+                    + eth_tx.value()
+            }
+            OwshenTransaction::Custom(_) => U256::ZERO,
+        }
+    }
+
+    fn is_sibling(&self, other: &QueuedTransaction) -> bool {
+        self.sender == other.sender && self.custom == other.custom
+    }
+}
+
+/// Whether `new` raises both fee caps of `old` enough to replace it
+fn is_fee_bump(old: &OwshenTransaction, new: &OwshenTransaction) -> bool {
+    let bumped = |old: u128, new: u128| new * 100 >= old * (100 + REPLACEMENT_FEE_BUMP);
+    match (old, new) {
+        (OwshenTransaction::Eth(old), OwshenTransaction::Eth(new)) => {
+            bumped(old.max_fee_per_gas(), new.max_fee_per_gas())
+                && bumped(
+                    old.max_priority_fee_per_gas()
+                        .unwrap_or(old.max_fee_per_gas()),
+                    new.max_priority_fee_per_gas()
+                        .unwrap_or(new.max_fee_per_gas()),
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Transactions waiting to be included, in the order they arrived. Those
+/// coming from users go through `add`, which only admits transactions that
+/// could be executed on top of the ones already queued.
+#[derive(Debug, Clone)]
+pub struct TransactionQueue {
+    queue: VecDeque<QueuedTransaction>,
+    rejected: VecDeque<(OwshenTransaction, String)>,
+    events: Option<EventSender>,
+}
+
+impl TransactionQueue {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            rejected: VecDeque::new(),
+            events: None,
+        }
+    }
+
+    /// A queue announcing the transactions entering it on `events`
+    pub fn with_events(events: EventSender) -> Self {
+        Self {
+            events: Some(events),
+            ..Self::new()
+        }
+    }
+
+    fn announce(&self, hash: FixedBytes<32>) {
+        if let Some(events) = &self.events {
+            // Nobody listening is not an error
+            let _ = events.send(ChainEvent::PendingTransaction(hash));
+        }
+    }
+
+    /// Queues a transaction without validating it, for the ones the node
+    /// creates itself. Unsigned transactions can't be included and are
+    /// rejected right away.
+    pub fn enqueue(&mut self, tx: OwshenTransaction) {
+        match QueuedTransaction::new(tx.clone()) {
+            Ok(queued) => {
+                self.announce(queued.hash);
+                self.queue.push_back(queued);
+            }
+            Err(e) => self.reject(tx, e.to_string()),
+        }
+    }
+
+    /// Validates a transaction against the chain and the transactions already
+    /// queued and queues it, replacing a queued transaction of the same sender
+    /// and nonce if it pays enough more. Refusals come back as `NonceError` or
+    /// `InvalidTransaction`.
+    pub fn add<B: Blockchain>(
+        &mut self,
+        chain: &B,
+        tx: OwshenTransaction,
+    ) -> Result<FixedBytes<32>> {
+        self.admit(chain, tx)
+            .map_err(|e| match e.is::<NonceError>() {
+                true => e,
+                false => InvalidTransaction(e.to_string()).into(),
+            })
+    }
+
+    fn admit<B: Blockchain>(&mut self, chain: &B, tx: OwshenTransaction) -> Result<FixedBytes<32>> {
+        if tx.chain_id()? != chain.config().chain_id {
+            return Err(anyhow!("Chain id is not valid!"));
+        }
+        let base_fee = chain.pending_base_fee()?;
+        if let OwshenTransaction::Eth(eth_tx) = &tx {
+            check_tx_type(eth_tx)?;
+            if eth_tx.max_fee_per_gas() < base_fee as u128 {
+                return Err(anyhow!("Max fee per gas is lower than the base fee!"));
+            }
+            if eth_tx.gas_limit() as u64 > chain.block_limits().gas {
+                return Err(anyhow!("Gas limit exceeds the block gas limit!"));
+            }
+            let access_list = eth_tx.access_list().cloned().unwrap_or_default();
+            let intrinsic = intrinsic_gas(eth_tx.input(), eth_tx.to().is_create(), &access_list);
+            if (eth_tx.gas_limit() as u64) < intrinsic {
+                return Err(anyhow!("Intrinsic gas too low!"));
+            }
+            if let Some(max_priority_fee_per_gas) = eth_tx.max_priority_fee_per_gas() {
+                if max_priority_fee_per_gas > eth_tx.max_fee_per_gas() {
+                    return Err(anyhow!(
+                        "Max priority fee per gas is higher than the max fee!"
+                    ));
+                }
+            }
+        }
+        // Blocks don't depend on the local networks, only bridging to a network
+        // this node knows about is admitted
+        if let OwshenTransaction::Custom(custom_tx) = &tx {
+            let msg = custom_tx.msg()?;
+            chain.config().network(msg.network_id())?;
+            if let CustomTxMsg::MintTx(_) = msg {
+                check_minter(chain.config(), tx.signer()?)?;
+            }
+        }
+        if encoded_size(&tx)? > MAX_TX_SIZE {
+            return Err(anyhow!("Transaction is too large!"));
+        }
+
+        let new = QueuedTransaction::new(tx)?;
+        if self.contains(new.hash) {
+            return Err(anyhow!("Transaction already known!"));
+        }
+        let siblings = self
+            .queue
+            .iter()
+            .filter(|queued| queued.is_sibling(&new))
+            .collect::<Vec<_>>();
+        // Only eth transactions can be replaced, they are the ones paying fees
+        let replaced = match new.custom {
+            true => None,
+            false => siblings.iter().position(|queued| queued.nonce == new.nonce),
+        };
+        match replaced {
+            Some(index) => {
+                if !is_fee_bump(&siblings[index].tx, &new.tx) {
+                    return Err(anyhow!("Replacement transaction underpriced!"));
+                }
+            }
+            None => {
+                let pending_nonce = match new.custom {
+                    true => self.next_custom_nonce(chain, new.sender)?,
+                    false => self.next_eth_nonce(chain, new.sender)?,
+                };
+                check_nonce(new.sender, pending_nonce, new.nonce)?;
+                if siblings.len() >= MAX_QUEUED_TXS_PER_SENDER {
+                    return Err(anyhow!("Too many queued transactions from this sender!"));
+                }
+            }
+        }
+
+        // Everything the sender has queued has to be affordable at once
+        let committed = siblings
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != replaced)
+            .fold(U256::ZERO, |cost, (_, queued)| {
+                cost + queued.max_cost(base_fee)
+            });
+        if chain.get_balance(Token::Native, new.sender)? < committed + new.max_cost(base_fee) {
+            return Err(anyhow!("Insufficient balance to pay for gas!"));
+        }
+
+        let hash = new.hash;
+        match replaced {
+            Some(_) => {
+                let position = self
+                    .queue
+                    .iter()
+                    .position(|queued| queued.is_sibling(&new) && queued.nonce == new.nonce)
+                    .ok_or(anyhow!("Inconsistency!"))?;
+                self.queue[position] = new;
+            }
+            None => {
+                if self.queue.len() >= MAX_QUEUED_TXS {
+                    self.evict(&new, base_fee)?;
+                }
+                self.queue.push_back(new);
+            }
+        }
+        self.announce(hash);
+        Ok(hash)
+    }
+
+    /// Makes room for `new` by dropping the cheapest transaction that is the
+    /// last one of its sender, so no sender is left with a nonce gap
+    fn evict(&mut self, new: &QueuedTransaction, base_fee: u64) -> Result<()> {
+        let cheapest = (0..self.queue.len())
+            .filter(|index| {
+                let queued = &self.queue[*index];
+                !self
+                    .queue
+                    .iter()
+                    .any(|other| other.is_sibling(queued) && other.nonce > queued.nonce)
+            })
+            .min_by_key(|index| self.queue[*index].gas_price(base_fee));
+        match cheapest {
+            Some(index) if self.queue[index].gas_price(base_fee) < new.gas_price(base_fee) => {
+                let evicted = self.queue.remove(index).ok_or(anyhow!("Inconsistency!"))?;
+                log::info!("Evicted transaction {} from the queue", evicted.hash);
+                self.reject(evicted.tx, "Evicted by better paying transactions".into());
+                Ok(())
+            }
+            _ => Err(anyhow!("Transaction queue is full!")),
+        }
+    }
+
+    /// Takes a transaction out of the queue
+    pub fn remove(&mut self, hash: FixedBytes<32>) -> Option<OwshenTransaction> {
+        let position = self.queue.iter().position(|queued| queued.hash == hash)?;
+        self.queue.remove(position).map(|queued| queued.tx)
+    }
+
+    /// Queued transactions of each sender in nonce order, the senders in the
+    /// order their first transaction arrived
+    pub fn by_sender(&self) -> Vec<VecDeque<OwshenTransaction>> {
+        let mut senders = HashMap::new();
+        let mut lanes: Vec<Vec<&QueuedTransaction>> = Vec::new();
+        for queued in self.queue.iter() {
+            let lane = *senders
+                .entry((queued.sender, queued.custom))
+                .or_insert_with(|| {
+                    lanes.push(Vec::new());
+                    lanes.len() - 1
+                });
+            lanes[lane].push(queued);
+        }
+        lanes
+            .into_iter()
+            .map(|mut lane| {
+                lane.sort_by_key(|queued| queued.nonce);
+                lane.into_iter().map(|queued| queued.tx.clone()).collect()
+            })
+            .collect()
+    }
+
+    /// Queued Ethereum transactions by sender and nonce
+    pub fn eth_by_sender(&self) -> BTreeMap<Address, BTreeMap<U256, TxEnvelope>> {
+        let mut senders: BTreeMap<Address, BTreeMap<U256, TxEnvelope>> = BTreeMap::new();
+        for queued in self.queue.iter() {
+            if let OwshenTransaction::Eth(eth_tx) = &queued.tx {
+                senders
+                    .entry(queued.sender)
+                    .or_default()
+                    .insert(queued.nonce, eth_tx.clone());
+            }
+        }
+        senders
+    }
+
+    /// Queued transactions, oldest first
+    pub fn queue(&self) -> Vec<OwshenTransaction> {
+        self.queue.iter().map(|queued| queued.tx.clone()).collect()
+    }
+
+    pub fn contains(&self, hash: FixedBytes<32>) -> bool {
+        self.queue.iter().any(|queued| queued.hash == hash)
+    }
+
+    /// Remembers a transaction that failed to get into a block, along with the reason
+    pub fn reject(&mut self, tx: OwshenTransaction, reason: String) {
+        if self.rejected.len() == MAX_REJECTED_TXS {
+            self.rejected.pop_front();
+        }
+        self.rejected.push_back((tx, reason));
+    }
+
+    pub fn rejected(&self) -> &VecDeque<(OwshenTransaction, String)> {
+        &self.rejected
+    }
+
+    fn queued_count(&self, address: Address, custom: bool) -> usize {
+        self.queue
+            .iter()
+            .filter(|queued| queued.sender == address && queued.custom == custom)
+            .count()
+    }
+
+    /// Next eth nonce of `address`, counting its transactions still waiting in the queue
+    pub fn next_eth_nonce<B: Blockchain>(&self, chain: &B, address: Address) -> Result<U256> {
+        Ok(chain.get_eth_nonce(address)? + U256::from(self.queued_count(address, false)))
+    }
+
+    /// Next custom nonce of `address`, counting its transactions still waiting in the queue
+    pub fn next_custom_nonce<B: Blockchain>(&self, chain: &B, address: Address) -> Result<U256> {
+        Ok(chain.get_custom_nonce(address)? + U256::from(self.queued_count(address, true)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::{tx::NonceError, Config, Owshenchain};
+    use crate::db::{Key, KvStore, RamKvStore, Value};
+    use alloy::consensus::{TxEip1559, TypedTransaction};
+    use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+    use alloy::primitives::TxKind;
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn chain() -> Owshenchain<RamKvStore> {
+        let conf = Config {
+            networks: vec![],
+            ..Config::for_tests()
+        };
+        Owshenchain::new(conf, RamKvStore::new())
+    }
+
+    fn fund(chain: &mut Owshenchain<RamKvStore>, signer: &PrivateKeySigner, amount: u128) {
+        chain
+            .db
+            .put(
+                Key::Balance(signer.address(), Token::Native),
+                Some(Value::U256(U256::from(amount))),
+            )
+            .unwrap();
+    }
+
+    async fn transfer(
+        signer: &PrivateKeySigner,
+        chain_id: u64,
+        nonce: u64,
+        value: u128,
+        max_fee_per_gas: u128,
+    ) -> OwshenTransaction {
+        let tx = TxEip1559 {
+            chain_id,
+            nonce,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::from([6; 20])),
+            value: U256::from(value),
+            max_priority_fee_per_gas: max_fee_per_gas,
+            max_fee_per_gas,
+            ..Default::default()
+        };
+        let wallet = EthereumWallet::new(signer.clone());
+        OwshenTransaction::Eth(
+            <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+                &wallet,
+                TypedTransaction::Eip1559(tx),
+            )
+            .await
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_add() {
+        let mut chain = chain();
+        let signer = PrivateKeySigner::random();
+        let gas_price = 2_000_000_000;
+        let gas_cost = 21_000 * gas_price;
+        fund(&mut chain, &signer, 2 * gas_cost + 100);
+        let mut queue = TransactionQueue::new();
+
+        let tx = transfer(&signer, 1387, 0, 100, gas_price).await;
+        let hash = queue.add(&chain, tx.clone()).unwrap();
+        assert_eq!(hash, tx.hash().unwrap());
+        assert_eq!(
+            queue.add(&chain, tx).unwrap_err().to_string(),
+            "Transaction already known!"
+        );
+
+        let err = queue
+            .add(&chain, transfer(&signer, 1387, 2, 0, gas_price).await)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NonceError>(),
+            Some(NonceError::TooHigh { .. })
+        ));
+        assert!(queue
+            .add(&chain, transfer(&signer, 1, 1, 0, gas_price).await)
+            .is_err());
+        assert!(queue
+            .add(&chain, transfer(&signer, 1387, 1, 0, 100).await)
+            .is_err());
+
+        // The fees of both queued transactions have to be covered
+        assert_eq!(
+            queue
+                .add(&chain, transfer(&signer, 1387, 1, 1, gas_price).await)
+                .unwrap_err()
+                .to_string(),
+            "Insufficient balance to pay for gas!"
+        );
+
+        // A replacement has to pay at least 10% more
+        assert_eq!(
+            queue
+                .add(&chain, transfer(&signer, 1387, 0, 0, gas_price + 1).await)
+                .unwrap_err()
+                .to_string(),
+            "Replacement transaction underpriced!"
+        );
+        let replacement = transfer(&signer, 1387, 0, 0, gas_price * 11 / 10).await;
+        queue.add(&chain, replacement.clone()).unwrap();
+        assert_eq!(queue.queue(), vec![replacement]);
+        assert_eq!(
+            queue.next_eth_nonce(&chain, signer.address()).unwrap(),
+            U256::from(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_evict() {
+        let mut chain = chain();
+        let (alice, bob) = (PrivateKeySigner::random(), PrivateKeySigner::random());
+        fund(&mut chain, &alice, u64::MAX as u128);
+        fund(&mut chain, &bob, u64::MAX as u128);
+        let mut queue = TransactionQueue::new();
+        let base_fee = chain.pending_base_fee().unwrap();
+
+        let cheap = transfer(&alice, 1387, 0, 0, 1_000_000_000).await;
+        let cheap_tail = transfer(&alice, 1387, 1, 0, 1_500_000_000).await;
+        let expensive = transfer(&bob, 1387, 0, 0, 3_000_000_000).await;
+        for tx in [&cheap, &cheap_tail, &expensive] {
+            queue.add(&chain, tx.clone()).unwrap();
+        }
+
+        // Only the last transaction of a sender can go, the cheaper one
+        // before it would be left without a successor otherwise
+        let new = QueuedTransaction::new(transfer(&bob, 1387, 1, 0, 2_000_000_000).await).unwrap();
+        queue.evict(&new, base_fee).unwrap();
+        assert_eq!(queue.queue(), vec![cheap.clone(), expensive.clone()]);
+        assert_eq!(queue.rejected()[0].0, cheap_tail);
+
+        let new = QueuedTransaction::new(transfer(&bob, 1387, 1, 0, 900_000_000).await).unwrap();
+        assert_eq!(
+            queue.evict(&new, base_fee).unwrap_err().to_string(),
+            "Transaction queue is full!"
+        );
+        assert_eq!(queue.queue(), vec![cheap, expensive]);
+    }
+}
@@ -0,0 +1,127 @@
+use alloy::primitives::FixedBytes;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::state::hash_pair;
+use crate::types::{Block, Receipt};
+
+/// Binary Merkle tree over an ordered list, padded with zero leaves up to a
+/// power of two. Used for the transactions and receipts roots of blocks.
+fn levels(leaves: &[FixedBytes<32>]) -> Vec<Vec<FixedBytes<32>>> {
+    let mut level = leaves.to_vec();
+    level.resize(leaves.len().next_power_of_two(), FixedBytes::ZERO);
+    let mut levels = vec![level];
+    while levels[levels.len() - 1].len() > 1 {
+        let parents = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| hash_pair(pair[0], pair[1]))
+            .collect();
+        levels.push(parents);
+    }
+    levels
+}
+
+pub fn merkle_root(leaves: &[FixedBytes<32>]) -> FixedBytes<32> {
+    if leaves.is_empty() {
+        return FixedBytes::ZERO;
+    }
+    levels(leaves).last().unwrap()[0]
+}
+
+/// Siblings of the `index`th leaf, from the bottom of the tree up
+pub fn merkle_proof(leaves: &[FixedBytes<32>], mut index: usize) -> Vec<FixedBytes<32>> {
+    let levels = levels(leaves);
+    let mut proof = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        proof.push(level[index ^ 1]);
+        index /= 2;
+    }
+    proof
+}
+
+pub fn verify_merkle_proof(
+    root: FixedBytes<32>,
+    leaf: FixedBytes<32>,
+    mut index: usize,
+    proof: &[FixedBytes<32>],
+) -> bool {
+    let mut hash = leaf;
+    for sibling in proof {
+        hash = if index & 1 == 0 {
+            hash_pair(hash, *sibling)
+        } else {
+            hash_pair(*sibling, hash)
+        };
+        index /= 2;
+    }
+    index == 0 && hash == root
+}
+
+/// Proof that a transaction and its receipt are part of a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub block_number: usize,
+    pub block_hash: FixedBytes<32>,
+    pub transaction_index: usize,
+    pub transactions_root: FixedBytes<32>,
+    pub transaction_proof: Vec<FixedBytes<32>>,
+    pub receipt: Receipt,
+    pub receipts_root: FixedBytes<32>,
+    pub receipt_proof: Vec<FixedBytes<32>>,
+}
+
+impl InclusionProof {
+    /// Checks the proof against a block header the caller already trusts
+    pub fn verify(&self, tx_hash: FixedBytes<32>, block: &Block) -> Result<bool> {
+        Ok(block.hash()? == self.block_hash
+            && block.transactions_root == self.transactions_root
+            && block.receipts_root == self.receipts_root
+            && self.receipt.tx_hash == tx_hash
+            && verify_merkle_proof(
+                self.transactions_root,
+                tx_hash,
+                self.transaction_index,
+                &self.transaction_proof,
+            )
+            && verify_merkle_proof(
+                self.receipts_root,
+                self.receipt.hash()?,
+                self.transaction_index,
+                &self.receipt_proof,
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::keccak256;
+
+    #[test]
+    fn test_merkle_proof() {
+        assert_eq!(merkle_root(&[]), FixedBytes::ZERO);
+        let single = keccak256([0]);
+        assert_eq!(merkle_root(&[single]), single);
+        assert!(verify_merkle_proof(
+            single,
+            single,
+            0,
+            &merkle_proof(&[single], 0)
+        ));
+
+        let leaves = (0..5u8).map(|i| keccak256([i])).collect::<Vec<_>>();
+        let root = merkle_root(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle_proof(&leaves, index);
+            assert_eq!(proof.len(), 3);
+            assert!(verify_merkle_proof(root, *leaf, index, &proof));
+            assert!(!verify_merkle_proof(root, *leaf, index ^ 1, &proof));
+        }
+        assert!(!verify_merkle_proof(
+            root,
+            keccak256([9]),
+            0,
+            &merkle_proof(&leaves, 0)
+        ));
+    }
+}
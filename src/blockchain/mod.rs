@@ -1,23 +1,43 @@
-use std::collections::VecDeque;
+use std::cmp::Reverse;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::db::{Key, KvStore, MirrorKvStore, Value};
 use crate::services::ContextKvStore;
 use crate::types::{
-    BincodableOwshenTransaction, Block, CustomTxMsg, IncludedTransaction, OwshenTransaction, Token,
+    BincodableOwshenTransaction, Block, CustomTxMsg, IncludedTransaction, OwshenTransaction,
+    Receipt, Token,
 };
 
-use alloy::primitives::{Address, FixedBytes, U256};
+use alloy::primitives::{Address, FixedBytes, TxKind, U256};
 use anyhow::{anyhow, Result};
 
 mod config;
 pub use config::Config;
+pub mod events;
+pub mod gas;
+use gas::{
+    next_base_fee, BlockLimits, BLOCK_GAS_LIMIT, CUSTOM_TX_GAS, INITIAL_BASE_FEE, MAX_BLOCK_SIZE,
+};
 mod ovm;
+pub use ovm::BlockContext;
+use ovm::{Ovm, TxContext};
+pub mod logs;
+use logs::{LogEntry, LogFilter};
+pub mod mempool;
+pub use mempool::TransactionQueue;
+use mempool::{effective_tip, encoded_size, gas_limit};
+pub mod merkle;
+use merkle::InclusionProof;
+pub mod state;
+use state::StateProof;
 pub mod tx;
+use tx::TxOutcome;
 
 pub trait Blockchain {
     fn config(&self) -> &Config;
+    fn block_limits(&self) -> BlockLimits;
     fn get_block(&self, index: usize) -> Result<Block>;
+    fn get_block_by_hash(&self, hash: FixedBytes<32>) -> Result<Block>;
     fn get_last_block(&self) -> Result<Option<Block>>;
     fn get_height(&self) -> Result<usize>;
     fn get_balance(&self, token: Token, address: Address) -> Result<U256>;
@@ -44,45 +64,67 @@ pub trait Blockchain {
     fn get_blocks(&self, offset: usize, limit: usize) -> Result<Vec<Block>>;
     fn get_token_decimal(&self, token_address: Address) -> Result<U256>;
     fn get_token_symbol(&self, token_address: Address) -> Result<String>;
+    fn get_code(&self, address: Address) -> Result<Vec<u8>>;
+    fn call_contract(&self, caller: Address, to: Address, input: Vec<u8>) -> Result<Vec<u8>>;
+    fn estimate_gas(&self, from: Address, to: TxKind, value: U256, input: Vec<u8>) -> Result<u64>;
+    fn get_gas_used(&self, tx_hash: FixedBytes<32>) -> Result<u64>;
+    fn get_receipt(&self, tx_hash: FixedBytes<32>) -> Result<Receipt>;
+    fn pending_base_fee(&self) -> Result<u64>;
+    fn get_state_root(&self) -> Result<FixedBytes<32>>;
+    fn get_state_proof(&self, key: Key) -> Result<(Option<Value>, StateProof)>;
+    fn get_block_receipts(&self, index: usize) -> Result<Vec<Receipt>>;
+    fn get_inclusion_proof(&self, tx_hash: FixedBytes<32>) -> Result<InclusionProof>;
+    fn get_logs(&self, filter: &LogFilter) -> Result<Vec<LogEntry>>;
 }
 
 #[derive(Debug, Clone)]
 pub struct Owshenchain<K: ContextKvStore> {
     config: Config,
+    block_limits: BlockLimits,
     pub db: K,
 }
-#[derive(Debug, Clone)]
-pub struct TransactionQueue {
-    queue: VecDeque<OwshenTransaction>,
+
+/// A fork with the transactions of a block applied, along with their receipts
+type FilledBlock<'a, K> = (
+    Owshenchain<MirrorKvStore<'a, K>>,
+    Vec<OwshenTransaction>,
+    Vec<Receipt>,
+);
+
+/// Roots of the ordered transaction hashes and receipts of a block
+fn block_roots(
+    tx_hashes: &[FixedBytes<32>],
+    receipts: &[Receipt],
+) -> Result<(FixedBytes<32>, FixedBytes<32>)> {
+    let receipt_hashes = receipts
+        .iter()
+        .map(|receipt| receipt.hash())
+        .collect::<Result<Vec<_>>>()?;
+    Ok((
+        merkle::merkle_root(tx_hashes),
+        merkle::merkle_root(&receipt_hashes),
+    ))
 }
 
-impl TransactionQueue {
-    pub fn new() -> Self {
+impl<K: ContextKvStore> Owshenchain<K> {
+    pub fn new(config: Config, db: K) -> Self {
         Self {
-            queue: VecDeque::new(),
+            config,
+            block_limits: BlockLimits::default(),
+            db,
         }
     }
 
-    pub fn enqueue(&mut self, tx: OwshenTransaction) {
-        self.queue.push_back(tx);
-    }
-
-    pub fn dequeue(&mut self) -> Option<OwshenTransaction> {
-        self.queue.pop_front()
+    /// Produces blocks holding no more than `block_limits`
+    pub fn with_block_limits(mut self, block_limits: BlockLimits) -> Self {
+        self.block_limits = block_limits;
+        self
     }
 
-    pub fn queue(&self) -> &VecDeque<OwshenTransaction> {
-        &self.queue
-    }
-}
-
-impl<K: ContextKvStore> Owshenchain<K> {
-    pub fn new(config: Config, db: K) -> Self {
-        Self { config, db }
-    }
     fn fork<'a>(&'a self) -> Owshenchain<MirrorKvStore<'a, K>> {
         Owshenchain {
             config: self.config.clone(),
+            block_limits: self.block_limits,
             db: MirrorKvStore::new(&self.db),
         }
     }
@@ -98,7 +140,111 @@ impl<K: ContextKvStore> Owshenchain<K> {
         Ok(ret)
     }
 
-    fn apply_tx(&mut self, tx: &OwshenTransaction) -> Result<()> {
+    /// Applies the queued transactions the next block takes to a fork of the
+    /// chain and takes them out of `txs`, rejecting the invalid ones
+    fn fill_block(
+        &self,
+        txs: &mut TransactionQueue,
+        block_ctx: &BlockContext,
+    ) -> Result<FilledBlock<'_, K>> {
+        let mut selected_txs = Vec::new();
+        let mut fork = self.fork();
+        if block_ctx.number == 0 {
+            fork.commit_genesis()?;
+        }
+        let base_fee = block_ctx.base_fee;
+        let mut gas_used = 0;
+        let mut receipts = Vec::new();
+        let mut size = 0;
+
+        // Each round takes the best paying of the next transactions of every
+        // sender, so that nonces stay in order. Ties go to the earliest sender.
+        let mut lanes = txs.by_sender();
+        while let Some(lane) = lanes
+            .iter()
+            .enumerate()
+            .filter_map(|(lane, lane_txs)| Some((lane, effective_tip(lane_txs.front()?, base_fee))))
+            .max_by_key(|(lane, tip)| (*tip, Reverse(*lane)))
+            .map(|(lane, _)| lane)
+        {
+            let tx = lanes[lane].pop_front().ok_or(anyhow!("Inconsistency!"))?;
+            let tx_size = encoded_size(&tx)?;
+            if gas_used + gas_limit(&tx) > self.block_limits.gas
+                || size + tx_size > self.block_limits.size
+            {
+                // Left in the queue for the next block, along with the rest of
+                // its sender's transactions
+                lanes[lane].clear();
+                continue;
+            }
+            txs.remove(tx.hash()?);
+            match fork.apply_tx(&tx, block_ctx) {
+                Ok(outcome) => {
+                    if let Some(e) = &outcome.error {
+                        log::info!("Transaction {} failed: {}", tx.hash()?, e);
+                    }
+                    gas_used += outcome.gas_used;
+                    size += tx_size;
+                    receipts.push(Receipt {
+                        tx_hash: tx.hash()?,
+                        success: outcome.is_success(),
+                        gas_used: outcome.gas_used,
+                        cumulative_gas_used: gas_used,
+                        logs: outcome.logs,
+                    });
+                    selected_txs.push(tx)
+                }
+                Err(e) => {
+                    log::info!("Transaction {} rejected: {}", tx.hash()?, e);
+                    txs.reject(tx, e.to_string());
+                    // The rest of the sender's transactions would fail on their
+                    // nonces, they stay queued instead
+                    lanes[lane].clear();
+                }
+            }
+        }
+
+        Ok((fork, selected_txs, receipts))
+    }
+
+    /// The chain as it would be with the next block drafted from `txs`, for
+    /// reads of the `pending` block
+    pub fn pending_state(
+        &self,
+        txs: &TransactionQueue,
+    ) -> Result<Owshenchain<MirrorKvStore<'_, K>>> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let block_ctx = self.block_context(self.get_height()?, timestamp, self.pending_base_fee()?);
+        let (fork, _, _) = self.fill_block(&mut txs.clone(), &block_ctx)?;
+        Ok(fork)
+    }
+
+    /// Stores the genesis balances, so that the first block commits to them in
+    /// its state root like to any other balance
+    fn commit_genesis(&mut self) -> Result<()> {
+        for (token, balances) in self.config.genesis.tokens.clone() {
+            for (address, balance) in balances {
+                let key = Key::Balance(address, token.clone());
+                if self.db.get(key.clone())?.is_none() {
+                    self.db.put(key, Some(Value::U256(balance)))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn block_context(&self, index: usize, timestamp: u64, base_fee: u64) -> BlockContext {
+        BlockContext {
+            number: index as u64,
+            timestamp,
+            coinbase: self.config.owner.unwrap_or_default(),
+            base_fee,
+        }
+    }
+
+    /// Applies `tx` on top of the current state. Invalid transactions are
+    /// refused, failing Ethereum transactions come back with their error.
+    fn apply_tx(&mut self, tx: &OwshenTransaction, block: &BlockContext) -> Result<TxOutcome> {
         self.atomic(|chain| {
             let from = tx.signer()?;
 
@@ -107,34 +253,52 @@ impl<K: ContextKvStore> Owshenchain<K> {
             }
 
             match tx {
-                OwshenTransaction::Custom(custom_tx) => match custom_tx.msg()? {
-                    // CustomTxMsg::OwshenAirdrop {
-                    //     owshen_address,
-                    //     owshen_sig,
-                    // } => {
-                    //     log::info!("Someone is claiming his owshen airdrop, by {}!", from);
-                    // }
-                    CustomTxMsg::MintTx(mint_data) => {
-                        tx::mint_tx(
-                            chain,
-                            mint_data.tx_hash.to_vec(),
-                            mint_data.user_tx_hash,
-                            mint_data.token,
-                            mint_data.amount,
-                            mint_data.address,
-                        )?;
-                        log::info!("Mint transaction, by {}!", from);
-                    }
-                    CustomTxMsg::BurnTx(burn_data) => {
-                        tx::burn_tx(chain, burn_data)?;
-                        log::info!("Burn transaction, by {}!", from);
-                    }
-                },
-                OwshenTransaction::Eth(eth_tx) => {
-                    tx::eth(chain, from, eth_tx)?;
+                OwshenTransaction::Custom(custom_tx) => {
+                    let msg = custom_tx.msg()?;
+                    let nonce = chain.get_custom_nonce(from)?;
+                    tx::check_nonce(from, nonce, msg.nonce())?;
+
+                    let log = match msg {
+                        // CustomTxMsg::OwshenAirdrop {
+                        //     owshen_address,
+                        //     owshen_sig,
+                        // } => {
+                        //     log::info!("Someone is claiming his owshen airdrop, by {}!", from);
+                        // }
+                        CustomTxMsg::MintTx(mint_data) => {
+                            tx::check_minter(chain.config(), from)?;
+                            tx::mint_tx(
+                                chain,
+                                tx.hash()?,
+                                mint_data.tx_hash.to_vec(),
+                                mint_data.user_tx_hash,
+                                mint_data.token.clone(),
+                                mint_data.amount,
+                                mint_data.address,
+                            )?;
+                            log::info!("Mint transaction, by {}!", from);
+                            tx::mint_log(&mint_data.token, mint_data.address, mint_data.amount)
+                        }
+                        CustomTxMsg::BurnTx(burn_data) => {
+                            let log = tx::burn_log(from, &burn_data);
+                            tx::burn_tx(chain, tx.hash()?, from, burn_data)?;
+                            log::info!("Burn transaction, by {}!", from);
+                            log
+                        }
+                    };
+
+                    chain.db.put(
+                        Key::NonceCustom(from),
+                        Some(Value::U256(nonce + U256::from(1))),
+                    )?;
+                    Ok(TxOutcome {
+                        gas_used: CUSTOM_TX_GAS,
+                        logs: vec![log],
+                        error: None,
+                    })
                 }
+                OwshenTransaction::Eth(eth_tx) => tx::eth(chain, from, eth_tx, block),
             }
-            Ok(())
         })
     }
 
@@ -150,6 +314,9 @@ impl<K: ContextKvStore> Blockchain for Owshenchain<K> {
     fn config(&self) -> &Config {
         &self.config
     }
+    fn block_limits(&self) -> BlockLimits {
+        self.block_limits
+    }
     fn get_block(&self, index: usize) -> Result<Block> {
         if index >= self.get_height()? {
             Err(anyhow!("Block doesn't exist!"))
@@ -159,6 +326,12 @@ impl<K: ContextKvStore> Blockchain for Owshenchain<K> {
             Err(anyhow!("Inconsistency!"))
         }
     }
+    fn get_block_by_hash(&self, hash: FixedBytes<32>) -> Result<Block> {
+        self.db
+            .get(Key::BlockHash(U256::from_be_bytes(hash.into())))?
+            .ok_or(anyhow!("Block doesn't exist!"))?
+            .as_block()
+    }
     fn get_last_block(&self) -> Result<Option<Block>> {
         let height = self.get_height()?;
         if height > 0 {
@@ -242,9 +415,45 @@ impl<K: ContextKvStore> Blockchain for Owshenchain<K> {
                 .db
                 .put(Key::TransactionCount, Some(Value::U256(new_tx_count)))?;
 
+            if block.base_fee_per_gas != chain.pending_base_fee()? {
+                return Err(anyhow!("Bad base fee!"));
+            }
+
+            let size = block
+                .txs
+                .iter()
+                .map(bincode::serialized_size)
+                .sum::<Result<u64, _>>()?;
+            if size > MAX_BLOCK_SIZE {
+                return Err(anyhow!("Block is too large!"));
+            }
+
+            if block.index == 0 {
+                chain.commit_genesis()?;
+            }
+
+            let block_ctx =
+                chain.block_context(block.index, block.timestamp, block.base_fee_per_gas);
+            let mut block_gas_used = 0;
+            let mut tx_hashes = Vec::new();
+            let mut receipts = Vec::new();
             for (ind, bin_tx) in block.txs.iter().enumerate() {
                 let tx = bin_tx.try_into()?;
-                chain.apply_tx(&tx)?;
+                let outcome = chain.apply_tx(&tx, &block_ctx)?;
+                block_gas_used += outcome.gas_used;
+                let receipt = Receipt {
+                    tx_hash: tx.hash()?,
+                    success: outcome.is_success(),
+                    gas_used: outcome.gas_used,
+                    cumulative_gas_used: block_gas_used,
+                    logs: outcome.logs,
+                };
+                chain.db.put(
+                    Key::Receipt(tx.hash()?),
+                    Some(Value::Receipt(receipt.clone())),
+                )?;
+                tx_hashes.push(tx.hash()?);
+                receipts.push(receipt);
                 chain.db.put(
                     Key::TransactionHash(tx.hash()?),
                     Some(Value::Transaction(IncludedTransaction {
@@ -275,6 +484,26 @@ impl<K: ContextKvStore> Blockchain for Owshenchain<K> {
                 chain.db.put(key, Some(Value::Transactions(transactions)))?;
             }
 
+            if block.gas_used != block_gas_used {
+                return Err(anyhow!("Bad block gas used!"));
+            }
+            if block_gas_used > BLOCK_GAS_LIMIT {
+                return Err(anyhow!("Block gas limit exceeded!"));
+            }
+
+            if block.state_root != state::update_state_root(&mut chain.db)? {
+                return Err(anyhow!("Bad state root!"));
+            }
+
+            let (transactions_root, receipts_root) = block_roots(&tx_hashes, &receipts)?;
+            if block.transactions_root != transactions_root {
+                return Err(anyhow!("Bad transactions root!"));
+            }
+            if block.receipts_root != receipts_root {
+                return Err(anyhow!("Bad receipts root!"));
+            }
+            logs::index_logs(&mut chain.db, block.index, &receipts)?;
+
             let _ = chain.store_block_hash(block.clone());
             chain.db.put(Key::Height, Some(Value::Usize(height + 1)))?;
             chain
@@ -314,16 +543,20 @@ impl<K: ContextKvStore> Blockchain for Owshenchain<K> {
         })
     }
     fn draft_block(&self, txs: &mut TransactionQueue, timestamp: u64) -> Result<Block> {
-        let mut selected_txs = Vec::new();
-        let mut fork = self.fork();
+        let base_fee = self.pending_base_fee()?;
+        let block_ctx = self.block_context(self.get_height()?, timestamp, base_fee);
+        let (mut fork, selected_txs, receipts) = self.fill_block(txs, &block_ctx)?;
+        let gas_used = receipts
+            .last()
+            .map(|receipt| receipt.cumulative_gas_used)
+            .unwrap_or_default();
+        let tx_hashes = receipts
+            .iter()
+            .map(|receipt| receipt.tx_hash)
+            .collect::<Vec<_>>();
 
-        while let Some(tx) = txs.dequeue() {
-            if fork.apply_tx(&tx).is_ok() {
-                selected_txs.push(tx)
-            } else {
-                //pass
-            }
-        }
+        let state_root = state::update_state_root(&mut fork.db)?;
+        let (transactions_root, receipts_root) = block_roots(&tx_hashes, &receipts)?;
 
         let prev_hash = if let Some(b) = self.get_last_block()? {
             Some(b.hash()?)
@@ -340,6 +573,11 @@ impl<K: ContextKvStore> Blockchain for Owshenchain<K> {
                 .collect::<Result<Vec<_>, _>>()?,
             sig: None,
             timestamp,
+            base_fee_per_gas: base_fee,
+            gas_used,
+            state_root,
+            transactions_root,
+            receipts_root,
         };
 
         Ok(blk)
@@ -474,6 +712,174 @@ impl<K: ContextKvStore> Blockchain for Owshenchain<K> {
             Ok(String::from("Unknown"))
         }
     }
+    fn get_code(&self, address: Address) -> Result<Vec<u8>> {
+        if let Some(v) = self.db.get(Key::ContractCode(address))? {
+            v.as_vec_u8()
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    /// Runs a contract call on top of the current state, without committing it
+    fn call_contract(&self, caller: Address, to: Address, input: Vec<u8>) -> Result<Vec<u8>> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let block = self.block_context(self.get_height()?, timestamp, self.pending_base_fee()?);
+        let mut fork = self.fork();
+        let mut ovm = Ovm::new(
+            &mut fork,
+            block,
+            TxContext {
+                origin: caller,
+                gas_price: U256::ZERO,
+                gas_limit: BLOCK_GAS_LIMIT,
+                access_list: Default::default(),
+            },
+        );
+        ovm.execute(caller, to, U256::ZERO, input)?.result
+    }
+    /// Smallest gas limit the message succeeds with, fees are not charged
+    fn estimate_gas(&self, from: Address, to: TxKind, value: U256, input: Vec<u8>) -> Result<u64> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let block = self.block_context(self.get_height()?, timestamp, self.pending_base_fee()?);
+        let run = |gas_limit: u64| {
+            let msg = tx::EthMessage {
+                to,
+                value,
+                input: input.clone(),
+                gas_limit,
+                gas_price: U256::ZERO,
+                access_list: Default::default(),
+            };
+            let outcome = tx::execute_message(&mut self.fork(), from, &msg, &block)?;
+            match outcome.error {
+                Some(e) => Err(anyhow!(e)),
+                None => Ok(outcome.gas_used),
+            }
+        };
+
+        // Calls only get 63/64 of the gas left and refunds are paid back at
+        // the end, so a limit of exactly the gas used may not be enough
+        let gas_used = run(BLOCK_GAS_LIMIT)?;
+        let (mut low, mut high) = (gas_used, BLOCK_GAS_LIMIT);
+        if run(low).is_ok() {
+            return Ok(low);
+        }
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if run(mid).is_ok() {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Ok(high)
+    }
+    fn get_gas_used(&self, tx_hash: FixedBytes<32>) -> Result<u64> {
+        Ok(self.get_receipt(tx_hash)?.gas_used)
+    }
+    fn get_receipt(&self, tx_hash: FixedBytes<32>) -> Result<Receipt> {
+        match self.db.get(Key::Receipt(tx_hash))? {
+            Some(v) => v.as_receipt(),
+            None => Err(anyhow!("Transaction not found!")),
+        }
+    }
+    /// Base fee of the next block, set by the gas usage of the last one
+    fn pending_base_fee(&self) -> Result<u64> {
+        Ok(match self.get_last_block()? {
+            Some(block) => next_base_fee(block.base_fee_per_gas, block.gas_used),
+            None => INITIAL_BASE_FEE,
+        })
+    }
+    fn get_state_root(&self) -> Result<FixedBytes<32>> {
+        state::state_root(&self.db)
+    }
+    /// The entry as stored, along with its proof against the current state root
+    fn get_state_proof(&self, key: Key) -> Result<(Option<Value>, StateProof)> {
+        if !state::is_state_key(&key) {
+            return Err(anyhow!("Not a state entry!"));
+        }
+        let proof = state::state_proof(&self.db, &key)?;
+        Ok((self.db.get(key)?, proof))
+    }
+    fn get_block_receipts(&self, index: usize) -> Result<Vec<Receipt>> {
+        self.get_transactions_by_block(index)?
+            .iter()
+            .map(|tx| self.get_receipt(tx.hash()?))
+            .collect()
+    }
+    fn get_inclusion_proof(&self, tx_hash: FixedBytes<32>) -> Result<InclusionProof> {
+        let included = self.get_transaction_by_hash(tx_hash)?;
+        let block = self.get_block(included.block_number)?;
+        let index = included.transaction_index;
+        let tx_hashes = self
+            .get_transactions_by_block(block.index)?
+            .iter()
+            .map(|tx| tx.hash())
+            .collect::<Result<Vec<_>>>()?;
+        let receipts = self.get_block_receipts(block.index)?;
+        let receipt_hashes = receipts
+            .iter()
+            .map(|receipt| receipt.hash())
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(InclusionProof {
+            block_number: block.index,
+            block_hash: block.hash()?,
+            transaction_index: index,
+            transactions_root: block.transactions_root,
+            transaction_proof: merkle::merkle_proof(&tx_hashes, index),
+            receipt: receipts[index].clone(),
+            receipts_root: block.receipts_root,
+            receipt_proof: merkle::merkle_proof(&receipt_hashes, index),
+        })
+    }
+    fn get_logs(&self, filter: &LogFilter) -> Result<Vec<LogEntry>> {
+        let height = self.get_height()?;
+        if height == 0 || filter.from_block > filter.to_block || filter.from_block >= height {
+            return Ok(Vec::new());
+        }
+        let filter = LogFilter {
+            to_block: filter.to_block.min(height - 1),
+            ..filter.clone()
+        };
+        if filter.to_block - filter.from_block >= logs::MAX_LOG_BLOCK_RANGE {
+            return Err(anyhow!(
+                "Block range is limited to {} blocks!",
+                logs::MAX_LOG_BLOCK_RANGE
+            ));
+        }
+
+        let mut entries = Vec::new();
+        for block_number in logs::candidate_blocks(&self.db, &filter)? {
+            let block_hash = self.get_block(block_number)?.hash()?;
+            let mut log_index = 0;
+            for (transaction_index, receipt) in self
+                .get_block_receipts(block_number)?
+                .into_iter()
+                .enumerate()
+            {
+                for log in receipt.logs {
+                    if filter.matches(&log) {
+                        entries.push(LogEntry {
+                            log,
+                            block_number,
+                            block_hash,
+                            tx_hash: receipt.tx_hash,
+                            transaction_index,
+                            log_index,
+                        });
+                    }
+                    log_index += 1;
+                }
+            }
+            if entries.len() > logs::MAX_LOG_RESULTS {
+                return Err(anyhow!(
+                    "Query returned more than {} logs!",
+                    logs::MAX_LOG_RESULTS
+                ));
+            }
+        }
+        Ok(entries)
+    }
 }
 
 #[cfg(test)]
@@ -1,188 +1,807 @@
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    convert::Infallible,
+    rc::Rc,
+};
 
-use anyhow::Result;
+use alloy::{
+    primitives::{keccak256, Address},
+    rpc::types::AccessList,
+};
+use anyhow::{anyhow, Result};
 use evm::{
+    gasometer::{self, Gasometer, StorageTarget},
     Capture, Context, CreateScheme, ExitError, ExitReason, ExternalOperation, Handler, Machine,
-    Opcode, Stack, Transfer,
+    Opcode, Resolve, Runtime, Stack, Transfer,
 };
 use primitive_types::{H160, H256, U256};
 
 use crate::{
-    db::{Key, KvStore, Value},
+    db::{Blob, Key, KvStore, Value},
     services::ContextKvStore,
+    types::{Log, Token},
 };
 
-use super::Owshenchain;
+use super::{
+    gas::{intrinsic_gas, BLOCK_GAS_LIMIT, GAS_CONFIG},
+    Blockchain, Owshenchain,
+};
+
+/// The block a transaction is executed in
+#[derive(Debug, Clone, Default)]
+pub struct BlockContext {
+    pub number: u64,
+    pub timestamp: u64,
+    pub coinbase: Address,
+    pub base_fee: u64,
+}
+
+/// Outcome of a transaction run by the OVM. A revert or an execution error
+/// lands in `result`, the gas is used either way.
+pub struct Execution<T> {
+    pub result: Result<T>,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+}
+
+/// Environment of a single transaction
+#[derive(Debug, Clone, Default)]
+pub struct TxContext {
+    pub origin: Address,
+    pub gas_price: alloy::primitives::U256,
+    pub gas_limit: u64,
+    pub access_list: AccessList,
+}
+
+fn to_address(address: H160) -> Address {
+    Address::from(address.to_fixed_bytes())
+}
+
+fn to_h160(address: Address) -> H160 {
+    H160::from(address.into_array())
+}
+
+fn to_alloy_u256(value: U256) -> alloy::primitives::U256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    alloy::primitives::U256::from_be_bytes(bytes)
+}
+
+fn from_alloy_u256(value: alloy::primitives::U256) -> U256 {
+    U256::from_big_endian(&value.to_be_bytes::<32>())
+}
+
+/// All but one 64th of the available gas (EIP-150)
+fn l64(gas: u64) -> u64 {
+    gas - gas / 64
+}
 
 // Owshen Virtual Machine
 pub struct Ovm<'a, K: ContextKvStore> {
     error: RefCell<Option<anyhow::Error>>,
     chain: &'a mut Owshenchain<K>,
+    block: BlockContext,
+    tx: TxContext,
+    /// Writes of the calls in progress, innermost last. A call's writes are
+    /// merged into its parent when it succeeds and dropped when it fails.
+    checkpoints: Vec<BTreeMap<Blob, (Key, Option<Value>)>>,
+    /// Logs emitted so far, and how many there were when each call in
+    /// progress started, so that those of failed calls can be dropped
+    logs: Vec<Log>,
+    log_marks: Vec<usize>,
+    /// Gas of the transaction followed by the gas of each call in progress
+    gasometers: Vec<Gasometer<'static>>,
+    /// Addresses and storage slots already touched by the transaction (EIP-2929)
+    accessed: BTreeSet<(H160, Option<H256>)>,
+    is_static: bool,
 }
 
 impl<'a, K: ContextKvStore> Ovm<'a, K> {
-    pub fn new(chain: &'a mut Owshenchain<K>) -> Self {
+    pub fn new(chain: &'a mut Owshenchain<K>, block: BlockContext, tx: TxContext) -> Self {
         Self {
             chain,
             error: RefCell::new(None),
+            block,
+            tx,
+            checkpoints: Vec::new(),
+            logs: Vec::new(),
+            log_marks: Vec::new(),
+            gasometers: Vec::new(),
+            accessed: BTreeSet::new(),
+            is_static: false,
+        }
+    }
+
+    /// Deploys `init_code` on behalf of `caller`, resulting in the contract
+    /// address
+    pub fn deploy(
+        &mut self,
+        caller: Address,
+        value: alloy::primitives::U256,
+        init_code: Vec<u8>,
+    ) -> Result<Execution<Address>> {
+        self.start(&init_code, true)?;
+        let (reason, address, ret) = self.create_frame(
+            to_h160(caller),
+            CreateScheme::Legacy {
+                caller: to_h160(caller),
+            },
+            from_alloy_u256(value),
+            init_code,
+            None,
+            false,
+        );
+        let result = self.check(reason, &ret).and_then(|_| {
+            address
+                .map(to_address)
+                .ok_or(anyhow!("Contract was not deployed!"))
+        });
+        self.conclude(result)
+    }
+
+    /// Calls the contract at `to` on behalf of `caller`, resulting in its
+    /// output
+    pub fn execute(
+        &mut self,
+        caller: Address,
+        to: Address,
+        value: alloy::primitives::U256,
+        input: Vec<u8>,
+    ) -> Result<Execution<Vec<u8>>> {
+        self.start(&input, false)?;
+        self.accessed.insert((to_h160(to), None));
+        let value = from_alloy_u256(value);
+        let (reason, ret) = self.call_frame(
+            to_h160(to),
+            Some(Transfer {
+                source: to_h160(caller),
+                target: to_h160(to),
+                value,
+            }),
+            input,
+            None,
+            false,
+            Context {
+                address: to_h160(to),
+                caller: to_h160(caller),
+                apparent_value: value,
+            },
+            false,
+        );
+        let result = self.check(reason, &ret).map(|_| ret);
+        self.conclude(result)
+    }
+
+    /// Charges the intrinsic gas and warms up the accounts every transaction
+    /// starts with
+    fn start(&mut self, input: &[u8], is_create: bool) -> Result<()> {
+        let mut gasometer = Gasometer::new(self.tx.gas_limit, &GAS_CONFIG);
+        gasometer
+            .record_cost(intrinsic_gas(input, is_create, &self.tx.access_list))
+            .map_err(|_| anyhow!("Intrinsic gas too low!"))?;
+        self.gasometers = vec![gasometer];
+
+        self.accessed.insert((to_h160(self.tx.origin), None));
+        // EIP-3651
+        self.accessed.insert((to_h160(self.block.coinbase), None));
+        for item in self.tx.access_list.iter() {
+            let address = to_h160(item.address);
+            self.accessed.insert((address, None));
+            for key in item.storage_keys.iter() {
+                self.accessed.insert((address, Some(H256::from(key.0))));
+            }
         }
+        Ok(())
+    }
+
+    /// Gas used by the transaction, after the refunds (EIP-3529)
+    fn finish(&mut self) -> u64 {
+        let Some(gasometer) = self.gasometers.pop() else {
+            return 0;
+        };
+        let used = gasometer.total_used_gas();
+        let refund = gasometer.refunded_gas().max(0) as u64;
+        used - refund.min(used / GAS_CONFIG.max_refund_quotient)
+    }
+
+    /// Database errors abort the transaction instead of failing it
+    fn conclude<T>(&mut self, result: Result<T>) -> Result<Execution<T>> {
+        let gas_used = self.finish();
+        if let Some(err) = self.error.borrow_mut().take() {
+            return Err(err);
+        }
+        Ok(Execution {
+            result,
+            gas_used,
+            logs: std::mem::take(&mut self.logs),
+        })
     }
+
+    fn check(&self, reason: ExitReason, ret: &[u8]) -> Result<()> {
+        match reason {
+            ExitReason::Succeed(_) => Ok(()),
+            ExitReason::Revert(_) => {
+                Err(anyhow!("Execution reverted: 0x{}", alloy::hex::encode(ret)))
+            }
+            ExitReason::Error(e) => Err(anyhow!("Execution failed: {:?}", e)),
+            ExitReason::Fatal(e) => Err(anyhow!("Execution failed: {:?}", e)),
+        }
+    }
+
+    /// Keeps the first database error around, to be reported once the
+    /// execution stops
+    fn fail<T: Default>(&self, res: Result<T>) -> T {
+        match res {
+            Ok(v) => v,
+            Err(e) => {
+                self.error.borrow_mut().get_or_insert(e);
+                Default::default()
+            }
+        }
+    }
+
+    fn get(&self, k: Key) -> Result<Option<Value>> {
+        let blob: Blob = (&k).try_into()?;
+        for checkpoint in self.checkpoints.iter().rev() {
+            if let Some((_, v)) = checkpoint.get(&blob) {
+                return Ok(v.clone());
+            }
+        }
+        self.chain.db.get(k)
+    }
+
+    fn put(&mut self, k: Key, v: Option<Value>) -> Result<()> {
+        match self.checkpoints.last_mut() {
+            Some(checkpoint) => {
+                checkpoint.insert((&k).try_into()?, (k, v));
+                Ok(())
+            }
+            None => self.chain.db.put(k, v),
+        }
+    }
+
+    fn checkpoint(&mut self) {
+        self.checkpoints.push(BTreeMap::new());
+        self.log_marks.push(self.logs.len());
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        self.log_marks.pop();
+        let writes = self.checkpoints.pop().unwrap_or_default();
+        match self.checkpoints.last_mut() {
+            Some(parent) => parent.extend(writes),
+            None => self.chain.db.batch_put(writes.into_values())?,
+        }
+        Ok(())
+    }
+
+    fn revert(&mut self) {
+        self.checkpoints.pop();
+        if let Some(mark) = self.log_marks.pop() {
+            self.logs.truncate(mark);
+        }
+    }
+
+    fn get_balance(&self, address: H160) -> Result<alloy::primitives::U256> {
+        Ok(
+            match self.get(Key::Balance(to_address(address), Token::Native))? {
+                Some(v) => v.as_u256()?,
+                None => alloy::primitives::U256::ZERO,
+            },
+        )
+    }
+
+    fn get_nonce(&self, address: H160) -> Result<alloy::primitives::U256> {
+        Ok(match self.get(Key::NonceEth(to_address(address)))? {
+            Some(v) => v.as_u256()?,
+            None => alloy::primitives::U256::ZERO,
+        })
+    }
+
+    fn get_code(&self, address: H160) -> Result<Vec<u8>> {
+        Ok(match self.get(Key::ContractCode(to_address(address)))? {
+            Some(v) => v.as_vec_u8()?,
+            None => Vec::new(),
+        })
+    }
+
+    fn transfer(&mut self, transfer: &Transfer) -> Result<(), ExitError> {
+        if transfer.value.is_zero() {
+            return Ok(());
+        }
+        let value = to_alloy_u256(transfer.value);
+        let source_balance = self.get_balance(transfer.source).map_err(db_error)?;
+        if source_balance < value {
+            return Err(ExitError::OutOfFund);
+        }
+        self.put(
+            Key::Balance(to_address(transfer.source), Token::Native),
+            Some(Value::U256(source_balance - value)),
+        )
+        .map_err(db_error)?;
+        let target_balance = self.get_balance(transfer.target).map_err(db_error)?;
+        self.put(
+            Key::Balance(to_address(transfer.target), Token::Native),
+            Some(Value::U256(target_balance + value)),
+        )
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    fn run(&mut self, code: Vec<u8>, input: Vec<u8>, context: Context) -> (ExitReason, Vec<u8>) {
+        let mut runtime = Runtime::new(
+            Rc::new(code),
+            Rc::new(input),
+            context,
+            GAS_CONFIG.stack_limit,
+            GAS_CONFIG.memory_limit,
+        );
+        let reason = match runtime.run(self) {
+            Capture::Exit(reason) => reason,
+            Capture::Trap(Resolve::Call(interrupt, _)) => match interrupt {},
+            Capture::Trap(Resolve::Create(interrupt, _)) => match interrupt {},
+        };
+        (reason, runtime.machine().return_value())
+    }
+
+    /// Gas a nested call or create gets, taken from the current frame
+    fn reserve_gas(&mut self, target_gas: Option<u64>, take_l64: bool) -> Result<u64, ExitError> {
+        let Some(gasometer) = self.gasometers.last_mut() else {
+            return Ok(target_gas.unwrap_or(self.tx.gas_limit));
+        };
+        let after_gas = if take_l64 && GAS_CONFIG.call_l64_after_gas {
+            l64(gasometer.gas())
+        } else {
+            gasometer.gas()
+        };
+        let gas_limit = target_gas.unwrap_or(after_gas).min(after_gas);
+        gasometer.record_cost(gas_limit)?;
+        Ok(gas_limit)
+    }
+
+    /// Runs `f` in a new checkpoint with `gas_limit` gas of its own. The writes
+    /// are kept only if the call succeeds, the gas left goes back to the caller
+    /// unless the call failed with an error.
+    fn frame<R: Default, F: FnOnce(&mut Self) -> (ExitReason, R)>(
+        &mut self,
+        gas_limit: u64,
+        f: F,
+    ) -> (ExitReason, R) {
+        self.checkpoint();
+        self.gasometers.push(Gasometer::new(gas_limit, &GAS_CONFIG));
+        let (mut reason, ret) = f(self);
+        if reason.is_succeed() {
+            if let Err(e) = self.commit() {
+                self.error.borrow_mut().get_or_insert(e);
+                reason = db_error(anyhow!("Commit failed")).into();
+            }
+        } else {
+            self.revert();
+        }
+
+        let child = self.gasometers.pop();
+        if let (Some(child), Some(parent)) = (child, self.gasometers.last_mut()) {
+            let settled = match reason {
+                ExitReason::Succeed(_) => parent
+                    .record_stipend(child.gas())
+                    .and_then(|_| parent.record_refund(child.refunded_gas())),
+                ExitReason::Revert(_) => parent.record_stipend(child.gas()),
+                ExitReason::Error(_) | ExitReason::Fatal(_) => Ok(()),
+            };
+            if let Err(e) = settled {
+                reason = e.into();
+            }
+        }
+        (reason, ret)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn call_frame(
+        &mut self,
+        code_address: H160,
+        transfer: Option<Transfer>,
+        input: Vec<u8>,
+        target_gas: Option<u64>,
+        is_static: bool,
+        context: Context,
+        is_opcode: bool,
+    ) -> (ExitReason, Vec<u8>) {
+        if self.checkpoints.len() >= GAS_CONFIG.call_stack_limit {
+            return (ExitError::CallTooDeep.into(), Vec::new());
+        }
+        let mut gas_limit = match self.reserve_gas(target_gas, is_opcode) {
+            Ok(gas_limit) => gas_limit,
+            Err(e) => return (e.into(), Vec::new()),
+        };
+        if let Some(transfer) = transfer.as_ref() {
+            if is_opcode && !transfer.value.is_zero() {
+                gas_limit = gas_limit.saturating_add(GAS_CONFIG.call_stipend);
+            }
+        }
+
+        self.frame(gas_limit, |ovm| {
+            if let Some(transfer) = transfer {
+                if ovm.is_static && !transfer.value.is_zero() {
+                    return (write_protection().into(), Vec::new());
+                }
+                if let Err(e) = ovm.transfer(&transfer) {
+                    return (e.into(), Vec::new());
+                }
+            }
+            let code = match ovm.get_code(code_address) {
+                Ok(code) => code,
+                Err(e) => return (db_error(e).into(), Vec::new()),
+            };
+
+            let was_static = ovm.is_static;
+            ovm.is_static |= is_static;
+            let (reason, ret) = ovm.run(code, input, context);
+            ovm.is_static = was_static;
+            (reason, ret)
+        })
+    }
+
+    fn create_frame(
+        &mut self,
+        caller: H160,
+        scheme: CreateScheme,
+        value: U256,
+        init_code: Vec<u8>,
+        target_gas: Option<u64>,
+        is_opcode: bool,
+    ) -> (ExitReason, Option<H160>, Vec<u8>) {
+        if self.is_static {
+            return (write_protection().into(), None, Vec::new());
+        }
+        if self.checkpoints.len() >= GAS_CONFIG.call_stack_limit {
+            return (ExitError::CallTooDeep.into(), None, Vec::new());
+        }
+        if let Some(limit) = GAS_CONFIG.max_initcode_size {
+            if init_code.len() > limit {
+                return (ExitError::CreateContractLimit.into(), None, Vec::new());
+            }
+        }
+        // EIP-3860, transactions pay for the init code words as intrinsic gas
+        if is_opcode {
+            if let Some(gasometer) = self.gasometers.last_mut() {
+                if let Err(e) = gasometer.record_cost(gasometer::init_code_cost(&init_code)) {
+                    return (e.into(), None, Vec::new());
+                }
+            }
+        }
+        match self.get_balance(caller) {
+            Ok(balance) if balance < to_alloy_u256(value) => {
+                return (ExitError::OutOfFund.into(), None, Vec::new())
+            }
+            Ok(_) => {}
+            Err(e) => return (db_error(e).into(), None, Vec::new()),
+        }
+
+        let nonce = match self.get_nonce(caller) {
+            Ok(nonce) => nonce,
+            Err(e) => return (db_error(e).into(), None, Vec::new()),
+        };
+        let address = match scheme {
+            CreateScheme::Legacy { caller } => match u64::try_from(nonce) {
+                Ok(nonce) => to_h160(to_address(caller).create(nonce)),
+                Err(_) => return (ExitError::MaxNonce.into(), None, Vec::new()),
+            },
+            CreateScheme::Create2 {
+                caller,
+                code_hash,
+                salt,
+            } => to_h160(to_address(caller).create2(salt.0, code_hash.0)),
+            CreateScheme::Fixed(address) => address,
+        };
+        let gas_limit = match self.reserve_gas(target_gas, is_opcode) {
+            Ok(gas_limit) => gas_limit,
+            Err(e) => return (e.into(), None, Vec::new()),
+        };
+        // The nonce is spent even if the deployment fails
+        if let Err(e) = self.put(
+            Key::NonceEth(to_address(caller)),
+            Some(Value::U256(nonce + alloy::primitives::U256::from(1))),
+        ) {
+            return (db_error(e).into(), None, Vec::new());
+        }
+        self.accessed.insert((address, None));
+
+        let (reason, (address, ret)) = self.frame(gas_limit, |ovm| {
+            let collision = ovm.get_nonce(address).map(|n| !n.is_zero()).unwrap_or(true)
+                || !ovm.code(address).is_empty();
+            if collision {
+                return (ExitError::CreateCollision.into(), (None, Vec::new()));
+            }
+            // EIP-161, contracts start with a nonce of one
+            if let Err(e) = ovm.put(
+                Key::NonceEth(to_address(address)),
+                Some(Value::U256(alloy::primitives::U256::from(1))),
+            ) {
+                return (db_error(e).into(), (None, Vec::new()));
+            }
+            let transfer = Transfer {
+                source: caller,
+                target: address,
+                value,
+            };
+            if let Err(e) = ovm.transfer(&transfer) {
+                return (e.into(), (None, Vec::new()));
+            }
+
+            let context = Context {
+                address,
+                caller,
+                apparent_value: value,
+            };
+            let (reason, code) = ovm.run(init_code, Vec::new(), context);
+            if !reason.is_succeed() {
+                return (reason, (None, code));
+            }
+            if let Some(limit) = GAS_CONFIG.create_contract_limit {
+                if code.len() > limit {
+                    return (ExitError::CreateContractLimit.into(), (None, Vec::new()));
+                }
+            }
+            // EIP-3541
+            if code.first() == Some(&0xef) {
+                return (
+                    ExitError::InvalidCode(Opcode(0xef)).into(),
+                    (None, Vec::new()),
+                );
+            }
+            if let Some(gasometer) = ovm.gasometers.last_mut() {
+                if let Err(e) = gasometer.record_deposit(code.len()) {
+                    return (e.into(), (None, Vec::new()));
+                }
+            }
+            if let Err(e) = ovm.put(
+                Key::ContractCode(to_address(address)),
+                Some(Value::VecU8(code)),
+            ) {
+                return (db_error(e).into(), (None, Vec::new()));
+            }
+            (reason, (Some(address), Vec::new()))
+        });
+        (reason, address, ret)
+    }
+}
+
+fn db_error(e: anyhow::Error) -> ExitError {
+    ExitError::Other(format!("DB Failure: {}", e).into())
 }
 
 impl<'a, K: ContextKvStore> Handler for Ovm<'a, K> {
     type CallFeedback = ();
-    type CallInterrupt = ();
+    type CallInterrupt = Infallible;
     type CreateFeedback = ();
-    type CreateInterrupt = ();
-    fn balance(&self, _address: H160) -> U256 {
-        unimplemented!()
+    type CreateInterrupt = Infallible;
+    fn balance(&self, address: H160) -> U256 {
+        from_alloy_u256(self.fail(self.get_balance(address)))
     }
     fn block_base_fee_per_gas(&self) -> U256 {
-        unimplemented!()
+        U256::from(self.block.base_fee)
     }
     fn block_coinbase(&self) -> H160 {
-        unimplemented!()
+        to_h160(self.block.coinbase)
     }
     fn block_difficulty(&self) -> U256 {
-        unimplemented!()
+        U256::zero()
     }
     fn block_gas_limit(&self) -> U256 {
-        unimplemented!()
+        U256::from(BLOCK_GAS_LIMIT)
     }
-    fn block_hash(&self, _number: U256) -> H256 {
-        unimplemented!()
+    fn block_hash(&self, number: U256) -> H256 {
+        let current = U256::from(self.block.number);
+        if number >= current || current - number > U256::from(256) {
+            return H256::zero();
+        }
+        let hash = self.fail(
+            self.chain
+                .get_block(number.as_usize())
+                .and_then(|b| b.hash()),
+        );
+        H256::from(hash.0)
     }
     fn block_number(&self) -> U256 {
-        unimplemented!()
+        U256::from(self.block.number)
     }
     fn block_randomness(&self) -> Option<H256> {
-        unimplemented!()
+        Some(H256::zero())
     }
     fn block_timestamp(&self) -> U256 {
-        unimplemented!()
+        U256::from(self.block.timestamp)
     }
     fn call(
         &mut self,
-        _code_address: H160,
-        _transfer: Option<Transfer>,
-        _input: Vec<u8>,
-        _target_gas: Option<u64>,
-        _is_static: bool,
-        _context: Context,
+        code_address: H160,
+        transfer: Option<Transfer>,
+        input: Vec<u8>,
+        target_gas: Option<u64>,
+        is_static: bool,
+        context: Context,
     ) -> Capture<(ExitReason, Vec<u8>), Self::CallInterrupt> {
-        unimplemented!()
+        Capture::Exit(self.call_frame(
+            code_address,
+            transfer,
+            input,
+            target_gas,
+            is_static,
+            context,
+            true,
+        ))
     }
     fn call_feedback(&mut self, _feedback: Self::CallFeedback) -> Result<(), ExitError> {
-        unimplemented!()
+        Ok(())
     }
     fn chain_id(&self) -> U256 {
-        unimplemented!()
+        U256::from(self.chain.config.chain_id)
     }
-    fn code(&self, _address: H160) -> Vec<u8> {
-        unimplemented!()
+    fn code(&self, address: H160) -> Vec<u8> {
+        self.fail(self.get_code(address))
     }
-    fn code_hash(&self, _address: H160) -> H256 {
-        unimplemented!()
+    fn code_hash(&self, address: H160) -> H256 {
+        if !self.exists(address) {
+            return H256::zero();
+        }
+        H256::from(keccak256(self.code(address)).0)
     }
-    fn code_size(&self, _address: H160) -> U256 {
-        unimplemented!()
+    fn code_size(&self, address: H160) -> U256 {
+        U256::from(self.code(address).len())
     }
     fn create(
         &mut self,
-        _caller: H160,
-        _scheme: CreateScheme,
-        _value: U256,
-        _init_code: Vec<u8>,
-        _target_gas: Option<u64>,
+        caller: H160,
+        scheme: CreateScheme,
+        value: U256,
+        init_code: Vec<u8>,
+        target_gas: Option<u64>,
     ) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Self::CreateInterrupt> {
-        unimplemented!()
+        Capture::Exit(self.create_frame(caller, scheme, value, init_code, target_gas, true))
     }
     fn create_feedback(&mut self, _feedback: Self::CreateFeedback) -> Result<(), ExitError> {
-        unimplemented!()
+        Ok(())
     }
     fn deleted(&self, _address: H160) -> bool {
-        unimplemented!()
+        // Accounts are never deleted, SELFDESTRUCT only moves the funds (EIP-6780)
+        false
     }
-    fn exists(&self, _address: H160) -> bool {
-        unimplemented!()
+    fn exists(&self, address: H160) -> bool {
+        !self.balance(address).is_zero()
+            || !self.fail(self.get_nonce(address)).is_zero()
+            || !self.code(address).is_empty()
     }
     fn gas_left(&self) -> U256 {
-        unimplemented!()
+        U256::from(
+            self.gasometers
+                .last()
+                .map(|gasometer| gasometer.gas())
+                .unwrap_or(self.tx.gas_limit),
+        )
     }
     fn gas_price(&self) -> U256 {
-        unimplemented!()
+        from_alloy_u256(self.tx.gas_price)
     }
-    fn is_cold(&mut self, _address: H160, _index: Option<H256>) -> Result<bool, ExitError> {
-        unimplemented!()
+    fn is_cold(&mut self, address: H160, index: Option<H256>) -> Result<bool, ExitError> {
+        Ok(!self.accessed.contains(&(address, index)))
     }
-    fn log(&mut self, _address: H160, _topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
-        println!("Log: {:?}", data);
+    fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+        if self.is_static {
+            return Err(write_protection());
+        }
+        self.logs.push(Log {
+            address: to_address(address),
+            topics: topics.into_iter().map(|t| t.0.into()).collect(),
+            data,
+        });
         Ok(())
     }
-    fn mark_delete(&mut self, _address: H160, _target: H160) -> Result<(), ExitError> {
-        unimplemented!()
+    fn mark_delete(&mut self, address: H160, target: H160) -> Result<(), ExitError> {
+        if self.is_static {
+            return Err(write_protection());
+        }
+        let value = self.balance(address);
+        self.transfer(&Transfer {
+            source: address,
+            target,
+            value,
+        })
     }
     fn origin(&self) -> H160 {
-        unimplemented!()
+        to_h160(self.tx.origin)
     }
-    fn original_storage(&self, _address: H160, _index: H256) -> H256 {
-        unimplemented!()
+    fn original_storage(&self, address: H160, index: H256) -> H256 {
+        // Writes of the running transaction stay in the checkpoints until it
+        // finishes, so the database still has the values it started with
+        let value = self.chain.db.get(storage_key(address, index));
+        self.fail(storage_value(value))
     }
-    fn other(&mut self, _opcode: Opcode, _stack: &mut Machine) -> Result<(), ExitError> {
-        unimplemented!()
+    fn other(&mut self, opcode: Opcode, _stack: &mut Machine) -> Result<(), ExitError> {
+        Err(ExitError::InvalidCode(opcode))
     }
     fn pre_validate(
         &mut self,
-        _context: &Context,
-        _opcode: Opcode,
-        _stack: &Stack,
+        context: &Context,
+        opcode: Opcode,
+        stack: &Stack,
     ) -> Result<(), ExitError> {
         if let Some(err) = self.error.borrow().as_ref() {
             return Err(ExitError::Other(format!("{}", err).into()));
         }
+        if self.gasometers.is_empty() {
+            return Ok(());
+        }
+        if let Some(cost) = gasometer::static_opcode_cost(opcode) {
+            if let Some(gasometer) = self.gasometers.last_mut() {
+                gasometer.record_cost(cost)?;
+            }
+            return Ok(());
+        }
+        let (cost, target, memory_cost) = gasometer::dynamic_opcode_cost(
+            context.address,
+            opcode,
+            stack,
+            self.is_static,
+            &GAS_CONFIG,
+            self,
+        )?;
+        if let Some(gasometer) = self.gasometers.last_mut() {
+            gasometer.record_dynamic_cost(cost, memory_cost)?;
+        }
+        match target {
+            StorageTarget::Address(address) => {
+                self.accessed.insert((address, None));
+            }
+            StorageTarget::Slot(address, index) => {
+                self.accessed.insert((address, Some(index)));
+            }
+            StorageTarget::None => {}
+        }
         Ok(())
     }
     fn record_external_operation(&mut self, _op: ExternalOperation) -> Result<(), ExitError> {
-        unimplemented!()
-    }
-    fn set_storage(&mut self, address: H160, index: H256, _value: H256) -> Result<(), ExitError> {
-        let index_alloy = alloy::primitives::U256::from_le_bytes(index.to_fixed_bytes());
-        let address_alloy = alloy::primitives::Address::from_slice(&address.to_fixed_bytes());
-        self.chain
-            .db
-            .put(
-                Key::ContractStorage(address_alloy, index_alloy),
-                Some(Value::U256(index_alloy)),
-            )
-            .map_err(|_| ExitError::Other("DB Failure".into()))?;
         Ok(())
     }
-    fn storage(&self, address: H160, index: H256) -> H256 {
-        let f = move || -> Result<H256> {
-            let index_alloy = alloy::primitives::U256::from_le_bytes(index.to_fixed_bytes());
-            let address_alloy = alloy::primitives::Address::from_slice(&address.to_fixed_bytes());
-            Ok(
-                if let Some(val) = self
-                    .chain
-                    .db
-                    .get(Key::ContractStorage(address_alloy, index_alloy))?
-                {
-                    H256::from(val.as_u256()?.to_le_bytes())
-                } else {
-                    Default::default()
-                },
-            )
-        };
-
-        match f() {
-            Ok(res) => res,
-            Err(e) => {
-                *self.error.borrow_mut() = Some(e);
-                Default::default()
-            }
+    fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError> {
+        if self.is_static {
+            return Err(write_protection());
         }
+        // Zero slots are not kept around, reading them gives zero anyway
+        let value = if value.is_zero() {
+            None
+        } else {
+            Some(Value::U256(alloy::primitives::U256::from_be_bytes(
+                value.to_fixed_bytes(),
+            )))
+        };
+        self.put(storage_key(address, index), value)
+            .map_err(db_error)?;
+        Ok(())
+    }
+    fn storage(&self, address: H160, index: H256) -> H256 {
+        let value = self.get(storage_key(address, index));
+        self.fail(storage_value(value))
     }
 }
 
+/// EVM words are big-endian
+fn storage_key(address: H160, index: H256) -> Key {
+    Key::ContractStorage(
+        to_address(address),
+        alloy::primitives::U256::from_be_bytes(index.to_fixed_bytes()),
+    )
+}
+
+fn storage_value(value: Result<Option<Value>>) -> Result<H256> {
+    Ok(match value? {
+        Some(v) => H256::from(v.as_u256()?.to_be_bytes::<32>()),
+        None => H256::zero(),
+    })
+}
+
+fn write_protection() -> ExitError {
+    ExitError::Other("Write protection".into())
+}
+
 #[cfg(test)]
 mod tests;
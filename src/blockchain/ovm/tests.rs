@@ -1,41 +1,455 @@
-// use alloy::{
-//     network::{EthereumWallet, TransactionBuilder},
-//     rpc::types::TransactionRequest,
-//     signers::local::PrivateKeySigner,
-// };
-
-// use crate::{
-//     blockchain::Config,
-//     config::{self, CHAIN_ID},
-//     db::RamKvStore,
-//     genesis::GENESIS,
-//     types::OwshenTransaction,
-// };
-
-// use super::*;
-
-// #[tokio::test]
-// async fn test_contract_storage() {
-//     let conf = Config {
-//         chain_id: CHAIN_ID,
-//         owner: None,
-//         genesis: GENESIS.clone(),
-//         owshen: config::OWSHEN_CONTRACT,
-//     };
-//     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
-//     let signer: PrivateKeySigner = PrivateKeySigner::random();
-//     let vitalik = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
-//         .parse()
-//         .unwrap();
-//     let tx = TransactionRequest::default()
-//         .with_to(vitalik)
-//         .with_deploy_code(vec![0, 0, 0, 0])
-//         .with_nonce(1)
-//         .with_gas_limit(100)
-//         .with_max_fee_per_gas(100)
-//         .with_max_priority_fee_per_gas(100)
-//         .with_chain_id(CHAIN_ID);
-//     let wallet = EthereumWallet::new(signer);
-//     let tx = OwshenTransaction::Eth(tx.build(&wallet).await.unwrap());
-//     chain.apply_tx(&tx).unwrap();
-// }
+use alloy::{
+    consensus::{TxEip1559, TypedTransaction},
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{Bytes, TxKind},
+    signers::local::PrivateKeySigner,
+    sol,
+    sol_types::SolCall,
+};
+
+use crate::{
+    blockchain::tx::TxOutcome,
+    blockchain::Config,
+    config::{self, CHAIN_ID},
+    db::RamKvStore,
+    genesis::GENESIS,
+    types::{network::Network, OwshenTransaction},
+};
+
+use super::*;
+
+sol! {
+    interface ICounter {
+        function number() external view returns (uint256);
+        function setNumber(uint256 newNumber) external;
+        function increment() external;
+    }
+}
+
+/// Runtime code of the counter contract below, laid out the way solc does:
+///
+/// ```solidity
+/// contract Counter {
+///     uint256 public number;
+///     function setNumber(uint256 newNumber) public { number = newNumber; }
+///     function increment() public { number++; }
+/// }
+/// ```
+///
+/// Non-payable, dispatches on the selector and reverts when `increment`
+/// overflows.
+const COUNTER_RUNTIME: &str = concat!(
+    "6080604052",                             // free memory pointer
+    "34801561001057600080fd5b50",             // callvalue check
+    "600436106100415760003560e01c",           // selector
+    "80633fb5c1cb1461005257",                 // setNumber(uint256)
+    "80638381f58a1461004657",                 // number()
+    "8063d09de08a1461006257",                 // increment()
+    "5b600080fd",                             // fallback: revert
+    "5b60005460805260206080f3",               // number: return slot 0
+    "5b602436106100415760043560005500",       // setNumber: slot 0 = arg
+    "5b600054806001019081106100415760005500", // increment: checked slot 0 += 1
+);
+
+/// Init code copying `COUNTER_RUNTIME` into the contract code
+fn counter_init_code() -> Vec<u8> {
+    let runtime = alloy::hex::decode(COUNTER_RUNTIME).unwrap();
+    let len = runtime.len() as u8;
+    // PUSH1 len PUSH1 12 PUSH1 0 CODECOPY PUSH1 len PUSH1 0 RETURN
+    let mut code = vec![
+        0x60, len, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, len, 0x60, 0x00, 0xf3,
+    ];
+    code.extend(runtime);
+    code
+}
+
+fn test_chain() -> Owshenchain<RamKvStore> {
+    let conf = Config {
+        chain_id: CHAIN_ID,
+        owner: None,
+        genesis: GENESIS.clone(),
+        networks: vec![Network {
+            chain_id: 1,
+            owshen: config::OWSHEN_CONTRACT,
+            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
+            confirmations: 1,
+        }],
+    };
+    Owshenchain::new(conf, RamKvStore::new())
+}
+
+async fn send(
+    chain: &mut Owshenchain<RamKvStore>,
+    wallet: &EthereumWallet,
+    nonce: u64,
+    to: TxKind,
+    input: Vec<u8>,
+) -> Result<TxOutcome> {
+    send_with_gas(chain, wallet, nonce, to, input, 1_000_000).await
+}
+
+async fn send_with_gas(
+    chain: &mut Owshenchain<RamKvStore>,
+    wallet: &EthereumWallet,
+    nonce: u64,
+    to: TxKind,
+    input: Vec<u8>,
+    gas_limit: u64,
+) -> Result<TxOutcome> {
+    let tx = TxEip1559 {
+        nonce,
+        gas_limit: gas_limit as u128,
+        to,
+        value: alloy::primitives::U256::ZERO,
+        input: Bytes::from(input),
+        chain_id: CHAIN_ID,
+        max_priority_fee_per_gas: 100,
+        max_fee_per_gas: 100,
+        access_list: Default::default(),
+    };
+    let tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+        wallet,
+        TypedTransaction::Eip1559(tx),
+    )
+    .await?;
+    chain.apply_tx(&OwshenTransaction::Eth(tx), &BlockContext::default())
+}
+
+/// A wallet with enough native tokens to pay for its transactions
+fn funded_wallet(chain: &mut Owshenchain<RamKvStore>) -> (Address, EthereumWallet) {
+    let signer = PrivateKeySigner::random();
+    let address = signer.address();
+    chain
+        .db
+        .put(
+            Key::Balance(address, Token::Native),
+            Some(Value::U256(alloy::primitives::U256::from(
+                1_000_000_000_000_000u64,
+            ))),
+        )
+        .unwrap();
+    (address, EthereumWallet::new(signer))
+}
+
+fn number(chain: &Owshenchain<RamKvStore>, counter: Address) -> alloy::primitives::U256 {
+    let ret = chain
+        .call_contract(Address::ZERO, counter, ICounter::numberCall {}.abi_encode())
+        .unwrap();
+    ICounter::numberCall::abi_decode_returns(&ret, true)
+        .unwrap()
+        ._0
+}
+
+#[tokio::test]
+async fn test_contract_storage() {
+    let mut chain = test_chain();
+    let (from, wallet) = funded_wallet(&mut chain);
+
+    send(&mut chain, &wallet, 0, TxKind::Create, counter_init_code())
+        .await
+        .unwrap();
+    let counter = from.create(0);
+    assert_eq!(number(&chain, counter), alloy::primitives::U256::ZERO);
+
+    let value = alloy::primitives::U256::from(0x1234);
+    send(
+        &mut chain,
+        &wallet,
+        1,
+        TxKind::Call(counter),
+        ICounter::setNumberCall { newNumber: value }.abi_encode(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(number(&chain, counter), value);
+    assert_eq!(
+        chain
+            .db
+            .get(Key::ContractStorage(counter, alloy::primitives::U256::ZERO))
+            .unwrap()
+            .unwrap()
+            .as_u256()
+            .unwrap(),
+        value
+    );
+
+    send(
+        &mut chain,
+        &wallet,
+        2,
+        TxKind::Call(counter),
+        ICounter::incrementCall {}.abi_encode(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        number(&chain, counter),
+        value + alloy::primitives::U256::from(1)
+    );
+
+    // Resetting the slot removes it
+    send(
+        &mut chain,
+        &wallet,
+        3,
+        TxKind::Call(counter),
+        ICounter::setNumberCall {
+            newNumber: alloy::primitives::U256::ZERO,
+        }
+        .abi_encode(),
+    )
+    .await
+    .unwrap();
+    assert!(chain
+        .db
+        .get(Key::ContractStorage(counter, alloy::primitives::U256::ZERO))
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_contract_storage_revert() {
+    let mut chain = test_chain();
+    let (from, wallet) = funded_wallet(&mut chain);
+
+    send(&mut chain, &wallet, 0, TxKind::Create, counter_init_code())
+        .await
+        .unwrap();
+    let counter = from.create(0);
+    send(
+        &mut chain,
+        &wallet,
+        1,
+        TxKind::Call(counter),
+        ICounter::setNumberCall {
+            newNumber: alloy::primitives::U256::MAX,
+        }
+        .abi_encode(),
+    )
+    .await
+    .unwrap();
+
+    // Overflows, nothing is written but the nonce and the fee
+    let outcome = send(
+        &mut chain,
+        &wallet,
+        2,
+        TxKind::Call(counter),
+        ICounter::incrementCall {}.abi_encode(),
+    )
+    .await
+    .unwrap();
+    assert!(outcome.error.unwrap().starts_with("Execution reverted"));
+    assert_eq!(number(&chain, counter), alloy::primitives::U256::MAX);
+    assert_eq!(
+        chain.get_eth_nonce(from).unwrap(),
+        alloy::primitives::U256::from(3)
+    );
+}
+
+#[tokio::test]
+async fn test_contract_gas() {
+    let mut chain = test_chain();
+    let (from, wallet) = funded_wallet(&mut chain);
+
+    send(&mut chain, &wallet, 0, TxKind::Create, counter_init_code())
+        .await
+        .unwrap();
+    let counter = from.create(0);
+
+    let set_number = ICounter::setNumberCall {
+        newNumber: alloy::primitives::U256::from(0x1234),
+    }
+    .abi_encode();
+    let estimate = chain
+        .estimate_gas(
+            from,
+            TxKind::Call(counter),
+            alloy::primitives::U256::ZERO,
+            set_number.clone(),
+        )
+        .unwrap();
+    // Intrinsic cost plus a cold write of a fresh slot
+    assert!(estimate > 21_000 + 22_100);
+
+    // One gas short runs out in the middle of the call and pays for all of it
+    let balance = chain.get_balance(Token::Native, from).unwrap();
+    let outcome = send_with_gas(
+        &mut chain,
+        &wallet,
+        1,
+        TxKind::Call(counter),
+        set_number.clone(),
+        estimate - 1,
+    )
+    .await
+    .unwrap();
+    assert!(!outcome.is_success());
+    assert_eq!(outcome.gas_used, estimate - 1);
+    assert_eq!(number(&chain, counter), alloy::primitives::U256::ZERO);
+    assert_eq!(
+        chain.get_balance(Token::Native, from).unwrap(),
+        balance - alloy::primitives::U256::from((estimate - 1) * 100)
+    );
+
+    let balance = chain.get_balance(Token::Native, from).unwrap();
+    let gas_used = send_with_gas(
+        &mut chain,
+        &wallet,
+        2,
+        TxKind::Call(counter),
+        set_number,
+        estimate,
+    )
+    .await
+    .unwrap()
+    .gas_used;
+    assert_eq!(gas_used, estimate);
+    assert_eq!(
+        chain.get_balance(Token::Native, from).unwrap(),
+        balance - alloy::primitives::U256::from(gas_used * 100)
+    );
+
+    // Clearing the slot is partly refunded
+    let gas_used = send(
+        &mut chain,
+        &wallet,
+        3,
+        TxKind::Call(counter),
+        ICounter::setNumberCall {
+            newNumber: alloy::primitives::U256::ZERO,
+        }
+        .abi_encode(),
+    )
+    .await
+    .unwrap()
+    .gas_used;
+    assert!(gas_used < estimate - 10_000);
+}
+
+#[tokio::test]
+async fn test_contract_logs() {
+    let mut chain = test_chain();
+    let (from, wallet) = funded_wallet(&mut chain);
+    let topic = alloy::primitives::B256::from([7; 32]);
+
+    // MSTORE 0x2a, LOG1 the word with `topic`, then STOP or REVERT
+    let runtime = |last: u8| {
+        let mut code = vec![0x60, 0x2a, 0x60, 0x00, 0x52, 0x7f];
+        code.extend(topic.as_slice());
+        code.extend([0x60, 0x20, 0x60, 0x00, 0xa1, 0x60, 0x00, 0x60, 0x00, last]);
+        code
+    };
+    for (nonce, last) in [0x00, 0xfd].into_iter().enumerate() {
+        let code = runtime(last);
+        let len = code.len() as u8;
+        // PUSH1 len PUSH1 12 PUSH1 0 CODECOPY PUSH1 len PUSH1 0 RETURN
+        let mut init_code = vec![
+            0x60, len, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, len, 0x60, 0x00, 0xf3,
+        ];
+        init_code.extend(code);
+        send(&mut chain, &wallet, nonce as u64, TxKind::Create, init_code)
+            .await
+            .unwrap();
+    }
+
+    let outcome = send(&mut chain, &wallet, 2, TxKind::Call(from.create(0)), vec![])
+        .await
+        .unwrap();
+    assert!(outcome.is_success());
+    assert_eq!(
+        outcome.logs,
+        vec![Log {
+            address: from.create(0),
+            topics: vec![topic],
+            data: alloy::primitives::U256::from(0x2a)
+                .to_be_bytes::<32>()
+                .to_vec(),
+        }]
+    );
+
+    // Logs of a reverted call are dropped with its writes
+    let outcome = send(&mut chain, &wallet, 3, TxKind::Call(from.create(1)), vec![])
+        .await
+        .unwrap();
+    assert!(!outcome.is_success());
+    assert!(outcome.logs.is_empty());
+}
+
+#[test]
+fn test_storage_words_are_big_endian() {
+    let mut chain = test_chain();
+    let address = H160::repeat_byte(7);
+    let index = H256::from_low_u64_be(1);
+    let value = H256::from_low_u64_be(0x1234);
+    {
+        let mut ovm = Ovm::new(&mut chain, BlockContext::default(), TxContext::default());
+        ovm.set_storage(address, index, value).unwrap();
+        assert_eq!(ovm.storage(address, index), value);
+        assert_eq!(ovm.storage(address, H256::from_low_u64_le(1)), H256::zero());
+    }
+
+    assert_eq!(
+        chain
+            .db
+            .get(Key::ContractStorage(
+                to_address(address),
+                alloy::primitives::U256::from(1)
+            ))
+            .unwrap()
+            .unwrap()
+            .as_u256()
+            .unwrap(),
+        alloy::primitives::U256::from(0x1234)
+    );
+}
+
+#[test]
+fn test_original_storage() {
+    let mut chain = test_chain();
+    let address = H160::repeat_byte(7);
+    let index = H256::from_low_u64_be(3);
+    let original = H256::from_low_u64_be(10);
+    let mut ovm = Ovm::new(&mut chain, BlockContext::default(), TxContext::default());
+    ovm.set_storage(address, index, original).unwrap();
+
+    // A transaction in progress
+    ovm.checkpoint();
+    ovm.set_storage(address, index, H256::from_low_u64_be(20))
+        .unwrap();
+    assert_eq!(ovm.storage(address, index), H256::from_low_u64_be(20));
+    assert_eq!(ovm.original_storage(address, index), original);
+
+    ovm.commit().unwrap();
+    assert_eq!(
+        ovm.original_storage(address, index),
+        H256::from_low_u64_be(20)
+    );
+}
+
+#[tokio::test]
+async fn test_create_max_nonce() {
+    let mut chain = test_chain();
+    let (_, wallet) = funded_wallet(&mut chain);
+
+    // PUSH1 0 PUSH1 0 PUSH1 0 CREATE STOP, from a contract out of nonces
+    let factory = Address::from([9; 20]);
+    let nonce = alloy::primitives::U256::from(u64::MAX) + alloy::primitives::U256::from(1);
+    chain
+        .db
+        .put(
+            Key::ContractCode(factory),
+            Some(Value::VecU8(vec![0x60, 0, 0x60, 0, 0x60, 0, 0xf0, 0x00])),
+        )
+        .unwrap();
+    chain
+        .db
+        .put(Key::NonceEth(factory), Some(Value::U256(nonce)))
+        .unwrap();
+
+    let outcome = send(&mut chain, &wallet, 0, TxKind::Call(factory), Vec::new())
+        .await
+        .unwrap();
+    assert!(outcome.is_success());
+    assert_eq!(chain.get_eth_nonce(factory).unwrap(), nonce);
+}
@@ -0,0 +1,273 @@
+use alloy::primitives::{keccak256, FixedBytes, U256};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    db::{Blob, Key, KvStore, MirrorKvStore, Value},
+    services::ContextKvStore,
+};
+
+/// The state is committed to by a sparse Merkle tree with one level per bit of
+/// `keccak256(key)`. Leaves hold `keccak256(value)`, zero for a missing entry,
+/// and nodes equal to the hash of an empty subtree are never stored.
+pub const TREE_DEPTH: u16 = 256;
+
+lazy_static::lazy_static! {
+    /// Hash of an empty subtree, indexed by its level
+    static ref EMPTY_HASHES: Vec<FixedBytes<32>> = {
+        let mut hashes = vec![FixedBytes::ZERO];
+        for level in 0..TREE_DEPTH as usize {
+            hashes.push(hash_pair(hashes[level], hashes[level]));
+        }
+        hashes
+    };
+}
+
+/// Entries committed to by the state root. Genesis balances are stored along
+/// with the first block, so they are part of the tree from then on.
+pub fn is_state_key(key: &Key) -> bool {
+    matches!(
+        key,
+        Key::Balance(..)
+            | Key::Allowance(..)
+            | Key::NonceEth(_)
+            | Key::NonceCustom(_)
+            | Key::ContractStorage(..)
+    )
+}
+
+pub fn hash_pair(left: FixedBytes<32>, right: FixedBytes<32>) -> FixedBytes<32> {
+    keccak256([left.as_slice(), right.as_slice()].concat())
+}
+
+fn get_node<K: KvStore>(db: &K, level: u16, path: U256) -> Result<FixedBytes<32>> {
+    Ok(match db.get(Key::StateNode(level, path))? {
+        Some(v) => v.as_u256()?.to_be_bytes().into(),
+        None => EMPTY_HASHES[level as usize],
+    })
+}
+
+pub fn state_root<K: KvStore>(db: &K) -> Result<FixedBytes<32>> {
+    get_node(db, TREE_DEPTH, U256::ZERO)
+}
+
+/// Position of an entry among the leaves of the tree
+fn key_path(key: &Key) -> Result<U256> {
+    let blob: Blob = key.try_into()?;
+    Ok(U256::from_be_bytes(keccak256(blob).0))
+}
+
+fn leaf_hash(value: Option<&Value>) -> Result<FixedBytes<32>> {
+    Ok(match value {
+        Some(v) => {
+            let blob: Blob = v.try_into()?;
+            keccak256(blob)
+        }
+        None => EMPTY_HASHES[0],
+    })
+}
+
+/// Siblings on the path from a leaf to the root. Most of them are empty
+/// subtrees, so only the others are listed and `bitmap` marks their levels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateProof {
+    pub bitmap: U256,
+    pub siblings: Vec<FixedBytes<32>>,
+}
+
+pub fn state_proof<K: KvStore>(db: &K, key: &Key) -> Result<StateProof> {
+    let mut path = key_path(key)?;
+    let mut bitmap = U256::ZERO;
+    let mut siblings = Vec::new();
+    for level in 0..TREE_DEPTH {
+        let bit = U256::from(1) << level;
+        let sibling = get_node(db, level, path ^ bit)?;
+        if sibling != EMPTY_HASHES[level as usize] {
+            bitmap |= bit;
+            siblings.push(sibling);
+        }
+        path &= !bit;
+    }
+    Ok(StateProof { bitmap, siblings })
+}
+
+/// Checks that `key` holds `value` in the state committed to by `root`, with
+/// `None` proving the entry is absent.
+pub fn verify_state_proof(
+    root: FixedBytes<32>,
+    key: &Key,
+    value: Option<&Value>,
+    proof: &StateProof,
+) -> Result<bool> {
+    let path = key_path(key)?;
+    let mut hash = leaf_hash(value)?;
+    let mut siblings = proof.siblings.iter();
+    for level in 0..TREE_DEPTH {
+        let bit = U256::from(1) << level;
+        let sibling = if (proof.bitmap & bit).is_zero() {
+            EMPTY_HASHES[level as usize]
+        } else {
+            match siblings.next() {
+                Some(sibling) => *sibling,
+                None => return Ok(false),
+            }
+        };
+        hash = if (path & bit).is_zero() {
+            hash_pair(hash, sibling)
+        } else {
+            hash_pair(sibling, hash)
+        };
+    }
+    Ok(siblings.next().is_none() && hash == root)
+}
+
+/// Folds the state entries written to `db` since it was forked into the tree,
+/// only rehashing the paths from the touched leaves up to the root
+pub fn update_state_root<K: ContextKvStore>(
+    db: &mut MirrorKvStore<'_, K>,
+) -> Result<FixedBytes<32>> {
+    let mut nodes = BTreeMap::new();
+    for (k, v) in db.overwrites() {
+        if is_state_key(&Key::try_from(k)?) {
+            let leaf = match v {
+                Some(v) => keccak256(v),
+                None => EMPTY_HASHES[0],
+            };
+            nodes.insert(U256::from_be_bytes(keccak256(k).0), leaf);
+        }
+    }
+    if nodes.is_empty() {
+        return state_root(db);
+    }
+
+    for level in 0..=TREE_DEPTH {
+        let mut parents = BTreeMap::new();
+        for (path, hash) in nodes.iter() {
+            let key = Key::StateNode(level, *path);
+            if *hash != EMPTY_HASHES[level as usize] {
+                db.put(key, Some(Value::U256(U256::from_be_bytes(hash.0))))?;
+            } else if db.get(key.clone())?.is_some() {
+                // Subtrees emptied by this block are dropped, the others were
+                // never stored
+                db.put(key, None)?;
+            }
+            if level == TREE_DEPTH {
+                return Ok(*hash);
+            }
+
+            let bit = U256::from(1) << level;
+            let sibling = match nodes.get(&(path ^ bit)) {
+                Some(sibling) => *sibling,
+                None => get_node(db, level, path ^ bit)?,
+            };
+            let parent = if (path & bit).is_zero() {
+                hash_pair(*hash, sibling)
+            } else {
+                hash_pair(sibling, *hash)
+            };
+            parents.insert(path & !bit, parent);
+        }
+        nodes = parents;
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::RamKvStore;
+    use crate::types::Token;
+    use alloy::primitives::Address;
+
+    fn root_after(base: &RamKvStore, writes: &[(Key, Option<Value>)]) -> FixedBytes<32> {
+        let mut db = MirrorKvStore::new(base);
+        db.batch_put(writes.iter().cloned()).unwrap();
+        update_state_root(&mut db).unwrap()
+    }
+
+    #[test]
+    fn test_state_root() {
+        let balance = |i: u8, amount: u64| {
+            (
+                Key::Balance(Address::from([i; 20]), Token::Native),
+                Some(Value::U256(U256::from(amount))),
+            )
+        };
+        let empty = RamKvStore::new();
+        assert_eq!(state_root(&empty).unwrap(), EMPTY_HASHES[256]);
+
+        // Only state entries are committed to
+        assert_eq!(
+            root_after(&empty, &[(Key::Height, Some(Value::Usize(1)))]),
+            EMPTY_HASHES[256]
+        );
+
+        // Updating incrementally agrees with building the tree at once
+        let mut db = RamKvStore::new();
+        let mut fork = MirrorKvStore::new(&db);
+        fork.batch_put([balance(1, 10), balance(2, 20)].into_iter())
+            .unwrap();
+        let first = update_state_root(&mut fork).unwrap();
+        let buffer = fork.buffer();
+        db.batch_put_raw(buffer.into_iter()).unwrap();
+        assert_eq!(state_root(&db).unwrap(), first);
+
+        let incremental = root_after(&db, &[balance(2, 25), balance(3, 30)]);
+        let at_once = root_after(&empty, &[balance(1, 10), balance(2, 25), balance(3, 30)]);
+        assert_eq!(incremental, at_once);
+        assert_ne!(incremental, first);
+
+        // Subtrees that stay empty aren't written to
+        let mut fork = MirrorKvStore::new(&db);
+        fork.put(Key::Balance(Address::from([9; 20]), Token::Native), None)
+            .unwrap();
+        assert_eq!(update_state_root(&mut fork).unwrap(), first);
+        assert_eq!(
+            fork.overwrites().values().filter(|v| v.is_none()).count(),
+            1
+        );
+
+        // Removing every entry brings back the empty root
+        let removed = root_after(
+            &db,
+            &[
+                (Key::Balance(Address::from([1; 20]), Token::Native), None),
+                (Key::Balance(Address::from([2; 20]), Token::Native), None),
+            ],
+        );
+        assert_eq!(removed, EMPTY_HASHES[256]);
+    }
+
+    #[test]
+    fn test_state_proof() {
+        let key = |i: u8| Key::Balance(Address::from([i; 20]), Token::Native);
+        let value = |amount: u64| Value::U256(U256::from(amount));
+        let mut db = RamKvStore::new();
+        let mut fork = MirrorKvStore::new(&db);
+        fork.batch_put((1..5).map(|i| (key(i), Some(value(i as u64)))))
+            .unwrap();
+        let root = update_state_root(&mut fork).unwrap();
+        let buffer = fork.buffer();
+        db.batch_put_raw(buffer.into_iter()).unwrap();
+
+        let proof = state_proof(&db, &key(2)).unwrap();
+        assert_eq!(
+            proof.siblings.len() as u32,
+            proof.bitmap.count_ones() as u32
+        );
+        assert!(verify_state_proof(root, &key(2), Some(&value(2)), &proof).unwrap());
+        assert!(!verify_state_proof(root, &key(2), Some(&value(3)), &proof).unwrap());
+        assert!(!verify_state_proof(root, &key(2), None, &proof).unwrap());
+        assert!(!verify_state_proof(root, &key(3), Some(&value(2)), &proof).unwrap());
+
+        // Absent entries have proofs too
+        let proof = state_proof(&db, &key(9)).unwrap();
+        assert!(verify_state_proof(root, &key(9), None, &proof).unwrap());
+        assert!(!verify_state_proof(root, &key(9), Some(&value(0)), &proof).unwrap());
+
+        let mut truncated = state_proof(&db, &key(1)).unwrap();
+        truncated.siblings.pop();
+        assert!(!verify_state_proof(root, &key(1), Some(&value(1)), &truncated).unwrap());
+    }
+}
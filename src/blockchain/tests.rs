@@ -1,11 +1,10 @@
 use std::clone;
 
 use super::*;
-use crate::config;
 use crate::db::{Key, KvStore, Value};
-use crate::types::{network::Network, Burn, CustomTx, Token};
+use crate::types::{Burn, CustomTx, Token, WithdrawCalldata};
 use crate::types::{Mint, ERC20};
-use crate::{db::RamKvStore, genesis::GENESIS};
+use crate::db::RamKvStore;
 use alloy::primitives::Uint;
 use alloy::primitives::{utils::parse_units, Address, U256};
 use alloy::signers::local::PrivateKeySigner;
@@ -15,13 +14,7 @@ use tokio::time::{sleep, Duration};
 
 #[test]
 fn test_block_storage() -> Result<(), anyhow::Error> {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-    };
+    let conf = Config::for_tests();
     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
     let mut tx_queue = TransactionQueue::new();
 
@@ -55,13 +48,7 @@ fn test_block_storage() -> Result<(), anyhow::Error> {
 
 #[test]
 fn test_get_balance_without_genesis_balance() {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-    };
+    let conf = Config::for_tests();
     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
 
     let mock_user_address = Address::from([1; 20]);
@@ -121,13 +108,7 @@ fn test_get_balance_without_genesis_balance() {
 
 #[test]
 fn test_get_balance_with_genesis_balance() {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-    };
+    let conf = Config::for_tests();
     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
 
     let genesis_mock_user_address: Address = Address::from([2; 20]);
@@ -136,8 +117,8 @@ fn test_get_balance_with_genesis_balance() {
         decimals: U256::from(18),
         symbol: "USDT".to_owned(),
     });
-    let mock_genesis_native_amount = parse_units("2", 18).unwrap().into();
-    let mock_genesis_token_amount = parse_units("200", 18).unwrap().into();
+    let mock_genesis_native_amount: U256 = parse_units("2", 18).unwrap().into();
+    let mock_genesis_token_amount: U256 = parse_units("200", 18).unwrap().into();
 
     // user with (native) genesis balance
     assert_eq!(
@@ -197,13 +178,7 @@ fn test_get_balance_with_genesis_balance() {
 
 #[test]
 fn test_get_nonce_eth() {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-    };
+    let conf = Config::for_tests();
     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
 
     let mock_address = Address::from([1; 20]);
@@ -223,13 +198,7 @@ fn test_get_nonce_eth() {
 
 #[test]
 fn test_get_nonce_custom() {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-    };
+    let conf = Config::for_tests();
     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
 
     let mock_address = Address::from([2; 20]);
@@ -251,13 +220,7 @@ fn test_get_nonce_custom() {
 
 #[tokio::test]
 async fn test_get_user_withdraw_transactions() {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-    };
+    let conf = Config::for_tests();
     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
 
     let signer = PrivateKeySigner::random();
@@ -266,10 +229,11 @@ async fn test_get_user_withdraw_transactions() {
         123,
         CustomTxMsg::BurnTx(Burn {
             burn_id: FixedBytes::from([1u8; 32]),
-            network: Network::ETH,
+            chain_id: 1,
             token: Token::Native,
             amount: U256::from(100),
             calldata: None,
+            nonce: U256::from(0),
         }),
     )
     .await
@@ -305,13 +269,7 @@ async fn test_get_user_withdraw_transactions() {
 
 #[tokio::test]
 async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-    };
+    let conf = Config::for_tests();
     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf.clone(), RamKvStore::new());
     let mut tx_queue = TransactionQueue::new();
 
@@ -325,7 +283,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
         conf.clone().chain_id,
         CustomTxMsg::MintTx(Mint {
             tx_hash: tx_hash.clone(),
-            user_tx_hash: user_tx_hash.clone(),
+            user_tx_hash: format!("{}{}", user_tx_hash, 1),
             token:  Token::Erc20(ERC20 {
                 address: random_token_address,
                 decimals: U256::from(18),
@@ -333,6 +291,8 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
             }),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -347,7 +307,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
         conf.clone().chain_id,
         CustomTxMsg::MintTx(Mint {
             tx_hash: tx_hash.clone(),
-            user_tx_hash: user_tx_hash.clone(),
+            user_tx_hash: format!("{}{}", user_tx_hash, 2),
             token:  Token::Erc20(ERC20 {
                 address: random_token_address,
                 decimals: U256::from(18),
@@ -355,6 +315,8 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
             }),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -375,7 +337,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
         conf.clone().chain_id,
         CustomTxMsg::MintTx(Mint {
             tx_hash: tx_hash.clone(),
-            user_tx_hash: user_tx_hash.clone(),
+            user_tx_hash: format!("{}{}", user_tx_hash, 3),
 
             token:  Token::Erc20(ERC20 {
                 address: random_token_address,
@@ -384,6 +346,8 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
             }),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -398,7 +362,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
         conf.clone().chain_id,
         CustomTxMsg::MintTx(Mint {
             tx_hash: tx_hash.clone(),
-            user_tx_hash: user_tx_hash.clone(),
+            user_tx_hash: format!("{}{}", user_tx_hash, 4),
             token:  Token::Erc20(ERC20 {
                 address: random_token_address,
                 decimals: U256::from(18),
@@ -406,6 +370,8 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
             }),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -420,7 +386,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
         conf.clone().chain_id,
         CustomTxMsg::MintTx(Mint {
             tx_hash: tx_hash.clone(),
-            user_tx_hash: user_tx_hash.clone(),
+            user_tx_hash: format!("{}{}", user_tx_hash, 5),
             token:  Token::Erc20(ERC20 {
                 address: random_token_address,
                 decimals: U256::from(18),
@@ -428,6 +394,8 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
             }),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -449,7 +417,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
         conf.clone().chain_id,
         CustomTxMsg::MintTx(Mint {
             tx_hash: tx_hash.clone(),
-            user_tx_hash: user_tx_hash.clone(),
+            user_tx_hash: format!("{}{}", user_tx_hash, 6),
             token:  Token::Erc20(ERC20 {
                 address: random_token_address,
                 decimals: U256::from(18),
@@ -457,6 +425,8 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
             }),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -471,7 +441,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
         conf.clone().chain_id,
         CustomTxMsg::MintTx(Mint {
             tx_hash: tx_hash.clone(),
-            user_tx_hash: user_tx_hash.clone(),
+            user_tx_hash: format!("{}{}", user_tx_hash, 7),
             token:  Token::Erc20(ERC20 {
                 address: random_token_address,
                 decimals: U256::from(18),
@@ -479,6 +449,8 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
             }),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -493,7 +465,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
         conf.clone().chain_id,
         CustomTxMsg::MintTx(Mint {
             tx_hash: tx_hash.clone(),
-            user_tx_hash: user_tx_hash.clone(),
+            user_tx_hash: format!("{}{}", user_tx_hash, 8),
             token:  Token::Erc20(ERC20 {
                 address: random_token_address,
                 decimals: U256::from(18),
@@ -501,6 +473,8 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
             }),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -522,13 +496,7 @@ async fn test_get_transactions_per_second() -> Result<(), anyhow::Error> {
 
 #[test]
 fn test_get_allowance() {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-    };
+    let conf = Config::for_tests();
     let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
 
     let mock_user_address = Address::from([1; 20]);
@@ -561,3 +529,524 @@ fn test_get_allowance() {
         mock_erc20_amount
     );
 }
+
+#[tokio::test]
+async fn test_custom_tx_replay() -> Result<(), anyhow::Error> {
+    let conf = Config::for_tests();
+    let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf.clone(), RamKvStore::new());
+    let mut tx_queue = TransactionQueue::new();
+
+    let signer = PrivateKeySigner::random();
+    let user = Address::from([9; 20]);
+    let mint = |nonce: u64| Mint {
+        tx_hash: vec![nonce as u8; 32],
+        user_tx_hash: format!("0x{:x}", nonce),
+        token: Token::Native,
+        amount: U256::from(100),
+        address: user,
+        nonce: U256::from(nonce),
+        chain_id: 1,
+    };
+
+    let tx = CustomTx::create(&mut signer.clone(), conf.chain_id, CustomTxMsg::MintTx(mint(0)))
+        .await?;
+    tx_queue.enqueue(tx.clone());
+    assert_eq!(
+        tx_queue.next_custom_nonce(&chain, signer.address())?,
+        U256::from(1)
+    );
+
+    let timestamp: u64 = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let blk = chain.draft_block(&mut tx_queue, timestamp)?;
+    assert_eq!(blk.txs.len(), 1);
+    chain.push_block(blk)?;
+    assert_eq!(chain.get_custom_nonce(signer.address())?, U256::from(1));
+    assert_eq!(chain.get_balance(Token::Native, user)?, U256::from(100));
+
+    // The captured mint cannot be replayed into a later block
+    tx_queue.enqueue(tx.clone());
+    let blk = chain.draft_block(&mut tx_queue, timestamp)?;
+    assert!(blk.txs.is_empty());
+    let mut forged = blk.clone();
+    forged.txs.push((&tx).try_into()?);
+    assert!(chain.push_block(forged).is_err());
+
+    // Skipping a nonce is rejected as well
+    let tx = CustomTx::create(&mut signer.clone(), conf.chain_id, CustomTxMsg::MintTx(mint(2)))
+        .await?;
+    tx_queue.enqueue(tx);
+    assert!(chain.draft_block(&mut tx_queue, timestamp)?.txs.is_empty());
+
+    let tx = CustomTx::create(&mut signer.clone(), conf.chain_id, CustomTxMsg::MintTx(mint(1)))
+        .await?;
+    tx_queue.enqueue(tx);
+    let blk = chain.draft_block(&mut tx_queue, timestamp)?;
+    assert_eq!(blk.txs.len(), 1);
+    chain.push_block(blk)?;
+    assert_eq!(chain.get_custom_nonce(signer.address())?, U256::from(2));
+    assert_eq!(chain.get_balance(Token::Native, user)?, U256::from(200));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_double_mint_in_block() -> Result<(), anyhow::Error> {
+    let conf = Config::for_tests();
+    let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf.clone(), RamKvStore::new());
+
+    let signer = PrivateKeySigner::random();
+    let user = Address::from([9; 20]);
+    let user_tx_hash = "0xabcdef".to_string();
+    let mint = |nonce: u64| Mint {
+        tx_hash: vec![1; 32],
+        user_tx_hash: user_tx_hash.clone(),
+        token: Token::Native,
+        amount: U256::from(100),
+        address: user,
+        nonce: U256::from(nonce),
+        chain_id: 1,
+    };
+    let tx0 = CustomTx::create(&mut signer.clone(), conf.chain_id, CustomTxMsg::MintTx(mint(0)))
+        .await?;
+    let tx1 = CustomTx::create(&mut signer.clone(), conf.chain_id, CustomTxMsg::MintTx(mint(1)))
+        .await?;
+
+    // Two mints of the same deposit can't share a block
+    let blk = Block {
+        prev_hash: None,
+        index: 0,
+        txs: vec![(&tx0).try_into()?, (&tx1).try_into()?],
+        sig: None,
+        timestamp: 0,
+        base_fee_per_gas: INITIAL_BASE_FEE,
+        gas_used: 2 * CUSTOM_TX_GAS,
+        state_root: FixedBytes::ZERO,
+        transactions_root: FixedBytes::ZERO,
+        receipts_root: FixedBytes::ZERO,
+    };
+    assert!(chain.push_block(blk).is_err());
+    assert_eq!(chain.get_height()?, 0);
+    assert!(chain
+        .db
+        .get(Key::DepositedTransaction(user_tx_hash.clone()))?
+        .is_none());
+
+    let mut tx_queue = TransactionQueue::new();
+    tx_queue.enqueue(tx0.clone());
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    assert_eq!(blk.gas_used, CUSTOM_TX_GAS);
+    chain.push_block(blk.clone())?;
+    assert!(chain
+        .db
+        .get(Key::DepositedTransaction(user_tx_hash.clone()))?
+        .is_some());
+
+    // Nor can it be minted again in a later one
+    let blk2 = Block {
+        prev_hash: Some(blk.hash()?),
+        index: 1,
+        txs: vec![(&tx1).try_into()?],
+        sig: None,
+        timestamp: 0,
+        base_fee_per_gas: next_base_fee(INITIAL_BASE_FEE, CUSTOM_TX_GAS),
+        gas_used: CUSTOM_TX_GAS,
+        state_root: FixedBytes::ZERO,
+        transactions_root: FixedBytes::ZERO,
+        receipts_root: FixedBytes::ZERO,
+    };
+    assert!(chain.push_block(blk2.clone()).is_err());
+    assert_eq!(chain.get_balance(Token::Native, user)?, U256::from(100));
+
+    // The marker goes away with the block that wrote it
+    chain.pop_block()?;
+    assert!(chain
+        .db
+        .get(Key::DepositedTransaction(user_tx_hash.clone()))?
+        .is_none());
+    assert_eq!(chain.get_balance(Token::Native, user)?, U256::from(0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_double_burn_in_block() -> Result<(), anyhow::Error> {
+    let conf = Config::for_tests();
+    let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf.clone(), RamKvStore::new());
+
+    let signer = PrivateKeySigner::random();
+    chain.db.put(
+        Key::Balance(signer.address(), Token::Native),
+        Some(Value::U256(U256::from(1000))),
+    )?;
+    let burn_id = FixedBytes::from([5u8; 32]);
+    let burn = |nonce: u64| Burn {
+        burn_id,
+        chain_id: 1,
+        token: Token::Native,
+        amount: U256::from(100),
+        calldata: Some(crate::types::WithdrawCalldata::Eth {
+            address: signer.address(),
+        }),
+        nonce: U256::from(nonce),
+    };
+    let tx0 = CustomTx::create(&mut signer.clone(), conf.chain_id, CustomTxMsg::BurnTx(burn(0)))
+        .await?;
+    let tx1 = CustomTx::create(&mut signer.clone(), conf.chain_id, CustomTxMsg::BurnTx(burn(1)))
+        .await?;
+
+    let blk = Block {
+        prev_hash: None,
+        index: 0,
+        txs: vec![(&tx0).try_into()?, (&tx1).try_into()?],
+        sig: None,
+        timestamp: 0,
+        base_fee_per_gas: INITIAL_BASE_FEE,
+        gas_used: 2 * CUSTOM_TX_GAS,
+        state_root: FixedBytes::ZERO,
+        transactions_root: FixedBytes::ZERO,
+        receipts_root: FixedBytes::ZERO,
+    };
+    assert!(chain.push_block(blk).is_err());
+    assert!(chain.db.get(Key::BurnId(burn_id))?.is_none());
+
+    let mut tx_queue = TransactionQueue::new();
+    tx_queue.enqueue(tx0.clone());
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    assert_eq!(blk.gas_used, CUSTOM_TX_GAS);
+    chain.push_block(blk.clone())?;
+    assert!(chain.db.get(Key::BurnId(burn_id))?.is_some());
+
+    let blk2 = Block {
+        prev_hash: Some(blk.hash()?),
+        index: 1,
+        txs: vec![(&tx1).try_into()?],
+        sig: None,
+        timestamp: 0,
+        base_fee_per_gas: next_base_fee(INITIAL_BASE_FEE, CUSTOM_TX_GAS),
+        gas_used: CUSTOM_TX_GAS,
+        state_root: FixedBytes::ZERO,
+        transactions_root: FixedBytes::ZERO,
+        receipts_root: FixedBytes::ZERO,
+    };
+    assert!(chain.push_block(blk2).is_err());
+    assert_eq!(
+        chain.get_balance(Token::Native, signer.address())?,
+        U256::from(900)
+    );
+
+    chain.pop_block()?;
+    assert!(chain.db.get(Key::BurnId(burn_id))?.is_none());
+    assert_eq!(
+        chain.get_balance(Token::Native, signer.address())?,
+        U256::from(1000)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mint_by_non_owner() -> Result<(), anyhow::Error> {
+    let owner = PrivateKeySigner::random();
+    let conf = Config {
+        owner: Some(owner.address()),
+        ..Config::for_tests()
+    };
+    let chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
+
+    let mint = |signer: PrivateKeySigner| async move {
+        CustomTx::create(
+            &mut signer.clone(),
+            1387,
+            CustomTxMsg::MintTx(Mint {
+                tx_hash: vec![1; 32],
+                user_tx_hash: "0xabcdef".to_string(),
+                token: Token::Native,
+                amount: U256::from(100),
+                address: signer.address(),
+                nonce: U256::from(0),
+                chain_id: 1,
+            }),
+        )
+        .await
+    };
+    let forged = mint(PrivateKeySigner::random()).await?;
+    let mut tx_queue = TransactionQueue::new();
+    assert!(tx_queue
+        .add(&chain, forged.clone())
+        .unwrap_err()
+        .to_string()
+        .contains("not signed by the owner"));
+
+    // Blocks carrying it are refused too
+    tx_queue.enqueue(forged);
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    assert!(blk.txs.is_empty());
+    assert!(tx_queue.rejected()[0].1.contains("not signed by the owner"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsupported_network() -> Result<(), anyhow::Error> {
+    let conf = Config::for_tests();
+    let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf.clone(), RamKvStore::new());
+    let mut tx_queue = TransactionQueue::new();
+
+    let signer = PrivateKeySigner::random();
+    let tx = CustomTx::create(
+        &mut signer.clone(),
+        conf.chain_id,
+        CustomTxMsg::MintTx(Mint {
+            tx_hash: vec![1; 32],
+            user_tx_hash: "0xabcdef".to_string(),
+            token: Token::Native,
+            amount: U256::from(100),
+            address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 10,
+        }),
+    )
+    .await?;
+    assert!(tx_queue
+        .add(&chain, tx.clone())
+        .unwrap_err()
+        .to_string()
+        .contains("Network 10 is not supported"));
+
+    // Blocks of a sequencer bridging more networks are still valid
+    tx_queue.enqueue(tx);
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    assert_eq!(blk.txs.len(), 1);
+    chain.push_block(blk)?;
+    Ok(())
+}
+
+#[test]
+fn test_block_base_fee() -> Result<(), anyhow::Error> {
+    let conf = Config {
+        networks: vec![],
+        ..Config::for_tests()
+    };
+    let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
+    assert_eq!(chain.pending_base_fee()?, INITIAL_BASE_FEE);
+
+    let blk = chain.draft_block(&mut TransactionQueue::new(), 0)?;
+    assert_eq!(blk.base_fee_per_gas, INITIAL_BASE_FEE);
+    assert_eq!(blk.gas_used, 0);
+
+    let bad_fee = Block {
+        base_fee_per_gas: INITIAL_BASE_FEE + 1,
+        ..blk.clone()
+    };
+    assert!(chain.push_block(bad_fee).is_err());
+    let bad_gas = Block {
+        gas_used: 21_000,
+        ..blk.clone()
+    };
+    assert!(chain.push_block(bad_gas).is_err());
+    assert_eq!(chain.get_height()?, 0);
+
+    chain.push_block(blk)?;
+    // Empty blocks lower the base fee
+    assert_eq!(chain.pending_base_fee()?, next_base_fee(INITIAL_BASE_FEE, 0));
+    assert!(chain.pending_base_fee()? < INITIAL_BASE_FEE);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_state_root() -> Result<(), anyhow::Error> {
+    let conf = Config::for_tests();
+    let mut chain: Owshenchain<RamKvStore> = Owshenchain::new(conf.clone(), RamKvStore::new());
+    let empty_root = chain.get_state_root()?;
+
+    let signer = PrivateKeySigner::random();
+    let tx = CustomTx::create(
+        &mut signer.clone(),
+        conf.chain_id,
+        CustomTxMsg::MintTx(Mint {
+            tx_hash: vec![1; 32],
+            user_tx_hash: "0xabcdef".to_string(),
+            token: Token::Native,
+            amount: U256::from(100),
+            address: Address::from([9; 20]),
+            nonce: U256::from(0),
+            chain_id: 1,
+        }),
+    )
+    .await?;
+    let tx_hash = tx.hash()?;
+    let mut tx_queue = TransactionQueue::new();
+    tx_queue.enqueue(tx);
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    assert_ne!(blk.state_root, empty_root);
+
+    // Blocks claiming a different state are refused
+    let bad_root = Block {
+        state_root: empty_root,
+        ..blk.clone()
+    };
+    assert!(chain.push_block(bad_root).is_err());
+    assert_eq!(chain.get_height()?, 0);
+
+    // Nor can they misreport their transactions or receipts
+    assert_ne!(blk.transactions_root, FixedBytes::ZERO);
+    let bad_txs_root = Block {
+        transactions_root: FixedBytes::ZERO,
+        ..blk.clone()
+    };
+    assert!(chain.push_block(bad_txs_root).is_err());
+    let bad_receipts_root = Block {
+        receipts_root: blk.transactions_root,
+        ..blk.clone()
+    };
+    assert!(chain.push_block(bad_receipts_root).is_err());
+
+    chain.push_block(blk.clone())?;
+    assert_eq!(chain.get_state_root()?, blk.state_root);
+    let proof = chain.get_inclusion_proof(tx_hash)?;
+    assert!(proof.verify(tx_hash, &blk)?);
+    let receipt = chain.get_receipt(tx_hash)?;
+    assert!(receipt.success);
+    assert_eq!(receipt.logs.len(), 1);
+    assert_eq!(receipt.logs[0].topics[1], Address::from([9; 20]).into_word());
+
+    // Blocks that don't touch the state keep the root
+    let empty = chain.draft_block(&mut TransactionQueue::new(), 0)?;
+    assert_eq!(empty.state_root, blk.state_root);
+    chain.push_block(empty)?;
+
+    chain.pop_block()?;
+    chain.pop_block()?;
+    assert_eq!(chain.get_state_root()?, empty_root);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_draft_block_priority() -> Result<(), anyhow::Error> {
+    use alloy::consensus::{TxEip1559, TypedTransaction};
+    use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+
+    let conf = Config {
+        networks: vec![],
+        ..Config::for_tests()
+    };
+    let mut chain: Owshenchain<RamKvStore> =
+        Owshenchain::new(conf, RamKvStore::new()).with_block_limits(BlockLimits {
+            gas: 3 * 21_000,
+            size: MAX_BLOCK_SIZE,
+        });
+    let base_fee = chain.pending_base_fee()? as u128;
+
+    let transfer = |signer: &PrivateKeySigner, nonce: u64, tip: u128| {
+        let wallet = EthereumWallet::new(signer.clone());
+        let tx = TxEip1559 {
+            chain_id: 1387,
+            nonce,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::from([6; 20])),
+            max_priority_fee_per_gas: tip,
+            max_fee_per_gas: base_fee * 2 + tip,
+            ..Default::default()
+        };
+        async move {
+            OwshenTransaction::Eth(
+                <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+                    &wallet,
+                    TypedTransaction::Eip1559(tx),
+                )
+                .await
+                .unwrap(),
+            )
+        }
+    };
+    let (alice, bob, carol) = (
+        PrivateKeySigner::random(),
+        PrivateKeySigner::random(),
+        PrivateKeySigner::random(),
+    );
+    for signer in [&alice, &bob, &carol] {
+        chain.db.put(
+            Key::Balance(signer.address(), Token::Native),
+            Some(Value::U256(U256::from(10).pow(U256::from(18)))),
+        )?;
+    }
+
+    let alice_0 = transfer(&alice, 0, 1).await;
+    let alice_1 = transfer(&alice, 1, 5).await;
+    let bob_0 = transfer(&bob, 0, 3).await;
+    let carol_0 = transfer(&carol, 0, 0).await;
+    let mut tx_queue = TransactionQueue::new();
+    for tx in [&alice_0, &alice_1, &carol_0, &bob_0] {
+        tx_queue.add(&chain, tx.clone())?;
+    }
+
+    // Alice's better paying transaction has to wait for her first one, and
+    // Carol's doesn't fit in the block
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    let included = blk
+        .txs
+        .iter()
+        .map(|tx| {
+            let tx: OwshenTransaction = tx.try_into()?;
+            tx.hash()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        included,
+        vec![bob_0.hash()?, alice_0.hash()?, alice_1.hash()?]
+    );
+    assert_eq!(tx_queue.queue(), vec![carol_0.clone()]);
+    chain.push_block(blk)?;
+
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    assert_eq!(blk.txs.len(), 1);
+    assert!(tx_queue.queue().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_draft_block_rejection() -> Result<(), anyhow::Error> {
+    let conf = Config {
+        networks: vec![],
+        ..Config::for_tests()
+    };
+    let chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
+
+    let signer = PrivateKeySigner::random();
+    let burn = CustomTx::create(
+        &mut signer.clone(),
+        1387,
+        CustomTxMsg::BurnTx(Burn {
+            burn_id: FixedBytes::from([1u8; 32]),
+            chain_id: 1,
+            token: Token::Native,
+            amount: U256::from(100),
+            calldata: Some(WithdrawCalldata::Eth {
+                address: signer.address(),
+            }),
+            nonce: U256::from(0),
+        }),
+    )
+    .await?;
+    let mint = CustomTx::create(
+        &mut signer.clone(),
+        1387,
+        CustomTxMsg::MintTx(Mint {
+            tx_hash: vec![1; 32],
+            user_tx_hash: "0xabcdef".to_string(),
+            token: Token::Native,
+            amount: U256::from(100),
+            address: signer.address(),
+            nonce: U256::from(1),
+            chain_id: 1,
+        }),
+    )
+    .await?;
+    let mut tx_queue = TransactionQueue::new();
+    tx_queue.enqueue(burn.clone());
+    tx_queue.enqueue(mint.clone());
+
+    // The burn can't be paid for, the mint after it is kept for later
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    assert!(blk.txs.is_empty());
+    assert_eq!(tx_queue.rejected().len(), 1);
+    assert_eq!(tx_queue.rejected()[0].0, burn);
+    assert_eq!(tx_queue.queue(), vec![mint]);
+    Ok(())
+}
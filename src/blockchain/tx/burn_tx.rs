@@ -1,56 +1,74 @@
-use alloy::primitives::{Address, U256};
+use alloy::primitives::{keccak256, Address, FixedBytes, U256};
 use anyhow::Result;
 
 use crate::{
     blockchain::{Blockchain, Owshenchain},
     db::{Key, KvStore, Value},
     services::ContextKvStore,
-    types::{Burn, Token, WithdrawCalldata},
+    types::{Burn, Log, Token, WithdrawCalldata},
 };
 
-pub fn burn_tx<K: ContextKvStore>(_chain: &mut Owshenchain<K>, _data: Burn) -> Result<()> {
-    let address = match _data.calldata {
-        Some(WithdrawCalldata::Eth { address }) => address,
-        _ => return Err(anyhow::anyhow!("Invalid calldata!")),
-    };
+/// Burns `_data.amount` from the balance of `_from`, the signer of the burn.
+/// The calldata address is only the receiver of the funds on L1. The burn id
+/// points to `tx_hash`, so that the burn can be certified later on.
+pub fn burn_tx<K: ContextKvStore>(
+    _chain: &mut Owshenchain<K>,
+    tx_hash: FixedBytes<32>,
+    _from: Address,
+    _data: Burn,
+) -> Result<()> {
+    if !matches!(_data.calldata, Some(WithdrawCalldata::Eth { .. })) {
+        return Err(anyhow::anyhow!("Invalid calldata!"));
+    }
 
     if _chain.db.get(Key::BurnId(_data.burn_id.clone()))?.is_some() {
         return Err(anyhow::anyhow!("Burn id already used!"));
     }
 
-    let user_balance = _chain.get_balance(_data.token.clone(), address)?;
+    let user_balance = _chain.get_balance(_data.token.clone(), _from)?;
     if user_balance < _data.amount {
         return Err(anyhow::anyhow!("Insufficient balance!"));
     }
 
     _chain.db.put(
-        Key::Balance(address, _data.token.clone()),
+        Key::Balance(_from, _data.token.clone()),
         Some(Value::U256(user_balance - _data.amount)),
     )?;
+    _chain.db.put(
+        Key::BurnId(_data.burn_id),
+        Some(Value::U256(U256::from_be_bytes(tx_hash.0))),
+    )?;
 
     Ok(())
 }
 
+/// `Burn(address indexed from, bytes32 indexed burnId, uint256 amount,
+/// uint256 chainId)`, emitted by the burnt token
+pub fn burn_log(from: Address, burn: &Burn) -> Log {
+    let mut data = burn.amount.to_be_bytes::<32>().to_vec();
+    data.extend(U256::from(burn.chain_id).to_be_bytes::<32>());
+    Log {
+        address: burn.token.address(),
+        topics: vec![
+            keccak256("Burn(address,bytes32,uint256,uint256)"),
+            from.into_word(),
+            burn.burn_id,
+        ],
+        data,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloy::{primitives::FixedBytes, signers::local::PrivateKeySigner};
 
-    use crate::{
-        blockchain::Config, config::OWSHEN_CONTRACT, db::RamKvStore, genesis::GENESIS,
-        types::network::Network,
-    };
+    use crate::{blockchain::Config, db::RamKvStore};
 
     use super::*;
 
     #[test]
     fn test_burn_tx_success() {
-        let conf = Config {
-            chain_id: 1387,
-            owner: None,
-            genesis: GENESIS.clone(),
-            owshen: OWSHEN_CONTRACT,
-            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-        };
+        let conf = Config::for_tests();
 
         let mut chain = Owshenchain::new(conf, RamKvStore::new());
 
@@ -67,32 +85,61 @@ mod tests {
 
         let data = Burn {
             burn_id: FixedBytes::from([1u8; 32]),
-            network: Network::ETH,
+            chain_id: 1,
             token: Token::Native,
             amount: U256::from(100),
             calldata: Some(WithdrawCalldata::Eth { address }),
+            nonce: U256::from(0),
         };
 
         assert_eq!(
             chain.get_balance(Token::Native, address).unwrap(),
             U256::from(1000)
         );
-        burn_tx(&mut chain, data).unwrap();
+        burn_tx(&mut chain, FixedBytes::ZERO, address, data.clone()).unwrap();
         assert_eq!(
             chain.get_balance(Token::Native, address).unwrap(),
             U256::from(900)
         );
+        assert!(chain.db.get(Key::BurnId(data.burn_id)).unwrap().is_some());
     }
 
     #[test]
-    fn test_burn_tx_fail_already_used_burn_id() {
-        let conf = Config {
-            chain_id: 1387,
-            owner: None,
-            genesis: GENESIS.clone(),
-            owshen: OWSHEN_CONTRACT,
-            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
+    fn test_burn_tx_debits_signer() {
+        let conf = Config::for_tests();
+
+        let mut chain = Owshenchain::new(conf, RamKvStore::new());
+
+        let signer = PrivateKeySigner::random().address();
+        let victim = PrivateKeySigner::random().address();
+
+        chain
+            .db
+            .put(
+                Key::Balance(victim, Token::Native),
+                Some(Value::U256(U256::from(1000))),
+            )
+            .unwrap();
+
+        // Naming someone else as the receiver doesn't spend their balance
+        let data = Burn {
+            burn_id: FixedBytes::from([1u8; 32]),
+            chain_id: 1,
+            token: Token::Native,
+            amount: U256::from(100),
+            calldata: Some(WithdrawCalldata::Eth { address: victim }),
+            nonce: U256::from(0),
         };
+        assert!(burn_tx(&mut chain, FixedBytes::ZERO, signer, data).is_err());
+        assert_eq!(
+            chain.get_balance(Token::Native, victim).unwrap(),
+            U256::from(1000)
+        );
+    }
+
+    #[test]
+    fn test_burn_tx_fail_already_used_burn_id() {
+        let conf = Config::for_tests();
 
         let mut chain = Owshenchain::new(conf, RamKvStore::new());
 
@@ -109,17 +156,18 @@ mod tests {
 
         let data = Burn {
             burn_id: FixedBytes::from([1u8; 32]),
-            network: Network::ETH,
+            chain_id: 1,
             token: Token::Native,
             amount: U256::from(100),
             calldata: Some(WithdrawCalldata::Eth { address }),
+            nonce: U256::from(0),
         };
 
         assert_eq!(
             chain.get_balance(Token::Native, address).unwrap(),
             U256::from(1000)
         );
-        burn_tx(&mut chain, data.clone()).unwrap();
+        burn_tx(&mut chain, FixedBytes::ZERO, address, data.clone()).unwrap();
         assert_eq!(
             chain.get_balance(Token::Native, address).unwrap(),
             U256::from(900)
@@ -129,7 +177,7 @@ mod tests {
             .db
             .put(Key::BurnId(data.burn_id.clone()), Some(Value::Void))
             .unwrap();
-        assert!(burn_tx(&mut chain, data).is_err());
+        assert!(burn_tx(&mut chain, FixedBytes::ZERO, address, data).is_err());
         assert_eq!(
             chain.get_balance(Token::Native, address).unwrap(),
             U256::from(900)
@@ -138,13 +186,7 @@ mod tests {
 
     #[test]
     fn test_burn_tx_insufficient_balance() {
-        let conf = Config {
-            chain_id: 1387,
-            owner: None,
-            genesis: GENESIS.clone(),
-            owshen: OWSHEN_CONTRACT,
-            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-        };
+        let conf = Config::for_tests();
 
         let mut chain = Owshenchain::new(conf, RamKvStore::new());
 
@@ -161,16 +203,17 @@ mod tests {
 
         let data = Burn {
             burn_id: FixedBytes::from([1u8; 32]),
-            network: Network::ETH,
+            chain_id: 1,
             token: Token::Native,
             amount: U256::from(1001),
             calldata: Some(WithdrawCalldata::Eth { address }),
+            nonce: U256::from(0),
         };
 
         assert_eq!(
             chain.get_balance(Token::Native, address).unwrap(),
             U256::from(1000)
         );
-        assert!(burn_tx(&mut chain, data).is_err());
+        assert!(burn_tx(&mut chain, FixedBytes::ZERO, address, data).is_err());
     }
 }
@@ -5,15 +5,14 @@ use alloy::primitives::Uint;
 use alloy::rlp::bytes::buf::Chain;
 use alloy::rlp::Decodable;
 use alloy::{
-    consensus::TxEnvelope,
-    primitives::{keccak256, Address, TxKind, U256},
+    primitives::{keccak256, Address, FixedBytes, TxKind, U256},
     sol_types::SolValue,
 };
 use alloy_sol_types::abi::token;
 use anyhow::{anyhow, Error, Result};
 use evm::{Capture, Context, ExitReason, Runtime};
 
-use crate::types::{Token, ERC20};
+use crate::types::{Log, Token, ERC20};
 use crate::{
     blockchain::{Blockchain, Owshenchain},
     db::{Key, KvStore, Value},
@@ -36,28 +35,25 @@ pub enum Erc20Operation {
     },
 }
 
-pub fn extract_erc20_transfer(tx: &TxEnvelope) -> Result<Option<Erc20Operation>> {
-    let tx = tx
-        .as_eip1559()
-        .ok_or(anyhow!("Only EIP-1559 is supported!"))?;
-
-    if tx.tx().value > U256::from(0) {
+pub fn extract_erc20_transfer(value: U256, input: &[u8]) -> Result<Option<Erc20Operation>> {
+    // Plain transfers of the native coin, including those of nothing
+    if value > U256::from(0) || input.is_empty() {
         return Ok(None);
     }
 
-    if tx.tx().input.len() < 4 {
+    if input.len() < 4 {
         return Err(anyhow!("Unknown transaction input!"));
     }
 
-    match &tx.tx().input[..4] {
+    match &input[..4] {
         &[169, 5, 156, 187] => {
             let (receiver, value): (Address, alloy::primitives::U256) =
-                SolValue::abi_decode(&tx.tx().input[4..], true)?;
+                SolValue::abi_decode(&input[4..], true)?;
             Ok(Some(Erc20Operation::Transfer { receiver, value }))
         }
         &[35, 184, 114, 221] => {
             let (from, receiver, value): (Address, Address, alloy::primitives::U256) =
-                SolValue::abi_decode(&tx.tx().input[4..], true)?;
+                SolValue::abi_decode(&input[4..], true)?;
             Ok(Some(Erc20Operation::TransferFrom {
                 from,
                 receiver,
@@ -66,7 +62,7 @@ pub fn extract_erc20_transfer(tx: &TxEnvelope) -> Result<Option<Erc20Operation>>
         }
         &[9, 94, 167, 179] => {
             let (spender, value): (Address, alloy::primitives::U256) =
-                SolValue::abi_decode(&tx.tx().input[4..], true)?;
+                SolValue::abi_decode(&input[4..], true)?;
             Ok(Some(Erc20Operation::Approve { spender, value }))
         }
         _ => Err(anyhow!("Unknown function signature")),
@@ -101,13 +97,6 @@ pub fn handle_erc20_transfer<K: ContextKvStore>(
             Some(Value::U256(receiver_balance + value)),
         )?;
 
-        let current_nonce = chain.get_eth_nonce(msg_sender)?;
-        let incremented_nonce = current_nonce + U256::from(1);
-        chain.db.put(
-            Key::NonceEth(msg_sender),
-            Some(Value::U256(incremented_nonce)),
-        )?;
-
         Ok(())
     } else {
         Err(anyhow!("Insufficient balance."))
@@ -157,13 +146,6 @@ pub fn handle_erc20_transfer_from<K: ContextKvStore>(
         Some(Value::U256(receiver_balance + value)),
     )?;
 
-    let current_nonce = chain.get_eth_nonce(msg_sender)?;
-    let incremented_nonce = current_nonce + U256::from(1);
-    chain.db.put(
-        Key::NonceEth(msg_sender),
-        Some(Value::U256(incremented_nonce)),
-    )?;
-
     Ok(())
 }
 
@@ -173,7 +155,7 @@ pub fn handle_erc20_approve<K: ContextKvStore>(
     spender: Address,
     value: Uint<256, 4>,
     token: Address,
-) -> Result<()> {
+) -> Result<U256> {
     let token_decimals = chain.get_token_decimal(token)?;
     let token_symbol = chain.get_token_symbol(token)?;
     let tx_token = Token::Erc20(ERC20 {
@@ -188,12 +170,36 @@ pub fn handle_erc20_approve<K: ContextKvStore>(
         Some(Value::U256(allowance + value)),
     )?;
 
-    let current_nonce = chain.get_eth_nonce(msg_sender)?;
-    let incremented_nonce = current_nonce + U256::from(1);
-    chain.db.put(
-        Key::NonceEth(msg_sender),
-        Some(Value::U256(incremented_nonce)),
-    )?;
+    Ok(allowance + value)
+}
 
-    Ok(())
+fn address_topic(address: Address) -> FixedBytes<32> {
+    address.into_word()
+}
+
+/// `Transfer(address indexed from, address indexed to, uint256 value)`
+pub fn transfer_log(token: Address, from: Address, to: Address, value: U256) -> Log {
+    Log {
+        address: token,
+        topics: vec![
+            keccak256("Transfer(address,address,uint256)"),
+            address_topic(from),
+            address_topic(to),
+        ],
+        data: value.to_be_bytes::<32>().to_vec(),
+    }
+}
+
+/// `Approval(address indexed owner, address indexed spender, uint256 value)`,
+/// with the allowance the approval resulted in
+pub fn approval_log(token: Address, owner: Address, spender: Address, allowance: U256) -> Log {
+    Log {
+        address: token,
+        topics: vec![
+            keccak256("Approval(address,address,uint256)"),
+            address_topic(owner),
+            address_topic(spender),
+        ],
+        data: allowance.to_be_bytes::<32>().to_vec(),
+    }
 }
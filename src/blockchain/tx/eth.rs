@@ -2,7 +2,10 @@ use std::{ops::Add, rc::Rc, sync::Arc};
 use tokio::sync::Mutex;
 
 use alloy::{
-    consensus::{Transaction, TxEip1559, TxEnvelope, TypedTransaction},
+    consensus::{
+        Transaction, TxEip1559, TxEip2930, TxEip4844, TxEip4844Variant, TxEnvelope, TxLegacy,
+        TypedTransaction,
+    },
     network::{Ethereum, EthereumWallet, NetworkWallet},
     primitives::{keccak256, Address, Bytes, TxKind, Uint, B256, U256},
     rlp::Decodable,
@@ -14,155 +17,260 @@ use alloy_sol_types::abi::encode;
 
 use anyhow::{anyhow, Result};
 
-use evm::{Capture, ExitReason, Runtime};
-
+#[cfg(test)]
+use crate::{blockchain::Config, db::RamKvStore};
 use crate::{
-    blockchain::{tx::erc20::*, Blockchain, Config, Owshenchain, TransactionQueue},
-    config,
-    db::{Key, KvStore, RamKvStore, Value},
-    genesis::GENESIS,
+    blockchain::{
+        gas::*,
+        ovm::{Execution, Ovm, TxContext},
+        tx::erc20::*,
+        tx::{check_nonce, NonceError, TxOutcome},
+        BlockContext, Blockchain, Owshenchain, TransactionQueue,
+    },
+    db::{Key, KvStore, Value},
     services::{Context, ContextKvStore},
     types::{Token, ERC20},
 };
 
+/// The parts of an Ethereum transaction its execution depends on
+#[derive(Debug, Clone)]
+pub struct EthMessage {
+    pub to: TxKind,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub gas_limit: u64,
+    pub gas_price: U256,
+    pub access_list: AccessList,
+}
+
+/// Legacy (EIP-155), access list (EIP-2930) and dynamic fee (EIP-1559)
+/// transactions are executed, other envelopes are refused
+pub fn check_tx_type(tx: &TxEnvelope) -> Result<()> {
+    match tx {
+        TxEnvelope::Legacy(_) | TxEnvelope::Eip2930(_) | TxEnvelope::Eip1559(_) => Ok(()),
+        TxEnvelope::Eip4844(_) => Err(anyhow!("Blob transactions (EIP-4844) are not supported!")),
+        _ => Err(anyhow!(
+            "Transaction type 0x{:x} is not supported!",
+            tx.ty()
+        )),
+    }
+}
+
+/// Price per gas `tx` pays in a block with the given base fee. Legacy and
+/// EIP-2930 transactions pay their gas price in full.
+pub fn effective_gas_price(tx: &TxEnvelope, base_fee: u64) -> u128 {
+    match tx.max_priority_fee_per_gas() {
+        Some(max_priority_fee_per_gas) => tx
+            .max_fee_per_gas()
+            .min(base_fee as u128 + max_priority_fee_per_gas),
+        None => tx.max_fee_per_gas(),
+    }
+}
+
 pub fn eth<K: ContextKvStore>(
     _chain: &mut Owshenchain<K>,
     _msg_sender: Address,
     _tx: &TxEnvelope,
-) -> Result<()> {
+    block: &BlockContext,
+) -> Result<TxOutcome> {
     log::info!("Ethereum transaction, by {}!", _msg_sender);
-    let tx = _tx
-        .as_eip1559()
-        .ok_or(anyhow!("Only EIP1559 transactions are supported!"))?;
-    match tx.tx().to {
+    check_tx_type(_tx)?;
+
+    let current_nonce = _chain.get_eth_nonce(_msg_sender)?;
+    check_nonce(_msg_sender, current_nonce, U256::from(_tx.nonce()))?;
+
+    if _tx.max_fee_per_gas() < block.base_fee as u128 {
+        return Err(anyhow!("Max fee per gas is lower than the base fee!"));
+    }
+    if let Some(max_priority_fee_per_gas) = _tx.max_priority_fee_per_gas() {
+        if max_priority_fee_per_gas > _tx.max_fee_per_gas() {
+            return Err(anyhow!(
+                "Max priority fee per gas is higher than the max fee!"
+            ));
+        }
+    }
+
+    let msg = EthMessage {
+        to: _tx.to(),
+        value: _tx.value(),
+        input: _tx.input().to_vec(),
+        gas_limit: _tx.gas_limit() as u64,
+        gas_price: U256::from(effective_gas_price(_tx, block.base_fee)),
+        access_list: _tx.access_list().cloned().unwrap_or_default(),
+    };
+    let outcome = execute_message(_chain, _msg_sender, &msg, block)?;
+
+    _chain.db.put(
+        Key::NonceEth(_msg_sender),
+        Some(Value::U256(current_nonce + U256::from(1))),
+    )?;
+    Ok(outcome)
+}
+
+/// Executes `msg` on behalf of `from` and charges its fee. Invalid messages
+/// are refused, failing ones keep nothing but their fee. Nonces are left to
+/// the caller.
+pub fn execute_message<K: ContextKvStore>(
+    chain: &mut Owshenchain<K>,
+    from: Address,
+    msg: &EthMessage,
+    block: &BlockContext,
+) -> Result<TxOutcome> {
+    if msg.gas_limit > BLOCK_GAS_LIMIT {
+        return Err(anyhow!("Gas limit exceeds the block gas limit!"));
+    }
+    let intrinsic = intrinsic_gas(&msg.input, msg.to.is_create(), &msg.access_list);
+    if msg.gas_limit < intrinsic {
+        return Err(anyhow!("Intrinsic gas too low!"));
+    }
+    let max_fee = U256::from(msg.gas_limit) * msg.gas_price;
+    if chain.get_balance(Token::Native, from)? < max_fee + msg.value {
+        return Err(anyhow!("Insufficient balance to pay for gas!"));
+    }
+
+    let tx_ctx = TxContext {
+        origin: from,
+        gas_price: msg.gas_price,
+        gas_limit: msg.gas_limit,
+        access_list: msg.access_list.clone(),
+    };
+    let outcome = match msg.to {
         TxKind::Create => {
-            return Err(anyhow!("Contract creation is not supported."));
-            // let bytecode = tx.tx().input.to_vec();
-            // let mut ovm = Ovm::new(chain);
-            // let ctx = Context {
-            //     address: H160::random(),
-            //     apparent_value: U256::from(0),
-            //     caller: H160::random(),
-            // };
-            // let mut runtime = Runtime::new(Rc::new(bytecode), Rc::new(vec![]), ctx, 10000, 10000);
-            // match runtime.run(&mut ovm) {
-            //     Capture::Exit(e) => match e {
-            //         ExitReason::Succeed(_s) => {
-            //             let contract_code = runtime.machine().return_value();
-            //             let contract_address = Address::from_slice(
-            //                 &keccak256((by, chain.get_eth_nonce(by)?).abi_encode()).0[12..],
-            //             );
-            //             chain.db.put(
-            //                 Key::ContractCode(contract_address),
-            //                 Some(Value::VecU8(contract_code)),
-            //             )?;
-            //             println!("Contract deployed on {}!", contract_address);
-            //         }
-            //         _ => {
-            //             return Err(anyhow!("Failed!"));
-            //         }
-            //     },
-            //     Capture::Trap(_) => {
-            //         return Err(anyhow!("Trapped!"));
-            //     }
-            // }
+            let mut ovm = Ovm::new(chain, block.clone(), tx_ctx);
+            let execution = ovm.deploy(from, msg.value, msg.input.clone())?;
+            if let Ok(contract_address) = &execution.result {
+                log::info!("Contract deployed on {}!", contract_address);
+            }
+            execution.into()
         }
         TxKind::Call(to) => {
-            let bytecode = _chain.db.get(Key::ContractCode(to))?;
-            if let Some(bytecode) = bytecode {
-                return Err(anyhow!("Contract calls are not supported."));
-
-                // let bytecode = bytecode.as_vec_u8()?;
-                // let mut ovm = Ovm::new(chain);
-                // let ctx = Context {
-                //     address: H160::random(),
-                //     apparent_value: U256::from(0),
-                //     caller: H160::random(),
-                // };
-                // let mut runtime = Runtime::new(
-                //     Rc::new(bytecode),
-                //     Rc::new(tx.tx().input.to_vec()),
-                //     ctx,
-                //     10000,
-                //     10000,
-                // );
-                // match runtime.run(&mut ovm) {
-                //     Capture::Exit(e) => match e {
-                //         ExitReason::Succeed(_s) => {
-                //             let _ret_value = runtime.machine().return_value();
-                //         }
-                //         _ => {
-                //             return Err(anyhow!("Failed!"));
-                //         }
-                //     },
-                //     Capture::Trap(_) => {
-                //         return Err(anyhow!("Trapped!"));
-                //     }
-                // }
+            if chain.db.get(Key::ContractCode(to))?.is_some() {
+                let mut ovm = Ovm::new(chain, block.clone(), tx_ctx);
+                ovm.execute(from, to, msg.value, msg.input.clone())?.into()
             } else {
-                let transaction = extract_erc20_transfer(&_tx)?;
-                match transaction {
-                    Some(Erc20Operation::Transfer { receiver, value }) => {
-                        handle_erc20_transfer(_chain, _msg_sender, receiver, value, to)?;
-                    }
-                    Some(Erc20Operation::TransferFrom {
-                        from,
-                        receiver,
-                        value,
-                    }) => {
-                        handle_erc20_transfer_from(_chain, _msg_sender, from, receiver, value, to)?
-                    }
-                    Some(Erc20Operation::Approve { spender, value }) => {
-                        handle_erc20_approve(_chain, _msg_sender, spender, value, to)?
-                    }
-                    None => {
-                        let value = tx.tx().value();
-                        let sender_balance = _chain.get_balance(Token::Native, _msg_sender)?;
-
-                        if sender_balance >= value {
-                            _chain.db.put(
-                                Key::Balance(_msg_sender, Token::Native),
-                                Some(Value::U256(sender_balance - value)),
-                            )?;
-
-                            let privious_receiver_balance =
-                                _chain.get_balance(Token::Native, to)?;
-
-                            _chain.db.put(
-                                Key::Balance(to, Token::Native),
-                                Some(Value::U256(privious_receiver_balance + value)),
-                            )?;
-                            let current_nonce = _chain.get_eth_nonce(_msg_sender)?;
-                            let incremented_nonce = current_nonce + U256::from(1);
-                            _chain.db.put(
-                                Key::NonceEth(_msg_sender),
-                                Some(Value::U256(incremented_nonce)),
-                            )?;
-                        } else {
-                            return Err(anyhow!("Insufficient balance."));
-                        }
-                    }
-                }
+                execute_builtin(chain, from, to, msg, intrinsic)
             }
         }
+    };
+
+    charge_fee(chain, from, block.coinbase, outcome.gas_used, msg.gas_price)?;
+    Ok(outcome)
+}
+
+impl<T> From<Execution<T>> for TxOutcome {
+    fn from(execution: Execution<T>) -> Self {
+        TxOutcome {
+            gas_used: execution.gas_used,
+            logs: execution.logs,
+            error: execution.result.err().map(|e| e.to_string()),
+        }
     }
-    Ok(())
 }
 
+/// Native transfers and the built-in ERC-20 operations on accounts without
+/// code, charged a fixed amount of gas on top of the intrinsic cost
+fn execute_builtin<K: ContextKvStore>(
+    chain: &mut Owshenchain<K>,
+    from: Address,
+    to: Address,
+    msg: &EthMessage,
+    intrinsic: u64,
+) -> TxOutcome {
+    let transaction = extract_erc20_transfer(msg.value, &msg.input);
+    let gas_used = intrinsic
+        + match transaction {
+            Ok(Some(Erc20Operation::Transfer { .. })) => ERC20_TRANSFER_GAS,
+            Ok(Some(Erc20Operation::TransferFrom { .. })) => ERC20_TRANSFER_FROM_GAS,
+            Ok(Some(Erc20Operation::Approve { .. })) => ERC20_APPROVE_GAS,
+            _ => 0,
+        };
+    if gas_used > msg.gas_limit {
+        return TxOutcome {
+            gas_used: msg.gas_limit,
+            logs: Vec::new(),
+            error: Some("Out of gas!".into()),
+        };
+    }
+
+    let result = chain.atomic(|chain| match transaction? {
+        Some(Erc20Operation::Transfer { receiver, value }) => {
+            handle_erc20_transfer(chain, from, receiver, value, to)?;
+            Ok(vec![transfer_log(to, from, receiver, value)])
+        }
+        Some(Erc20Operation::TransferFrom {
+            from: owner,
+            receiver,
+            value,
+        }) => {
+            handle_erc20_transfer_from(chain, from, owner, receiver, value, to)?;
+            Ok(vec![transfer_log(to, owner, receiver, value)])
+        }
+        Some(Erc20Operation::Approve { spender, value }) => {
+            let allowance = handle_erc20_approve(chain, from, spender, value, to)?;
+            Ok(vec![approval_log(to, from, spender, allowance)])
+        }
+        None => {
+            let value = msg.value;
+            let sender_balance = chain.get_balance(Token::Native, from)?;
+
+            if sender_balance >= value {
+                chain.db.put(
+                    Key::Balance(from, Token::Native),
+                    Some(Value::U256(sender_balance - value)),
+                )?;
+
+                let privious_receiver_balance = chain.get_balance(Token::Native, to)?;
+
+                chain.db.put(
+                    Key::Balance(to, Token::Native),
+                    Some(Value::U256(privious_receiver_balance + value)),
+                )?;
+                Ok(Vec::new())
+            } else {
+                Err(anyhow!("Insufficient balance."))
+            }
+        }
+    });
+
+    match result {
+        Ok(logs) => TxOutcome {
+            gas_used,
+            logs,
+            error: None,
+        },
+        Err(e) => TxOutcome {
+            gas_used,
+            logs: Vec::new(),
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
 fn setup_mock_chain() -> Owshenchain<RamKvStore> {
     let mock_db = RamKvStore::new();
     return Owshenchain {
         db: mock_db.clone(),
-        config: Config {
-            chain_id: 1387,
-            owner: None,
-            genesis: GENESIS.clone(),
-            owshen: config::OWSHEN_CONTRACT,
-            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-        },
+        block_limits: BlockLimits::default(),
+        config: Config::for_tests(),
     };
 }
 
+#[cfg(test)]
+const FUNDS: u64 = 1_000_000_000_000_000_000;
+
+/// Gives `address` enough native tokens to pay for its transactions
+#[cfg(test)]
+fn fund(chain: &mut Owshenchain<RamKvStore>, address: Address) {
+    chain
+        .db
+        .put(
+            Key::Balance(address, Token::Native),
+            Some(Value::U256(U256::from(FUNDS))),
+        )
+        .unwrap();
+}
+
 #[tokio::test]
 async fn test_erc20_transfer() {
     let mut chain = setup_mock_chain();
@@ -201,7 +309,7 @@ async fn test_erc20_transfer() {
 
     let tx = TxEip1559 {
         nonce: 0,
-        gas_limit: 21_000,
+        gas_limit: 100_000,
         to: TxKind::Call(token_contract),
         value: Uint::<256, 4>::from(0),
         input: Bytes::from(data.clone()),
@@ -223,8 +331,20 @@ async fn test_erc20_transfer() {
 
     let pre_tx_nonce = chain.get_eth_nonce(msg_sender).unwrap();
 
-    let result = eth(&mut chain, msg_sender, &signed_tx);
-    assert!(result.is_ok());
+    fund(&mut chain, msg_sender);
+    let outcome = eth(&mut chain, msg_sender, &signed_tx, &BlockContext::default()).unwrap();
+    assert!(outcome.is_success());
+    assert_eq!(outcome.logs.len(), 1);
+    assert_eq!(outcome.logs[0].address, token_contract);
+    assert_eq!(
+        outcome.logs[0].topics,
+        vec![
+            keccak256("Transfer(address,address,uint256)"),
+            msg_sender.into_word(),
+            receiver.into_word(),
+        ]
+    );
+    assert_eq!(outcome.logs[0].data, transaction_value.abi_encode());
 
     let sender_post_transaction_balance = chain.get_balance(tx_token.clone(), msg_sender).unwrap();
     let receiver_post_transaction_balance = chain.get_balance(tx_token.clone(), receiver).unwrap();
@@ -279,7 +399,7 @@ async fn test_erc20_approve() {
 
     let tx = TxEip1559 {
         nonce: 0,
-        gas_limit: 21_000,
+        gas_limit: 100_000,
         to: TxKind::Call(token_contract),
         value: Uint::<256, 4>::from(0),
         input: Bytes::from(data.clone()),
@@ -301,7 +421,8 @@ async fn test_erc20_approve() {
 
     let pre_tx_nonce = chain.get_eth_nonce(owner).unwrap();
 
-    let result = eth(&mut chain, owner, &signed_tx);
+    fund(&mut chain, owner);
+    let result = eth(&mut chain, owner, &signed_tx, &BlockContext::default());
 
     assert!(result.is_ok());
 
@@ -370,7 +491,7 @@ async fn test_erc20_transfer_from() {
 
     let tx = TxEip1559 {
         nonce: 0,
-        gas_limit: 21_000,
+        gas_limit: 100_000,
         to: TxKind::Call(token_contract),
         value: Uint::<256, 4>::from(0),
         input: Bytes::from(data.clone()),
@@ -396,7 +517,8 @@ async fn test_erc20_transfer_from() {
             .await
             .unwrap();
 
-    let result = eth(&mut chain, msg_sender, &signed_tx);
+    fund(&mut chain, msg_sender);
+    let result = eth(&mut chain, msg_sender, &signed_tx, &BlockContext::default());
     assert!(result.is_ok());
     let post_tx_nonce = chain.get_eth_nonce(msg_sender).unwrap();
 
@@ -432,12 +554,10 @@ async fn test_eth_transfer() {
         <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
 
     let receiver = Address::from([3; 20]);
+    let coinbase = Address::from([9; 20]);
     let transaction_value = Uint::<256, 4>::from(1000);
 
-    let _ = chain.db.put(
-        Key::Balance(msg_sender, Token::Native),
-        Some(Value::U256(U256::from(100000))),
-    );
+    fund(&mut chain, msg_sender);
 
     let from_pre_transaction_balance = chain.get_balance(Token::Native, msg_sender).unwrap();
     let receiver_pre_transaction_balance = chain.get_balance(Token::Native, receiver).unwrap();
@@ -452,10 +572,7 @@ async fn test_eth_transfer() {
         chain_id: chain.config.chain_id,
         max_priority_fee_per_gas: 3_000_000,
         max_fee_per_gas: 300_000_000,
-        access_list: AccessList(vec![AccessListItem {
-            address: Address::ZERO,
-            storage_keys: vec![B256::ZERO],
-        }]),
+        access_list: AccessList::default(),
     };
 
     let typed_tx = TypedTransaction::Eip1559(tx);
@@ -465,20 +582,427 @@ async fn test_eth_transfer() {
             .await
             .unwrap();
 
-    let result = eth(&mut chain, msg_sender, &signed_tx);
-    assert!(result.is_ok());
+    let block = BlockContext {
+        coinbase,
+        ..Default::default()
+    };
+    let result = eth(&mut chain, msg_sender, &signed_tx, &block);
+    assert_eq!(result.unwrap().gas_used, 21_000);
 
     let from_post_transaction_balance = chain.get_balance(Token::Native, msg_sender).unwrap();
     let receiver_post_transaction_balance = chain.get_balance(Token::Native, receiver).unwrap();
     let post_tx_nonce = chain.get_eth_nonce(msg_sender).unwrap();
 
+    // The base fee is zero, the sender only pays the priority fee
+    let fee = U256::from(21_000u64 * 3_000_000);
     assert_eq!(
         from_post_transaction_balance,
-        from_pre_transaction_balance - transaction_value
+        from_pre_transaction_balance - transaction_value - fee
     );
+    assert_eq!(chain.get_balance(Token::Native, coinbase).unwrap(), fee);
     assert_eq!(
         receiver_post_transaction_balance,
         receiver_pre_transaction_balance + transaction_value
     );
     assert_eq!(post_tx_nonce, pre_tx_nonce + U256::from(1));
 }
+
+#[tokio::test]
+async fn test_eth_zero_value_transfer() {
+    let mut chain = setup_mock_chain();
+    let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+    let msg_sender: Address =
+        <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+    let receiver = Address::from([3; 20]);
+    fund(&mut chain, msg_sender);
+
+    let tx = TxEip1559 {
+        nonce: 0,
+        gas_limit: 21_000,
+        to: TxKind::Call(receiver),
+        value: U256::ZERO,
+        input: Bytes::new(),
+        chain_id: chain.config.chain_id,
+        max_priority_fee_per_gas: 3_000_000,
+        max_fee_per_gas: 300_000_000,
+        access_list: AccessList::default(),
+    };
+    let signed_tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+        &wallet,
+        TypedTransaction::Eip1559(tx),
+    )
+    .await
+    .unwrap();
+
+    let outcome = eth(&mut chain, msg_sender, &signed_tx, &BlockContext::default()).unwrap();
+    assert_eq!(outcome.error, None);
+    assert_eq!(outcome.gas_used, 21_000);
+    assert_eq!(
+        chain.get_balance(Token::Native, receiver).unwrap(),
+        U256::ZERO
+    );
+    assert_eq!(chain.get_eth_nonce(msg_sender).unwrap(), U256::from(1));
+}
+
+#[tokio::test]
+async fn test_eth_transfer_nonce_validation() {
+    let mut chain = setup_mock_chain();
+    let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+    let msg_sender: Address =
+        <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+
+    let receiver = Address::from([3; 20]);
+
+    fund(&mut chain, msg_sender);
+
+    let mut signed_txs = Vec::new();
+    for nonce in [0, 2] {
+        let tx = TxEip1559 {
+            nonce,
+            gas_limit: 21_000,
+            to: TxKind::Call(receiver),
+            value: Uint::<256, 4>::from(1000),
+            input: Bytes::new(),
+            chain_id: chain.config.chain_id,
+            max_priority_fee_per_gas: 3_000_000,
+            max_fee_per_gas: 300_000_000,
+            access_list: AccessList::default(),
+        };
+        signed_txs.push(
+            <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+                &wallet,
+                TypedTransaction::Eip1559(tx),
+            )
+            .await
+            .unwrap(),
+        );
+    }
+
+    assert!(eth(
+        &mut chain,
+        msg_sender,
+        &signed_txs[0],
+        &BlockContext::default()
+    )
+    .is_ok());
+
+    // Replaying the very same signed transaction must fail
+    let err = eth(
+        &mut chain,
+        msg_sender,
+        &signed_txs[0],
+        &BlockContext::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<NonceError>(),
+        Some(NonceError::TooLow { .. })
+    ));
+
+    let err = eth(
+        &mut chain,
+        msg_sender,
+        &signed_txs[1],
+        &BlockContext::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<NonceError>(),
+        Some(NonceError::TooHigh { .. })
+    ));
+
+    assert_eq!(
+        chain.get_balance(Token::Native, msg_sender).unwrap(),
+        U256::from(FUNDS - 1000 - 21_000 * 3_000_000)
+    );
+    assert_eq!(
+        chain.get_balance(Token::Native, receiver).unwrap(),
+        U256::from(1000)
+    );
+    assert_eq!(chain.get_eth_nonce(msg_sender).unwrap(), U256::from(1));
+}
+
+#[cfg(test)]
+async fn sign_eip1559(
+    wallet: &EthereumWallet,
+    chain_id: u64,
+    nonce: u64,
+    to: TxKind,
+    value: U256,
+    input: Vec<u8>,
+) -> TxEnvelope {
+    let tx = TxEip1559 {
+        nonce,
+        gas_limit: 1_000_000,
+        to,
+        value,
+        input: Bytes::from(input),
+        chain_id,
+        max_priority_fee_per_gas: 3_000_000,
+        max_fee_per_gas: 300_000_000,
+        access_list: AccessList::default(),
+    };
+    <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+        wallet,
+        TypedTransaction::Eip1559(tx),
+    )
+    .await
+    .unwrap()
+}
+
+/// Init code returning `runtime` as the contract code
+#[cfg(test)]
+fn deployer(runtime: &[u8]) -> Vec<u8> {
+    let len = runtime.len() as u8;
+    // PUSH1 len PUSH1 12 PUSH1 0 CODECOPY PUSH1 len PUSH1 0 RETURN
+    let mut code = vec![
+        0x60, len, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, len, 0x60, 0x00, 0xf3,
+    ];
+    code.extend_from_slice(runtime);
+    code
+}
+
+#[tokio::test]
+async fn test_contract_deploy_and_call() {
+    let mut chain = setup_mock_chain();
+    let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+    let msg_sender: Address =
+        <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+    let chain_id = chain.config.chain_id;
+    fund(&mut chain, msg_sender);
+    let block = BlockContext {
+        number: 5,
+        timestamp: 1234,
+        coinbase: Address::from([9; 20]),
+        base_fee: 0,
+    };
+
+    // CHAINID PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+    let runtime = vec![0x46, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+    let tx = sign_eip1559(
+        &wallet,
+        chain_id,
+        0,
+        TxKind::Create,
+        U256::ZERO,
+        deployer(&runtime),
+    )
+    .await;
+    eth(&mut chain, msg_sender, &tx, &block).unwrap();
+    let contract = msg_sender.create(0);
+    assert_eq!(chain.get_code(contract).unwrap(), runtime);
+    assert_eq!(chain.get_eth_nonce(msg_sender).unwrap(), U256::from(1));
+
+    let tx = sign_eip1559(
+        &wallet,
+        chain_id,
+        1,
+        TxKind::Call(contract),
+        U256::from(100),
+        vec![],
+    )
+    .await;
+    let balance = chain.get_balance(Token::Native, msg_sender).unwrap();
+    let gas_used = eth(&mut chain, msg_sender, &tx, &block).unwrap().gas_used;
+    assert_eq!(
+        chain.get_balance(Token::Native, contract).unwrap(),
+        U256::from(100)
+    );
+    assert_eq!(
+        chain.get_balance(Token::Native, msg_sender).unwrap(),
+        balance - U256::from(100) - U256::from(gas_used * 3_000_000)
+    );
+
+    assert_eq!(
+        chain.call_contract(msg_sender, contract, vec![]).unwrap(),
+        U256::from(chain_id).to_be_bytes::<32>().to_vec()
+    );
+}
+
+#[tokio::test]
+async fn test_contract_call_revert() {
+    let mut chain = setup_mock_chain();
+    let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+    let msg_sender: Address =
+        <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+    let chain_id = chain.config.chain_id;
+    fund(&mut chain, msg_sender);
+
+    // PUSH1 0 PUSH1 0 REVERT
+    let runtime = vec![0x60, 0x00, 0x60, 0x00, 0xfd];
+    let tx = sign_eip1559(
+        &wallet,
+        chain_id,
+        0,
+        TxKind::Create,
+        U256::ZERO,
+        deployer(&runtime),
+    )
+    .await;
+    eth(&mut chain, msg_sender, &tx, &BlockContext::default()).unwrap();
+    let contract = msg_sender.create(0);
+    let balance = chain.get_balance(Token::Native, msg_sender).unwrap();
+
+    let tx = sign_eip1559(
+        &wallet,
+        chain_id,
+        1,
+        TxKind::Call(contract),
+        U256::from(100),
+        vec![],
+    )
+    .await;
+    // The call is included, the value stays with the sender who pays the fee
+    let outcome = eth(&mut chain, msg_sender, &tx, &BlockContext::default()).unwrap();
+    assert_eq!(outcome.error.unwrap(), "Execution reverted: 0x");
+    assert_eq!(
+        chain.get_balance(Token::Native, contract).unwrap(),
+        U256::ZERO
+    );
+    assert_eq!(
+        chain.get_balance(Token::Native, msg_sender).unwrap(),
+        balance - U256::from(outcome.gas_used * 3_000_000)
+    );
+    assert_eq!(chain.get_eth_nonce(msg_sender).unwrap(), U256::from(2));
+
+    // Deploying with more value than the balance can't be included
+    let balance = chain.get_balance(Token::Native, msg_sender).unwrap();
+    let tx = sign_eip1559(
+        &wallet,
+        chain_id,
+        2,
+        TxKind::Create,
+        balance,
+        deployer(&runtime),
+    )
+    .await;
+    assert!(eth(&mut chain, msg_sender, &tx, &BlockContext::default()).is_err());
+}
+
+#[tokio::test]
+async fn test_legacy_and_eip2930_transfers() {
+    let mut chain = setup_mock_chain();
+    let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+    let msg_sender: Address =
+        <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+    let receiver = Address::from([3; 20]);
+    let coinbase = Address::from([9; 20]);
+    let chain_id = chain.config.chain_id;
+    fund(&mut chain, msg_sender);
+    let block = BlockContext {
+        coinbase,
+        base_fee: 500_000_000,
+        ..Default::default()
+    };
+
+    let legacy = TxLegacy {
+        chain_id: Some(chain_id),
+        nonce: 0,
+        gas_price: 1_000_000_000,
+        gas_limit: 21_000,
+        to: TxKind::Call(receiver),
+        value: U256::from(1000),
+        input: Bytes::new(),
+    };
+    let signed_tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+        &wallet,
+        TypedTransaction::Legacy(legacy),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        eth(&mut chain, msg_sender, &signed_tx, &block)
+            .unwrap()
+            .gas_used,
+        21_000
+    );
+    // Legacy transactions pay their whole gas price
+    let fee = U256::from(21_000u64 * 1_000_000_000);
+    assert_eq!(chain.get_balance(Token::Native, coinbase).unwrap(), fee);
+
+    let access_list = TxEip2930 {
+        chain_id,
+        nonce: 1,
+        gas_price: 1_000_000_000,
+        gas_limit: 30_000,
+        to: TxKind::Call(receiver),
+        value: U256::from(1000),
+        access_list: AccessList(vec![AccessListItem {
+            address: receiver,
+            storage_keys: vec![],
+        }]),
+        input: Bytes::new(),
+    };
+    let signed_tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+        &wallet,
+        TypedTransaction::Eip2930(access_list),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        eth(&mut chain, msg_sender, &signed_tx, &block)
+            .unwrap()
+            .gas_used,
+        23_400
+    );
+
+    assert_eq!(
+        chain.get_balance(Token::Native, receiver).unwrap(),
+        U256::from(2000)
+    );
+    assert_eq!(
+        chain.get_balance(Token::Native, msg_sender).unwrap(),
+        U256::from(FUNDS) - U256::from(2000) - U256::from(44_400u64 * 1_000_000_000)
+    );
+    assert_eq!(chain.get_eth_nonce(msg_sender).unwrap(), U256::from(2));
+
+    // A gas price below the base fee can't be included
+    let cheap = TxLegacy {
+        chain_id: Some(chain_id),
+        nonce: 2,
+        gas_price: 100_000_000,
+        gas_limit: 21_000,
+        to: TxKind::Call(receiver),
+        value: U256::from(1000),
+        input: Bytes::new(),
+    };
+    let signed_tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+        &wallet,
+        TypedTransaction::Legacy(cheap),
+    )
+    .await
+    .unwrap();
+    assert!(eth(&mut chain, msg_sender, &signed_tx, &block).is_err());
+}
+
+#[tokio::test]
+async fn test_blob_transaction_rejected() {
+    let mut chain = setup_mock_chain();
+    let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+    let msg_sender: Address =
+        <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+    fund(&mut chain, msg_sender);
+
+    let tx = TxEip4844 {
+        chain_id: chain.config.chain_id,
+        gas_limit: 21_000,
+        max_fee_per_gas: 300_000_000,
+        max_fee_per_blob_gas: 1,
+        to: Address::from([3; 20]),
+        value: U256::from(1000),
+        blob_versioned_hashes: vec![B256::ZERO],
+        ..Default::default()
+    };
+    let signed_tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+        &wallet,
+        TypedTransaction::Eip4844(TxEip4844Variant::TxEip4844(tx)),
+    )
+    .await
+    .unwrap();
+    let err = eth(&mut chain, msg_sender, &signed_tx, &BlockContext::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Blob transactions (EIP-4844) are not supported!"
+    );
+    assert_eq!(chain.get_eth_nonce(msg_sender).unwrap(), U256::ZERO);
+}
@@ -1,15 +1,25 @@
-use alloy::primitives::{Address, FixedBytes, U256};
+use alloy::primitives::{keccak256, Address, FixedBytes, U256};
 use anyhow::{Ok, Result};
 
 use crate::{
-    blockchain::{Blockchain, Owshenchain},
+    blockchain::{Blockchain, Config, Owshenchain},
     db::{Key, Value},
     services::ContextKvStore,
-    types::Token,
+    types::{Log, Token},
 };
 
+/// Mints are signed by the owner, who watches the deposits on L1. Chains
+/// without an owner accept them from anyone.
+pub fn check_minter(config: &Config, from: Address) -> Result<()> {
+    match config.owner {
+        Some(owner) if owner != from => Err(anyhow::anyhow!("Mint is not signed by the owner!")),
+        _ => Ok(()),
+    }
+}
+
 pub fn mint_tx<K: ContextKvStore>(
     _chain: &mut Owshenchain<K>,
+    _mint_tx_hash: FixedBytes<32>,
     _tx_hash: Vec<u8>,
     _user_tx_hash: String,
     _token: Token,
@@ -22,7 +32,7 @@ pub fn mint_tx<K: ContextKvStore>(
 
     if _chain
         .db
-        .get(crate::db::Key::DepositedTransaction(_user_tx_hash))?
+        .get(crate::db::Key::DepositedTransaction(_user_tx_hash.clone()))?
         .is_some()
     {
         return Err(anyhow::anyhow!("Transaction already exists"));
@@ -45,18 +55,32 @@ pub fn mint_tx<K: ContextKvStore>(
         Some(Value::U256(new_balance)),
     )?;
 
+    // Marks the deposit as minted, rolled back along with the block
+    _chain.db.put(
+        Key::DepositedTransaction(_user_tx_hash),
+        Some(Value::DepositedTransaction(_mint_tx_hash.to_string())),
+    )?;
+
     Ok(())
 }
 
+/// `Mint(address indexed to, uint256 amount)`, emitted by the minted token
+pub fn mint_log(token: &Token, to: Address, amount: U256) -> Log {
+    Log {
+        address: token.address(),
+        topics: vec![keccak256("Mint(address,uint256)"), to.into_word()],
+        data: amount.to_be_bytes::<32>().to_vec(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloy::signers::local::PrivateKeySigner;
 
     use crate::{
         blockchain::Config,
-        config::{CHAIN_ID, OWSHEN_CONTRACT},
+        config::CHAIN_ID,
         db::{KvStore, RamKvStore},
-        genesis::GENESIS,
         types::{CustomTx, CustomTxMsg, IncludedTransaction, Mint},
     };
 
@@ -64,13 +88,7 @@ mod tests {
 
     #[test]
     fn test_mint_success() {
-        let conf = Config {
-            chain_id: 1387,
-            owner: None,
-            genesis: GENESIS.clone(),
-            owshen: OWSHEN_CONTRACT,
-            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-        };
+        let conf = Config::for_tests();
 
         let mut chain = Owshenchain::new(conf, RamKvStore::new());
 
@@ -82,7 +100,15 @@ mod tests {
         let token = Token::Native;
         let amount = U256::from(100);
 
-        let result = mint_tx(&mut chain, tx_hash, user_tx_hash, token.clone(), amount, address);
+        let result = mint_tx(
+            &mut chain,
+            FixedBytes::ZERO,
+            tx_hash,
+            user_tx_hash,
+            token.clone(),
+            amount,
+            address,
+        );
         assert!(result.is_ok());
 
         let balance = chain.get_balance(token.clone(), address).unwrap();
@@ -91,13 +117,7 @@ mod tests {
 
     #[test]
     fn test_mint_fail() {
-        let conf = Config {
-            chain_id: 1387,
-            owner: None,
-            genesis: GENESIS.clone(),
-            owshen: OWSHEN_CONTRACT,
-            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-        };
+        let conf = Config::for_tests();
 
         let mut chain = Owshenchain::new(conf, RamKvStore::new());
 
@@ -111,6 +131,7 @@ mod tests {
 
         let result = mint_tx(
             &mut chain,
+            FixedBytes::ZERO,
             tx_hash.clone(),
             user_tx_hash.clone(),
             token.clone(),
@@ -122,22 +143,24 @@ mod tests {
         let balance = chain.get_balance(token.clone(), address).unwrap();
         assert_eq!(balance, amount);
 
-        let result = mint_tx(&mut chain, tx_hash, user_tx_hash, token.clone(), amount, address);
-        assert!(result.is_ok());
+        let result = mint_tx(
+            &mut chain,
+            FixedBytes::ZERO,
+            tx_hash,
+            user_tx_hash,
+            token.clone(),
+            amount,
+            address,
+        );
+        assert!(result.is_err());
 
         let balance = chain.get_balance(token.clone(), address).unwrap();
-        assert_eq!(balance, amount * U256::from(2));
+        assert_eq!(balance, amount);
     }
 
     #[tokio::test]
     async fn test_mint_double_spend() {
-        let conf = Config {
-            chain_id: 1387,
-            owner: None,
-            genesis: GENESIS.clone(),
-            owshen: OWSHEN_CONTRACT,
-            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-        };
+        let conf = Config::for_tests();
 
         let mut chain = Owshenchain::new(conf, RamKvStore::new());
 
@@ -151,6 +174,7 @@ mod tests {
 
         let result = mint_tx(
             &mut chain,
+            FixedBytes::ZERO,
             tx_hash.clone(),
             user_tx_hash.clone(),
             token.clone(),
@@ -171,6 +195,8 @@ mod tests {
                 token: token.clone(),
                 amount,
                 address,
+                nonce: U256::from(0),
+                chain_id: 1,
             }),
         )
         .await
@@ -193,7 +219,15 @@ mod tests {
             )
             .unwrap();
 
-        let result = mint_tx(&mut chain, tx_hash, user_tx_hash, token.clone(), amount, address);
+        let result = mint_tx(
+            &mut chain,
+            FixedBytes::ZERO,
+            tx_hash,
+            user_tx_hash,
+            token.clone(),
+            amount,
+            address,
+        );
         assert!(result.is_err());
 
         let balance = chain.get_balance(token.clone(), address).unwrap();
@@ -202,13 +236,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_mint_double_spend_with_same_user_transaction_hash() {
-        let conf = Config {
-            chain_id: 1387,
-            owner: None,
-            genesis: GENESIS.clone(),
-            owshen: OWSHEN_CONTRACT,
-            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
-        };
+        let conf = Config::for_tests();
 
         let mut chain = Owshenchain::new(conf, RamKvStore::new());
 
@@ -222,6 +250,7 @@ mod tests {
 
         let result = mint_tx(
             &mut chain,
+            FixedBytes::ZERO,
             tx_hash.clone(),
             user_tx_hash.clone(),
             token.clone(),
@@ -241,7 +270,15 @@ mod tests {
             )
             .unwrap();
 
-        let result = mint_tx(&mut chain, tx_hash, user_tx_hash, token.clone(), amount, address);
+        let result = mint_tx(
+            &mut chain,
+            FixedBytes::ZERO,
+            tx_hash,
+            user_tx_hash,
+            token.clone(),
+            amount,
+            address,
+        );
         assert!(result.is_err());
 
         let balance = chain.get_balance(token.clone(), address).unwrap();
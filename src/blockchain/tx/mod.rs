@@ -5,6 +5,25 @@ pub mod owshen_airdrop;
 mod burn_tx;
 mod erc20;
 mod mint_tx;
+mod nonce;
 pub use burn_tx::*;
 pub use erc20::*;
 pub use mint_tx::*;
+pub use nonce::*;
+
+use crate::types::Log;
+
+/// What applying a valid transaction did. Failed transactions are still
+/// included, they pay for their gas but keep none of their other writes.
+#[derive(Debug, Clone, Default)]
+pub struct TxOutcome {
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    pub error: Option<String>,
+}
+
+impl TxOutcome {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
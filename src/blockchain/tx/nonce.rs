@@ -0,0 +1,90 @@
+use std::fmt;
+
+use alloy::primitives::{Address, U256};
+use anyhow::Result;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NonceError {
+    TooLow {
+        address: Address,
+        expected: U256,
+        got: U256,
+    },
+    TooHigh {
+        address: Address,
+        expected: U256,
+        got: U256,
+    },
+}
+
+impl fmt::Display for NonceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonceError::TooLow {
+                address,
+                expected,
+                got,
+            } => write!(
+                f,
+                "nonce too low: address {}, tx: {} state: {}",
+                address, got, expected
+            ),
+            NonceError::TooHigh {
+                address,
+                expected,
+                got,
+            } => write!(
+                f,
+                "nonce too high: address {}, tx: {} state: {}",
+                address, got, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NonceError {}
+
+pub fn check_nonce(address: Address, expected: U256, got: U256) -> Result<()> {
+    if got < expected {
+        return Err(NonceError::TooLow {
+            address,
+            expected,
+            got,
+        }
+        .into());
+    }
+    if got > expected {
+        return Err(NonceError::TooHigh {
+            address,
+            expected,
+            got,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_nonce() {
+        let address = Address::from([1; 20]);
+        assert!(check_nonce(address, U256::from(3), U256::from(3)).is_ok());
+
+        let err = check_nonce(address, U256::from(3), U256::from(2)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NonceError>(),
+            Some(NonceError::TooLow { .. })
+        ));
+        assert!(err.to_string().starts_with("nonce too low"));
+
+        let err = check_nonce(address, U256::from(3), U256::from(4)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NonceError>(),
+            Some(NonceError::TooHigh { .. })
+        ));
+        assert!(err.to_string().starts_with("nonce too high"));
+    }
+}
@@ -0,0 +1,374 @@
+use alloy::{
+    primitives::{Address, FixedBytes, U256},
+    providers::{Provider, ProviderBuilder},
+    sol,
+    sol_types::SolEvent,
+};
+use anyhow::{anyhow, Result};
+use std::fmt;
+
+use crate::{
+    blockchain::{Blockchain, Owshenchain},
+    db::Key,
+    services::{Context, ContextKvStore, ContextSigner},
+    types::{network::Network, CustomTx, CustomTxMsg, Mint, OwshenTransaction, Token, ERC20},
+};
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+}
+
+/// A deposit into the Owshen contract, as observed on L1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deposit {
+    pub chain_id: u64,
+    pub tx_hash: FixedBytes<32>,
+    /// `Address::ZERO` stands for the native coin, same as in Owshen.sol
+    pub token: Address,
+    pub amount: U256,
+    pub depositor: Address,
+    pub block_number: u64,
+}
+
+/// Why a transaction will never make a deposit, as opposed to failures of the
+/// provider which are worth retrying
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidDeposit {
+    Failed,
+    NotADeposit,
+    MultipleDeposits,
+}
+
+impl fmt::Display for InvalidDeposit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidDeposit::Failed => write!(f, "Deposit transaction has failed!"),
+            InvalidDeposit::NotADeposit => write!(f, "Transaction is not a deposit to Owshen!"),
+            InvalidDeposit::MultipleDeposits => {
+                write!(f, "Transaction contains more than one deposit!")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidDeposit {}
+
+#[derive(Debug, Clone)]
+pub struct DepositVerifier {
+    network: Network,
+}
+
+impl DepositVerifier {
+    pub fn new(network: &Network) -> Self {
+        Self {
+            network: network.clone(),
+        }
+    }
+
+    /// Fetches the L1 transaction and extracts the deposit it made into the
+    /// Owshen contract. Native deposits are plain value transfers to the
+    /// contract, ERC20 deposits are `Transfer` logs with the contract as receiver.
+    pub async fn verify(&self, tx_hash: FixedBytes<32>) -> Result<Deposit> {
+        let provider = ProviderBuilder::new().on_http(self.network.provider_address.clone());
+
+        let receipt = provider
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .ok_or(anyhow!("Deposit transaction not found!"))?;
+        if !receipt.status() {
+            return Err(InvalidDeposit::Failed.into());
+        }
+        let block_number = receipt
+            .block_number
+            .ok_or(anyhow!("Deposit transaction is still pending!"))?;
+
+        let head = provider.get_block_number().await?;
+        let confirmations = (head + 1).saturating_sub(block_number);
+        if confirmations < self.network.confirmations {
+            return Err(anyhow!(
+                "Deposit transaction has {} confirmations, {} required!",
+                confirmations,
+                self.network.confirmations
+            ));
+        }
+
+        let mut deposits = Vec::new();
+        for log in receipt.inner.logs() {
+            if log.topics().first() != Some(&Transfer::SIGNATURE_HASH) {
+                continue;
+            }
+            // ERC721 transfers share the signature but not the layout
+            if let Ok(transfer) = Transfer::decode_log_data(log.data(), true) {
+                if transfer.to == self.network.owshen {
+                    deposits.push(Deposit {
+                        chain_id: self.network.chain_id,
+                        tx_hash,
+                        token: log.address(),
+                        amount: transfer.value,
+                        depositor: transfer.from,
+                        block_number,
+                    });
+                }
+            }
+        }
+        if receipt.to == Some(self.network.owshen) {
+            let tx = provider
+                .get_transaction_by_hash(tx_hash)
+                .await?
+                .ok_or(anyhow!("Deposit transaction not found!"))?;
+            if tx.value > U256::ZERO {
+                deposits.push(Deposit {
+                    chain_id: self.network.chain_id,
+                    tx_hash,
+                    token: Address::ZERO,
+                    amount: tx.value,
+                    depositor: tx.from,
+                    block_number,
+                });
+            }
+        }
+
+        match deposits.len() {
+            0 => Err(InvalidDeposit::NotADeposit.into()),
+            1 => Ok(deposits.remove(0)),
+            _ => Err(InvalidDeposit::MultipleDeposits.into()),
+        }
+    }
+}
+
+pub fn is_mint_of(tx: &OwshenTransaction, user_tx_hash: &str) -> bool {
+    match tx {
+        OwshenTransaction::Custom(custom_tx) => matches!(
+            custom_tx.msg(),
+            Ok(CustomTxMsg::MintTx(mint)) if mint.user_tx_hash == user_tx_hash
+        ),
+        _ => false,
+    }
+}
+
+/// Whether the mint of the deposit is included in the chain
+pub fn is_deposit_minted<K: ContextKvStore>(
+    chain: &Owshenchain<K>,
+    user_tx_hash: &str,
+) -> Result<bool> {
+    Ok(chain
+        .db
+        .get(Key::DepositedTransaction(user_tx_hash.to_string()))?
+        .is_some())
+}
+
+/// Whether the deposit is already minted or its mint is waiting in the queue
+pub fn is_deposit_known<S: ContextSigner, K: ContextKvStore>(
+    ctx: &Context<S, K>,
+    user_tx_hash: &str,
+) -> Result<bool> {
+    if is_deposit_minted(&ctx.chain, user_tx_hash)? {
+        return Ok(true);
+    }
+    Ok(ctx
+        .tx_queue
+        .queue()
+        .iter()
+        .any(|tx| is_mint_of(tx, user_tx_hash)))
+}
+
+/// Signs a mint of `deposit` with the node key and puts it in the queue
+pub async fn enqueue_mint<S: ContextSigner, K: ContextKvStore>(
+    ctx: &mut Context<S, K>,
+    deposit: &Deposit,
+) -> Result<FixedBytes<32>> {
+    ctx.chain.config().network(deposit.chain_id)?;
+    let token = if deposit.token == Address::ZERO {
+        Token::Native
+    } else {
+        let decimals = ctx.chain.get_token_decimal(deposit.token)?;
+        let symbol = ctx.chain.get_token_symbol(deposit.token)?;
+        Token::Erc20(ERC20 {
+            address: deposit.token,
+            decimals,
+            symbol,
+        })
+    };
+
+    let nonce = ctx
+        .tx_queue
+        .next_custom_nonce(&ctx.chain, ctx.signer.address())?;
+    let tx = CustomTx::create(
+        &mut ctx.signer.clone(),
+        ctx.chain.config().chain_id,
+        CustomTxMsg::MintTx(Mint {
+            tx_hash: deposit.tx_hash.to_vec(),
+            user_tx_hash: deposit.tx_hash.to_string(),
+            token,
+            amount: deposit.amount,
+            address: deposit.depositor,
+            nonce,
+            chain_id: deposit.chain_id,
+        }),
+    )
+    .await?;
+    let hash = tx.hash()?;
+    ctx.tx_queue.enqueue(tx);
+    Ok(hash)
+}
+
+/// Signs the queued mints of the node again with consecutive nonces starting
+/// from its nonce on chain. A rejected mint leaves a gap that would keep all
+/// the later ones out of the blocks.
+pub async fn renumber_mints<S: ContextSigner, K: ContextKvStore>(
+    ctx: &mut Context<S, K>,
+) -> Result<()> {
+    let minter = ctx.signer.address();
+    let mut mints = Vec::new();
+    for tx in ctx.tx_queue.queue() {
+        if let OwshenTransaction::Custom(custom_tx) = &tx {
+            if let Ok(CustomTxMsg::MintTx(mint)) = custom_tx.msg() {
+                if tx.signer()? == minter {
+                    mints.push((tx.hash()?, mint));
+                }
+            }
+        }
+    }
+    mints.sort_by_key(|(_, mint)| mint.nonce);
+
+    let mut nonce = ctx.chain.get_custom_nonce(minter)?;
+    for (hash, mut mint) in mints {
+        if mint.nonce != nonce {
+            ctx.tx_queue.remove(hash);
+            mint.nonce = nonce;
+            let tx = CustomTx::create(
+                &mut ctx.signer.clone(),
+                ctx.chain.config().chain_id,
+                CustomTxMsg::MintTx(mint),
+            )
+            .await?;
+            ctx.tx_queue.enqueue(tx);
+        }
+        nonce += U256::from(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockchain::{events, Config, TransactionQueue},
+        bridge::mock_provider,
+        config,
+        db::{KvStore, RamKvStore, Value},
+        safe_signer::SafeSigner,
+        services::filters::Filters,
+    };
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn verifier(provider_address: reqwest::Url, confirmations: u64) -> DepositVerifier {
+        DepositVerifier::new(&Network {
+            chain_id: 1,
+            owshen: config::OWSHEN_CONTRACT,
+            provider_address,
+            confirmations,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_verify_deposit() {
+        let l1 = mock_provider::MockL1::new(100);
+        let (url, _handle) = mock_provider::serve(l1.clone()).await.unwrap();
+        let verifier = verifier(url, 10);
+
+        let user = Address::from([2; 20]);
+        let token = Address::from([3; 20]);
+        let amount = U256::from(1000);
+        let (native, erc20, early, failed, elsewhere) = {
+            let mut l1 = l1.lock().unwrap();
+            let native = l1.native_deposit(90, user, config::OWSHEN_CONTRACT, amount);
+            let erc20 = l1.erc20_deposit(91, token, user, config::OWSHEN_CONTRACT, amount);
+            let early = l1.native_deposit(92, user, config::OWSHEN_CONTRACT, amount);
+            let failed = l1.add_tx(50, user, config::OWSHEN_CONTRACT, amount, vec![], false);
+            let elsewhere = l1.native_deposit(50, user, token, amount);
+            (native, erc20, early, failed, elsewhere)
+        };
+
+        assert_eq!(
+            verifier.verify(native).await.unwrap(),
+            Deposit {
+                chain_id: 1,
+                tx_hash: native,
+                token: Address::ZERO,
+                amount,
+                depositor: user,
+                block_number: 90,
+            }
+        );
+        assert_eq!(
+            verifier.verify(erc20).await.unwrap(),
+            Deposit {
+                chain_id: 1,
+                tx_hash: erc20,
+                token,
+                amount,
+                depositor: user,
+                block_number: 91,
+            }
+        );
+
+        // 9 confirmations out of 10
+        assert!(verifier.verify(early).await.is_err());
+        l1.lock().unwrap().head = 101;
+        assert!(verifier.verify(early).await.is_ok());
+
+        assert!(verifier.verify(failed).await.is_err());
+        assert!(verifier.verify(elsewhere).await.is_err());
+        assert!(verifier.verify(FixedBytes::from([9; 32])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_renumber_mints() {
+        let conf = Config::for_tests();
+        let mut ctx = Context {
+            signer: SafeSigner::new(PrivateKeySigner::random()),
+            exit: false,
+            read_only: false,
+            tx_queue: TransactionQueue::new(),
+            filters: Filters::new(),
+            events: events::channel(),
+            chain: Owshenchain::new(conf, RamKvStore::new()),
+        };
+        let deposits = (1..=3u8)
+            .map(|i| Deposit {
+                chain_id: 1,
+                tx_hash: FixedBytes::from([i; 32]),
+                token: Address::ZERO,
+                amount: U256::from(1000),
+                depositor: Address::from([i; 20]),
+                block_number: 90,
+            })
+            .collect::<Vec<_>>();
+        for deposit in deposits.iter() {
+            enqueue_mint(&mut ctx, deposit).await.unwrap();
+        }
+
+        // The first mint is refused, leaving a gap before the other two
+        ctx.chain
+            .db
+            .put(
+                Key::DepositedTransaction(deposits[0].tx_hash.to_string()),
+                Some(Value::DepositedTransaction(String::new())),
+            )
+            .unwrap();
+        let blk = ctx.chain.draft_block(&mut ctx.tx_queue, 0).unwrap();
+        ctx.chain.push_block(blk).unwrap();
+        assert_eq!(ctx.tx_queue.rejected().len(), 1);
+        assert_eq!(ctx.tx_queue.queue().len(), 2);
+
+        renumber_mints(&mut ctx).await.unwrap();
+        let blk = ctx.chain.draft_block(&mut ctx.tx_queue, 1).unwrap();
+        assert_eq!(blk.txs.len(), 2);
+        ctx.chain.push_block(blk).unwrap();
+        assert!(ctx.tx_queue.queue().is_empty());
+        for deposit in deposits[1..].iter() {
+            assert!(is_deposit_minted(&ctx.chain, &deposit.tx_hash.to_string()).unwrap());
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! A tiny in-process L1 JSON-RPC endpoint, serving canned transactions and
+//! receipts to the bridge code under test.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::{Arc, Mutex},
+};
+
+use alloy::{
+    consensus::{Eip658Value, Receipt, ReceiptEnvelope, ReceiptWithBloom},
+    eips::BlockNumberOrTag,
+    primitives::{keccak256, Address, Bloom, FixedBytes, Log as PrimitiveLog, U256},
+    rpc::types::{
+        Block, BlockTransactions, Filter, FilteredParams, Header, Log, Transaction,
+        TransactionReceipt,
+    },
+    sol_types::SolEvent,
+};
+use anyhow::Result;
+use jsonrpsee::server::{RpcModule, Server, ServerHandle};
+
+use super::{deposit::Transfer, withdrawal::WithdrawExecuted};
+
+#[derive(Debug, Clone, Default)]
+pub struct MockL1 {
+    pub head: u64,
+    pub txs: HashMap<FixedBytes<32>, (Transaction, TransactionReceipt)>,
+    /// Transactions whose receipt isn't served, like by a lagging node
+    pub hidden: HashSet<FixedBytes<32>>,
+}
+
+impl MockL1 {
+    pub fn new(head: u64) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            head,
+            ..Default::default()
+        }))
+    }
+
+    pub fn add_tx(
+        &mut self,
+        block_number: u64,
+        from: Address,
+        to: Address,
+        value: U256,
+        logs: Vec<PrimitiveLog>,
+        success: bool,
+    ) -> FixedBytes<32> {
+        let tx_hash = keccak256((self.txs.len() as u64).to_be_bytes());
+        let tx = Transaction {
+            hash: tx_hash,
+            block_hash: Some(FixedBytes::ZERO),
+            block_number: Some(block_number),
+            transaction_index: Some(0),
+            from,
+            to: Some(to),
+            value,
+            gas: 100_000,
+            max_fee_per_gas: Some(1),
+            max_priority_fee_per_gas: Some(1),
+            chain_id: Some(1),
+            transaction_type: Some(2),
+            ..Default::default()
+        };
+        let logs = logs
+            .into_iter()
+            .map(|inner| Log {
+                inner,
+                block_hash: Some(FixedBytes::ZERO),
+                block_number: Some(block_number),
+                transaction_hash: Some(tx_hash),
+                transaction_index: Some(0),
+                log_index: Some(0),
+                ..Default::default()
+            })
+            .collect();
+        let receipt = TransactionReceipt {
+            inner: ReceiptEnvelope::Eip1559(ReceiptWithBloom {
+                receipt: Receipt {
+                    status: Eip658Value::Eip658(success),
+                    cumulative_gas_used: 21_000,
+                    logs,
+                },
+                logs_bloom: Bloom::default(),
+            }),
+            transaction_hash: tx_hash,
+            transaction_index: Some(0),
+            block_hash: Some(FixedBytes::ZERO),
+            block_number: Some(block_number),
+            gas_used: 21_000,
+            effective_gas_price: 1,
+            blob_gas_used: None,
+            blob_gas_price: None,
+            from,
+            to: Some(to),
+            contract_address: None,
+            state_root: None,
+            authorization_list: None,
+        };
+        self.txs.insert(tx_hash, (tx, receipt));
+        tx_hash
+    }
+
+    pub fn native_deposit(
+        &mut self,
+        block_number: u64,
+        from: Address,
+        owshen: Address,
+        amount: U256,
+    ) -> FixedBytes<32> {
+        self.add_tx(block_number, from, owshen, amount, vec![], true)
+    }
+
+    pub fn erc20_deposit(
+        &mut self,
+        block_number: u64,
+        token: Address,
+        from: Address,
+        owshen: Address,
+        amount: U256,
+    ) -> FixedBytes<32> {
+        let transfer = Transfer {
+            from,
+            to: owshen,
+            value: amount,
+        };
+        let log = PrimitiveLog {
+            address: token,
+            data: transfer.encode_log_data(),
+        };
+        self.add_tx(block_number, from, token, U256::ZERO, vec![log], true)
+    }
+
+    pub fn withdraw_executed(
+        &mut self,
+        block_number: u64,
+        owshen: Address,
+        event: WithdrawExecuted,
+    ) -> FixedBytes<32> {
+        let log = PrimitiveLog {
+            address: owshen,
+            data: event.encode_log_data(),
+        };
+        self.add_tx(block_number, event.to, owshen, U256::ZERO, vec![log], true)
+    }
+}
+
+pub async fn serve(l1: Arc<Mutex<MockL1>>) -> Result<(reqwest::Url, ServerHandle)> {
+    let server = Server::builder()
+        .build(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)))
+        .await?;
+    let addr = server.local_addr()?;
+    let mut module = RpcModule::new(l1);
+
+    module.register_method("eth_chainId", |_, _, _| "0x1")?;
+    module.register_method("eth_blockNumber", |_, l1, _| {
+        format!("{:#x}", l1.lock().unwrap().head)
+    })?;
+    module.register_method("eth_getTransactionByHash", |params, l1, _| {
+        let tx_hash: FixedBytes<32> = params.one().unwrap_or_default();
+        l1.lock()
+            .unwrap()
+            .txs
+            .get(&tx_hash)
+            .map(|(tx, _)| tx.clone())
+    })?;
+    module.register_method("eth_getTransactionReceipt", |params, l1, _| {
+        let tx_hash: FixedBytes<32> = params.one().unwrap_or_default();
+        let l1 = l1.lock().unwrap();
+        if l1.hidden.contains(&tx_hash) {
+            return None;
+        }
+        l1.txs.get(&tx_hash).map(|(_, receipt)| receipt.clone())
+    })?;
+
+    module.register_method("eth_getLogs", |params, l1, _| {
+        let filter: Filter = params.one().unwrap_or_default();
+        let params = FilteredParams::new(Some(filter));
+        let mut logs: Vec<Log> = l1
+            .lock()
+            .unwrap()
+            .txs
+            .values()
+            .flat_map(|(_, receipt)| receipt.inner.logs().to_vec())
+            .filter(|log| {
+                params.filter_block_range(log.block_number.unwrap_or_default())
+                    && params.filter_address(&log.address())
+                    && params.filter_topics(log.topics())
+            })
+            .collect();
+        logs.sort_by_key(|log| log.block_number);
+        logs
+    })?;
+    module.register_method("eth_getBlockByNumber", |params, l1, _| {
+        let mut seq = params.sequence();
+        let number: BlockNumberOrTag = seq.next().unwrap_or_default();
+        let l1 = l1.lock().unwrap();
+        let number = match number {
+            BlockNumberOrTag::Number(number) => number,
+            _ => l1.head,
+        };
+        if number > l1.head {
+            return None;
+        }
+        let mut txs: Vec<Transaction> = l1
+            .txs
+            .values()
+            .filter(|(tx, _)| tx.block_number == Some(number))
+            .map(|(tx, _)| tx.clone())
+            .collect();
+        txs.sort_by_key(|tx| tx.hash);
+        Some(Block {
+            header: Header {
+                number,
+                ..Default::default()
+            },
+            transactions: BlockTransactions::Full(txs),
+            ..Default::default()
+        })
+    })?;
+
+    let handle = server.start(module);
+    Ok((format!("http://{}", addr).parse()?, handle))
+}
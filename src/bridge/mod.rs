@@ -0,0 +1,9 @@
+mod deposit;
+mod watcher;
+mod withdrawal;
+pub use deposit::*;
+pub use watcher::*;
+pub use withdrawal::*;
+
+#[cfg(test)]
+pub mod mock_provider;
@@ -0,0 +1,412 @@
+use std::{collections::HashSet, sync::Arc};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{FixedBytes, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::Filter,
+    sol_types::SolEvent,
+};
+use anyhow::{anyhow, Result};
+use tokio::sync::Mutex;
+
+use super::{
+    enqueue_mint, is_deposit_known, is_deposit_minted, record_withdrawal_execution,
+    DepositVerifier, InvalidDeposit, Transfer, WithdrawExecuted,
+};
+use crate::{
+    blockchain::Blockchain,
+    db::{Key, Value},
+    services::{Context, ContextKvStore, ContextSigner},
+    types::network::Network,
+};
+
+/// Upper bound on the L1 blocks scanned in a single poll
+const MAX_BLOCKS_PER_POLL: u64 = 100;
+
+/// Polls every configured network for deposits into the Owshen contract and
+/// enqueues their mints, so users don't have to call `/deposit` themselves.
+/// Withdrawals redeemed on the contract are recorded along the way.
+pub async fn deposit_watcher<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+) -> Result<()> {
+    loop {
+        let networks = {
+            let ctx = ctx.lock().await;
+            if ctx.exit {
+                log::info!("Terminating the deposit watcher...");
+                return Ok(());
+            }
+            ctx.chain.config().networks.clone()
+        };
+        for network in networks.iter() {
+            if let Err(e) = watch_network(ctx.clone(), network).await {
+                log::info!(
+                    "Error while watching deposits on network {}: {}",
+                    network.chain_id,
+                    e
+                );
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(12)).await;
+    }
+}
+
+/// Processes the confirmed blocks of `network` after the persisted cursor.
+/// Without a cursor, watching starts at the current confirmed block.
+pub async fn watch_network<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    network: &Network,
+) -> Result<()> {
+    let provider = ProviderBuilder::new().on_http(network.provider_address.clone());
+    let head = provider.get_block_number().await?;
+    let confirmed = match (head + 1).checked_sub(network.confirmations.max(1)) {
+        Some(confirmed) => confirmed,
+        None => return Ok(()),
+    };
+
+    let cursor_key = Key::DepositCursor(network.chain_id);
+    let cursor = ctx.lock().await.chain.db.get(cursor_key.clone())?;
+    let from = match cursor {
+        Some(v) => v.as_u256()?.to::<u64>() + 1,
+        None => {
+            ctx.lock()
+                .await
+                .chain
+                .db
+                .put(cursor_key, Some(Value::U256(U256::from(confirmed))))?;
+            return Ok(());
+        }
+    };
+    if from > confirmed {
+        return Ok(());
+    }
+    let to = confirmed.min(from + MAX_BLOCKS_PER_POLL - 1);
+
+    // Candidate deposits along with the L1 block they are in
+    let mut candidates: Vec<(FixedBytes<32>, u64)> = Vec::new();
+
+    // ERC20 deposits are transfers to the contract
+    let filter = Filter::new()
+        .from_block(from)
+        .to_block(to)
+        .event_signature(Transfer::SIGNATURE_HASH)
+        .topic2(network.owshen.into_word());
+    for log in provider.get_logs(&filter).await? {
+        if let Some(tx_hash) = log.transaction_hash {
+            candidates.push((tx_hash, log.block_number.unwrap_or(from)));
+        }
+    }
+
+    // Native deposits don't emit anything, they are found by scanning the blocks
+    for number in from..=to {
+        let block = provider
+            .get_block_by_number(BlockNumberOrTag::Number(number), true)
+            .await?
+            .ok_or(anyhow!("Block {} not found!", number))?;
+        for tx in block.transactions.txns() {
+            if tx.to == Some(network.owshen) && tx.value > U256::ZERO {
+                candidates.push((tx.hash, number));
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    candidates.retain(|(tx_hash, _)| seen.insert(*tx_hash));
+
+    // Queued mints only live in memory, the cursor stays before the first
+    // deposit that isn't minted yet so that it is scanned again until it is
+    let mut first_unminted: Option<u64> = None;
+    let verifier = DepositVerifier::new(network);
+    for (tx_hash, block_number) in candidates {
+        if is_deposit_minted(&ctx.lock().await.chain, &tx_hash.to_string())? {
+            continue;
+        }
+        if !is_deposit_known(&*ctx.lock().await, &tx_hash.to_string())? {
+            match verifier.verify(tx_hash).await {
+                Ok(deposit) => {
+                    let mut ctx = ctx.lock().await;
+                    if !is_deposit_known(&ctx, &tx_hash.to_string())? {
+                        let owshen_tx_hash = enqueue_mint(&mut ctx, &deposit).await?;
+                        log::info!("Deposit {} is being minted in {}", tx_hash, owshen_tx_hash);
+                    }
+                }
+                Err(e) if e.downcast_ref::<InvalidDeposit>().is_some() => {
+                    log::info!("Ignoring transaction {}: {}", tx_hash, e);
+                    continue;
+                }
+                // The range is scanned again on the next poll
+                Err(e) => return Err(e.context(format!("Couldn't verify deposit {}", tx_hash))),
+            }
+        }
+        first_unminted = Some(first_unminted.map_or(block_number, |b| b.min(block_number)));
+    }
+
+    let filter = Filter::new()
+        .from_block(from)
+        .to_block(to)
+        .address(network.owshen)
+        .event_signature(WithdrawExecuted::SIGNATURE_HASH);
+    for log in provider.get_logs(&filter).await? {
+        let event = match WithdrawExecuted::decode_log_data(log.data(), true) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+        let (Some(tx_hash), Some(block_number)) = (log.transaction_hash, log.block_number) else {
+            continue;
+        };
+        let mut ctx = ctx.lock().await;
+        if record_withdrawal_execution(
+            &mut ctx.chain,
+            network.chain_id,
+            &event,
+            tx_hash,
+            block_number,
+        )? {
+            log::info!("Withdrawal {} executed in {}", event.id, tx_hash);
+        }
+    }
+
+    let cursor = match first_unminted {
+        Some(block_number) => block_number.max(from) - 1,
+        None => to,
+    };
+    ctx.lock()
+        .await
+        .chain
+        .db
+        .put(cursor_key, Some(Value::U256(U256::from(cursor))))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::Address, signers::local::PrivateKeySigner};
+
+    use super::*;
+    use crate::{
+        blockchain::{events, Config, Owshenchain, TransactionQueue},
+        bridge::{is_mint_of, mock_provider, push_burn_block, WithdrawalExecution},
+        config,
+        db::{KvStore, RamKvStore},
+        safe_signer::SafeSigner,
+        services::filters::Filters,
+        types::{Burn, Token, WithdrawCalldata},
+    };
+
+    #[tokio::test]
+    async fn test_watch_network() {
+        let l1 = mock_provider::MockL1::new(100);
+        let (url, _handle) = mock_provider::serve(l1.clone()).await.unwrap();
+        let network = Network {
+            chain_id: 1,
+            owshen: config::OWSHEN_CONTRACT,
+            provider_address: url,
+            confirmations: 10,
+        };
+        let conf = Config {
+            networks: vec![network.clone()],
+            ..Config::for_tests()
+        };
+        let ctx = Arc::new(Mutex::new(Context {
+            signer: SafeSigner::new(PrivateKeySigner::random()),
+            exit: false,
+            read_only: false,
+            tx_queue: TransactionQueue::new(),
+            filters: Filters::new(),
+            events: events::channel(),
+            chain: Owshenchain::new(conf, RamKvStore::new()),
+        }));
+        let cursor = |ctx: &Context<SafeSigner, RamKvStore>| {
+            ctx.chain
+                .db
+                .get(Key::DepositCursor(1))
+                .unwrap()
+                .map(|v| v.as_u256().unwrap())
+        };
+
+        let user = Address::from([2; 20]);
+        let token = Address::from([3; 20]);
+        let amount = U256::from(1000);
+        let old = l1
+            .lock()
+            .unwrap()
+            .native_deposit(80, user, config::OWSHEN_CONTRACT, amount);
+
+        // The first poll only marks where watching starts
+        watch_network(ctx.clone(), &network).await.unwrap();
+        assert_eq!(cursor(&*ctx.lock().await), Some(U256::from(91)));
+        assert!(ctx.lock().await.tx_queue.queue().is_empty());
+
+        let (native, erc20, unconfirmed) = {
+            let mut l1 = l1.lock().unwrap();
+            let native = l1.native_deposit(93, user, config::OWSHEN_CONTRACT, amount);
+            let erc20 = l1.erc20_deposit(94, token, user, config::OWSHEN_CONTRACT, amount);
+            let unconfirmed = l1.native_deposit(98, user, config::OWSHEN_CONTRACT, amount);
+            l1.native_deposit(93, user, token, amount);
+            l1.head = 105;
+            (native, erc20, unconfirmed)
+        };
+        let mint_queued = || async {
+            let mut ctx = ctx.lock().await;
+            let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+            let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+            ctx.tx_queue = tx_queue;
+            ctx.chain.push_block(blk).unwrap();
+        };
+
+        // Queued deposits hold the cursor back until they are minted
+        watch_network(ctx.clone(), &network).await.unwrap();
+        {
+            let ctx = ctx.lock().await;
+            assert_eq!(cursor(&ctx), Some(U256::from(92)));
+            let queue = ctx.tx_queue.queue();
+            assert_eq!(queue.len(), 2);
+            assert!(queue.iter().any(|tx| is_mint_of(tx, &native.to_string())));
+            assert!(queue.iter().any(|tx| is_mint_of(tx, &erc20.to_string())));
+            assert!(!queue.iter().any(|tx| is_mint_of(tx, &old.to_string())));
+        }
+
+        // Known deposits are not minted twice
+        watch_network(ctx.clone(), &network).await.unwrap();
+        assert_eq!(ctx.lock().await.tx_queue.queue().len(), 2);
+
+        // Mints lost along with the queue, like on a restart, are queued again
+        ctx.lock().await.tx_queue = TransactionQueue::new();
+        watch_network(ctx.clone(), &network).await.unwrap();
+        assert_eq!(ctx.lock().await.tx_queue.queue().len(), 2);
+        assert_eq!(cursor(&*ctx.lock().await), Some(U256::from(92)));
+
+        mint_queued().await;
+        watch_network(ctx.clone(), &network).await.unwrap();
+        assert_eq!(cursor(&*ctx.lock().await), Some(U256::from(96)));
+        assert!(ctx.lock().await.tx_queue.queue().is_empty());
+
+        l1.lock().unwrap().head = 120;
+        watch_network(ctx.clone(), &network).await.unwrap();
+        {
+            let ctx = ctx.lock().await;
+            assert_eq!(cursor(&ctx), Some(U256::from(97)));
+            assert_eq!(ctx.tx_queue.queue().len(), 1);
+            assert!(is_mint_of(
+                &ctx.tx_queue.queue()[0],
+                &unconfirmed.to_string()
+            ));
+        }
+        mint_queued().await;
+
+        // A deposit the provider fails to serve holds the cursor back until it
+        // is verified
+        let (lagging, failed) = {
+            let mut l1 = l1.lock().unwrap();
+            let lagging = l1.native_deposit(113, user, config::OWSHEN_CONTRACT, amount);
+            let failed = l1.add_tx(113, user, config::OWSHEN_CONTRACT, amount, vec![], false);
+            l1.hidden.insert(lagging);
+            l1.head = 125;
+            (lagging, failed)
+        };
+        assert!(watch_network(ctx.clone(), &network).await.is_err());
+        assert_eq!(cursor(&*ctx.lock().await), Some(U256::from(97)));
+
+        l1.lock().unwrap().hidden.clear();
+        watch_network(ctx.clone(), &network).await.unwrap();
+        let ctx = ctx.lock().await;
+        assert_eq!(cursor(&ctx), Some(U256::from(112)));
+        assert_eq!(ctx.tx_queue.queue().len(), 1);
+        assert!(is_mint_of(&ctx.tx_queue.queue()[0], &lagging.to_string()));
+        assert!(!is_mint_of(&ctx.tx_queue.queue()[0], &failed.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_watch_withdrawals() {
+        let l1 = mock_provider::MockL1::new(100);
+        let (url, _handle) = mock_provider::serve(l1.clone()).await.unwrap();
+        let network = Network {
+            chain_id: 1,
+            owshen: config::OWSHEN_CONTRACT,
+            provider_address: url,
+            confirmations: 1,
+        };
+        let conf = Config {
+            networks: vec![network.clone()],
+            ..Config::for_tests()
+        };
+        let signer = SafeSigner::new(PrivateKeySigner::random());
+        let mut chain = Owshenchain::new(conf, RamKvStore::new());
+
+        let user = PrivateKeySigner::random();
+        let amount = U256::from(1000);
+        let burn_id = FixedBytes::from([7u8; 32]);
+        let burn = Burn {
+            burn_id,
+            chain_id: 1,
+            token: Token::Native,
+            amount,
+            calldata: Some(WithdrawCalldata::Eth {
+                address: user.address(),
+            }),
+            nonce: U256::from(0),
+        };
+        push_burn_block(&mut chain, &user, burn).await.unwrap();
+        let id = U256::from_be_bytes(burn_id.0);
+        chain
+            .db
+            .put(Key::DepositCursor(1), Some(Value::U256(U256::from(100))))
+            .unwrap();
+        let ctx = Arc::new(Mutex::new(Context {
+            signer,
+            exit: false,
+            read_only: false,
+            tx_queue: TransactionQueue::new(),
+            filters: Filters::new(),
+            events: events::channel(),
+            chain,
+        }));
+
+        let (forged, executed) = {
+            let mut l1 = l1.lock().unwrap();
+            let forged = l1.withdraw_executed(
+                102,
+                config::OWSHEN_CONTRACT,
+                WithdrawExecuted {
+                    to: user.address(),
+                    token: Address::ZERO,
+                    id,
+                    amount: amount + U256::from(1),
+                },
+            );
+            let executed = l1.withdraw_executed(
+                101,
+                config::OWSHEN_CONTRACT,
+                WithdrawExecuted {
+                    to: user.address(),
+                    token: Address::ZERO,
+                    id,
+                    amount,
+                },
+            );
+            l1.head = 102;
+            (forged, executed)
+        };
+        assert_ne!(forged, executed);
+
+        watch_network(ctx.clone(), &network).await.unwrap();
+        let execution = ctx
+            .lock()
+            .await
+            .chain
+            .db
+            .get(Key::WithdrawalExecution(burn_id))
+            .unwrap()
+            .unwrap()
+            .as_withdrawal_execution()
+            .unwrap();
+        assert_eq!(
+            execution,
+            WithdrawalExecution {
+                chain_id: 1,
+                l1_tx_hash: executed,
+                block_number: 101,
+            }
+        );
+    }
+}
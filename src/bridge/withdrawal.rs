@@ -0,0 +1,297 @@
+use alloy::{
+    primitives::{keccak256, Address, Bytes, FixedBytes, U256},
+    signers::Signer,
+    sol,
+    sol_types::SolValue,
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blockchain::{Blockchain, Owshenchain},
+    db::{Key, Value},
+    services::ContextKvStore,
+    types::{Burn, CustomTxMsg, OwshenTransaction, Token, WithdrawCalldata},
+};
+
+sol! {
+    event WithdrawExecuted(address indexed to, address indexed token, uint256 id, uint256 amount);
+}
+
+/// Everything needed to call `withdrawNative`/`withdrawToken` of Owshen.sol
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalCertificate {
+    pub burn_id: FixedBytes<32>,
+    /// The only address able to redeem the certificate, `msg.sender` on L1
+    pub to: Address,
+    /// `Address::ZERO` stands for the native coin, same as in Owshen.sol
+    pub token: Address,
+    pub amount: U256,
+    /// `_id` argument of the withdraw functions
+    pub id: U256,
+    pub chain_id: u64,
+    pub signature: Bytes,
+}
+
+/// `keccak256(abi.encode(msg.sender, token, amount, id, block.chainid))`, as
+/// checked by `Owshen.sol::_processWithdraw`
+pub fn withdrawal_hash(
+    to: Address,
+    token: Address,
+    amount: U256,
+    id: U256,
+    chain_id: u64,
+) -> FixedBytes<32> {
+    keccak256((to, token, amount, id, U256::from(chain_id)).abi_encode_params())
+}
+
+/// The `to`, `token` and `_id` arguments of the withdraw call redeeming `burn`
+fn withdrawal_args(burn: &Burn) -> Result<(Address, Address, U256)> {
+    let to = match burn.calldata {
+        Some(WithdrawCalldata::Eth { address }) => address,
+        _ => return Err(anyhow!("Invalid calldata!")),
+    };
+    let token = match &burn.token {
+        Token::Native => Address::ZERO,
+        Token::Erc20(erc20) => erc20.address,
+    };
+    Ok((to, token, U256::from_be_bytes(burn.burn_id.0)))
+}
+
+impl WithdrawalCertificate {
+    pub async fn sign<S: Signer + Sync>(signer: &S, burn: &Burn) -> Result<Self> {
+        let (to, token, id) = withdrawal_args(burn)?;
+        let chain_id = burn.chain_id;
+
+        // The contract checks the signature against the raw hash, no EIP-191 prefix
+        let hash = withdrawal_hash(to, token, burn.amount, id, chain_id);
+        let signature = signer.sign_hash(&hash).await?;
+
+        Ok(Self {
+            burn_id: burn.burn_id,
+            to,
+            token,
+            amount: burn.amount,
+            id,
+            chain_id,
+            signature: Bytes::from(signature.as_bytes().to_vec()),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn signer(&self) -> Result<Address> {
+        let hash = withdrawal_hash(self.to, self.token, self.amount, self.id, self.chain_id);
+        let signature = alloy::primitives::Signature::try_from(self.signature.as_ref())?;
+        Ok(signature.recover_address_from_prehash(&hash)?)
+    }
+}
+
+/// A certificate redeemed on L1, as seen in a `WithdrawExecuted` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalExecution {
+    pub chain_id: u64,
+    pub l1_tx_hash: FixedBytes<32>,
+    pub block_number: u64,
+}
+
+/// The burn included in the chain under `burn_id`, if any
+pub fn get_burn<K: ContextKvStore>(
+    chain: &Owshenchain<K>,
+    burn_id: FixedBytes<32>,
+) -> Result<Option<Burn>> {
+    let tx_hash = match chain.db.get(Key::BurnId(burn_id))? {
+        Some(v) => FixedBytes::from(v.as_u256()?.to_be_bytes::<32>()),
+        None => return Ok(None),
+    };
+    let tx: OwshenTransaction = chain.get_transaction_by_hash(tx_hash)?.tx.try_into()?;
+    match tx {
+        OwshenTransaction::Custom(custom_tx) => match custom_tx.msg()? {
+            CustomTxMsg::BurnTx(burn) => Ok(Some(burn)),
+            _ => Err(anyhow!("Inconsistency!")),
+        },
+        _ => Err(anyhow!("Inconsistency!")),
+    }
+}
+
+/// Records the execution of the burn redeemed by `event`. Events not matching
+/// a burn included for `chain_id` are ignored.
+pub fn record_withdrawal_execution<K: ContextKvStore>(
+    chain: &mut Owshenchain<K>,
+    chain_id: u64,
+    event: &WithdrawExecuted,
+    l1_tx_hash: FixedBytes<32>,
+    block_number: u64,
+) -> Result<bool> {
+    let burn_id = FixedBytes::from(event.id.to_be_bytes::<32>());
+    let burn = match get_burn(chain, burn_id)? {
+        Some(burn) => burn,
+        None => return Ok(false),
+    };
+    let (to, token, _) = withdrawal_args(&burn)?;
+    if burn.chain_id != chain_id
+        || to != event.to
+        || token != event.token
+        || burn.amount != event.amount
+    {
+        return Ok(false);
+    }
+    chain.db.put(
+        Key::WithdrawalExecution(burn_id),
+        Some(Value::WithdrawalExecution(WithdrawalExecution {
+            chain_id,
+            l1_tx_hash,
+            block_number,
+        })),
+    )?;
+    Ok(true)
+}
+
+/// Signs the certificate of the burn included under `burn_id`. Signatures are
+/// deterministic, so the same certificate is returned on every call.
+pub async fn certify_withdrawal<S: Signer + Sync, K: ContextKvStore>(
+    chain: &Owshenchain<K>,
+    signer: &S,
+    burn_id: FixedBytes<32>,
+) -> Result<WithdrawalCertificate> {
+    let burn = get_burn(chain, burn_id)?.ok_or(anyhow!("Withdrawal certificate not found!"))?;
+    WithdrawalCertificate::sign(signer, &burn).await
+}
+
+/// Funds `user` and includes its `burn` in a new block
+#[cfg(test)]
+pub async fn push_burn_block<K: ContextKvStore>(
+    chain: &mut Owshenchain<K>,
+    user: &alloy::signers::local::PrivateKeySigner,
+    burn: Burn,
+) -> Result<()> {
+    use crate::{blockchain::TransactionQueue, types::CustomTx};
+
+    chain.db.put(
+        Key::Balance(user.address(), burn.token.clone()),
+        Some(Value::U256(burn.amount)),
+    )?;
+    let chain_id = chain.config().chain_id;
+    let tx = CustomTx::create(&mut user.clone(), chain_id, CustomTxMsg::BurnTx(burn)).await?;
+    let mut tx_queue = TransactionQueue::new();
+    tx_queue.enqueue(tx);
+    let blk = chain.draft_block(&mut tx_queue, 0)?;
+    if blk.txs.len() != 1 {
+        return Err(anyhow!("Burn was not included!"));
+    }
+    chain.push_block(blk)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::local::PrivateKeySigner;
+
+    use super::*;
+    use crate::{blockchain::Config, db::RamKvStore};
+
+    #[test]
+    fn test_withdrawal_hash() {
+        let to = Address::from([1; 20]);
+        let token = Address::from([2; 20]);
+        let amount = U256::from(3);
+        let id = U256::from(4);
+
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(&[0u8; 12]);
+        encoded.extend_from_slice(to.as_slice());
+        encoded.extend_from_slice(&[0u8; 12]);
+        encoded.extend_from_slice(token.as_slice());
+        encoded.extend_from_slice(&amount.to_be_bytes::<32>());
+        encoded.extend_from_slice(&id.to_be_bytes::<32>());
+        encoded.extend_from_slice(&U256::from(5).to_be_bytes::<32>());
+
+        assert_eq!(
+            withdrawal_hash(to, token, amount, id, 5),
+            keccak256(encoded)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_certify_withdrawal() {
+        let conf = Config::for_tests();
+        let mut chain = Owshenchain::new(conf, RamKvStore::new());
+
+        let node = PrivateKeySigner::random();
+        let user = PrivateKeySigner::random();
+        let burn = Burn {
+            burn_id: FixedBytes::from([7u8; 32]),
+            chain_id: 1,
+            token: Token::Native,
+            amount: U256::from(100),
+            calldata: Some(WithdrawCalldata::Eth {
+                address: user.address(),
+            }),
+            nonce: U256::from(0),
+        };
+        assert!(certify_withdrawal(&chain, &node, burn.burn_id)
+            .await
+            .is_err());
+        push_burn_block(&mut chain, &user, burn.clone())
+            .await
+            .unwrap();
+        assert_eq!(get_burn(&chain, burn.burn_id).unwrap(), Some(burn.clone()));
+
+        let certificate = certify_withdrawal(&chain, &node, burn.burn_id)
+            .await
+            .unwrap();
+        assert_eq!(
+            certify_withdrawal(&chain, &node, burn.burn_id)
+                .await
+                .unwrap(),
+            certificate
+        );
+        assert_eq!(certificate.to, user.address());
+        assert_eq!(certificate.token, Address::ZERO);
+        assert_eq!(certificate.amount, burn.amount);
+        assert_eq!(certificate.id, U256::from_be_bytes([7u8; 32]));
+        assert_eq!(certificate.chain_id, 1);
+        assert_eq!(certificate.signature.len(), 65);
+        assert_eq!(certificate.signer().unwrap(), node.address());
+    }
+
+    #[tokio::test]
+    async fn test_record_withdrawal_execution() {
+        let conf = Config::for_tests();
+        let mut chain = Owshenchain::new(conf, RamKvStore::new());
+
+        let user = PrivateKeySigner::random();
+        let burn = Burn {
+            burn_id: FixedBytes::from([7u8; 32]),
+            chain_id: 1,
+            token: Token::Native,
+            amount: U256::from(100),
+            calldata: Some(WithdrawCalldata::Eth {
+                address: user.address(),
+            }),
+            nonce: U256::from(0),
+        };
+        let event = WithdrawExecuted {
+            to: user.address(),
+            token: Address::ZERO,
+            id: U256::from_be_bytes(burn.burn_id.0),
+            amount: burn.amount,
+        };
+        let l1_tx_hash = FixedBytes::from([1u8; 32]);
+
+        // Nothing was certified, the included burn is enough
+        push_burn_block(&mut chain, &user, burn.clone())
+            .await
+            .unwrap();
+        assert!(!record_withdrawal_execution(&mut chain, 2, &event, l1_tx_hash, 10).unwrap());
+        let forged = WithdrawExecuted {
+            amount: burn.amount + U256::from(1),
+            ..event.clone()
+        };
+        assert!(!record_withdrawal_execution(&mut chain, 1, &forged, l1_tx_hash, 10).unwrap());
+        assert!(record_withdrawal_execution(&mut chain, 1, &event, l1_tx_hash, 10).unwrap());
+
+        // Undone burns can't be redeemed
+        chain.pop_block().unwrap();
+        assert!(get_burn(&chain, burn.burn_id).unwrap().is_none());
+        assert!(!record_withdrawal_execution(&mut chain, 1, &event, l1_tx_hash, 10).unwrap());
+    }
+}
@@ -1,11 +1,15 @@
 use std::path::PathBuf;
 
+use alloy::primitives::Address;
 use alloy::signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner};
-use anyhow::{Ok, Result};
+use anyhow::{anyhow, Ok, Result};
 
 mod node;
 
+use crate::blockchain::gas::{BlockLimits, BLOCK_GAS_LIMIT, MAX_BLOCK_SIZE};
+use crate::config;
 use crate::db::{DiskKvStore, RamKvStore};
+use crate::types::network::Network;
 use hex::FromHex;
 use structopt::StructOpt;
 
@@ -19,8 +23,16 @@ struct StartOpt {
     db: Option<PathBuf>,
     #[structopt(long)]
     private_key: Option<String>,
-    #[structopt(long, default_value = "https://eth.llamarpc.com")]
-    provider_address: reqwest::Url,
+    /// Bridged network as `<chain_id>,<owshen>,<provider_address>,<confirmations>`,
+    /// can be given multiple times (Defaults to Ethereum mainnet)
+    #[structopt(long = "network")]
+    networks: Vec<Network>,
+    /// Most gas a produced block may use (Defaults to the protocol limit)
+    #[structopt(long)]
+    block_gas_limit: Option<u64>,
+    /// Largest produced block in bytes (Defaults to the protocol limit)
+    #[structopt(long)]
+    max_block_size: Option<u64>,
 }
 
 impl StartOpt {
@@ -42,12 +54,58 @@ impl StartOpt {
 
         Ok(signer)
     }
+
+    fn networks(&self) -> Vec<Network> {
+        if self.networks.is_empty() {
+            vec![Network {
+                chain_id: 1,
+                owshen: config::OWSHEN_CONTRACT,
+                provider_address: "https://eth.llamarpc.com".parse().expect("valid url"),
+                confirmations: 12,
+            }]
+        } else {
+            self.networks.clone()
+        }
+    }
+
+    fn block_limits(&self) -> Result<BlockLimits> {
+        let limits = BlockLimits {
+            gas: self.block_gas_limit.unwrap_or(BLOCK_GAS_LIMIT),
+            size: self.max_block_size.unwrap_or(MAX_BLOCK_SIZE),
+        };
+        if limits.gas > BLOCK_GAS_LIMIT || limits.size > MAX_BLOCK_SIZE {
+            return Err(anyhow!("Block limits can't exceed the protocol limits!"));
+        }
+        Ok(limits)
+    }
+}
+
+/// Replicates the chain of a sequencer and serves it read-only
+#[derive(Debug, StructOpt)]
+struct FollowOpt {
+    #[structopt(long, default_value = "3000")]
+    api_port: u16,
+    #[structopt(long, default_value = "8645")]
+    rpc_port: u16,
+    #[structopt(long)]
+    db: Option<PathBuf>,
+    /// RPC endpoint of the sequencer the blocks are fetched from
+    #[structopt(long)]
+    upstream: String,
+    /// Address the sequencer signs its blocks with
+    #[structopt(long)]
+    owner: Address,
+    /// Networks bridged by the sequencer, in the same format as for `start`.
+    /// There is no default, they have to match the sequencer.
+    #[structopt(long = "network", required = true)]
+    networks: Vec<Network>,
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "Owshen", about = "Owshen node software!")]
 enum Opt {
     Start(StartOpt),
+    Follow(FollowOpt),
     Debug,
 }
 
@@ -56,12 +114,15 @@ pub async fn cli() -> Result<()> {
     match opt {
         Opt::Start(opt) => {
             let signing_key = opt.parse_signing_key()?;
+            let networks = opt.networks();
+            let block_limits = opt.block_limits()?;
             if let Some(db) = opt.db {
                 node::run_node(
                     DiskKvStore::new(db, 128)?,
                     opt.api_port,
                     opt.rpc_port,
-                    opt.provider_address,
+                    networks,
+                    block_limits,
                     signing_key,
                 )
                 .await?;
@@ -70,12 +131,36 @@ pub async fn cli() -> Result<()> {
                     RamKvStore::new(),
                     opt.api_port,
                     opt.rpc_port,
-                    opt.provider_address,
+                    networks,
+                    block_limits,
                     signing_key,
                 )
                 .await?;
             }
         }
+        Opt::Follow(opt) => {
+            if let Some(db) = opt.db {
+                node::run_follower(
+                    DiskKvStore::new(db, 128)?,
+                    opt.api_port,
+                    opt.rpc_port,
+                    opt.networks,
+                    opt.upstream,
+                    opt.owner,
+                )
+                .await?;
+            } else {
+                node::run_follower(
+                    RamKvStore::new(),
+                    opt.api_port,
+                    opt.rpc_port,
+                    opt.networks,
+                    opt.upstream,
+                    opt.owner,
+                )
+                .await?;
+            }
+        }
         Opt::Debug => {
             println!("Nothing to do!");
         }
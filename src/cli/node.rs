@@ -4,23 +4,30 @@ use std::{
 };
 
 use alloy::{
-    primitives::U256,
+    primitives::{Address, U256},
     signers::{local::PrivateKeySigner, Signer},
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use jsonrpsee::{
+    core::client::ClientT,
+    http_client::{HttpClient, HttpClientBuilder},
+    rpc_params,
+};
 use tokio::sync::Mutex;
 
 use crate::{
-    blockchain::{Blockchain, Config, Owshenchain, TransactionQueue},
+    blockchain::{events, gas::BlockLimits, Blockchain, Config, Owshenchain, TransactionQueue},
+    bridge::{deposit_watcher, renumber_mints},
     config,
     db::KvStore,
     genesis::GENESIS,
     safe_signer::SafeSigner,
     services::{
+        filters::Filters,
         server::{api_server, rpc_server},
         Context, ContextKvStore, ContextSigner,
     },
-    types::{CustomTx, CustomTxMsg, Mint, Token},
+    types::{network::Network, Block, CustomTx, CustomTxMsg, Mint, Token},
 };
 
 async fn block_producer<S: ContextSigner, K: ContextKvStore>(
@@ -42,6 +49,8 @@ async fn block_producer<S: ContextSigner, K: ContextKvStore>(
             blk = blk.signed(ctx.signer.clone()).await?;
             ctx.chain.push_block(blk.clone())?;
             log::info!("Produced a new block: {}", blk.index);
+            ctx.publish_block(&blk)?;
+            renumber_mints(&mut ctx).await?;
             Ok::<(), anyhow::Error>(())
         }
         .await
@@ -52,11 +61,61 @@ async fn block_producer<S: ContextSigner, K: ContextKvStore>(
     }
 }
 
+/// Pushes the blocks the sequencer at the other end of `client` has and we
+/// don't. `push_block` checks each of them is signed by the chain owner and
+/// executes it again. Returns the number of blocks pushed.
+async fn sync_blocks<S: ContextSigner, K: ContextKvStore>(
+    ctx: &Arc<Mutex<Context<S, K>>>,
+    client: &HttpClient,
+) -> Result<usize> {
+    let mut synced = 0;
+    loop {
+        let height = ctx.lock().await.chain.get_height()?;
+        let raw_blocks: Vec<String> = client
+            .request(
+                "owshen_getRawBlocks",
+                rpc_params![format!("0x{:x}", height)],
+            )
+            .await?;
+        if raw_blocks.is_empty() {
+            return Ok(synced);
+        }
+
+        let mut ctx = ctx.lock().await;
+        for raw_block in raw_blocks {
+            let block: Block =
+                bincode::deserialize(&hex::decode(raw_block.trim_start_matches("0x"))?)?;
+            ctx.chain.push_block(block.clone())?;
+            log::info!("Synced block: {}", block.index);
+            ctx.publish_block(&block)?;
+            synced += 1;
+        }
+    }
+}
+
+async fn block_follower<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    upstream: String,
+) -> Result<()> {
+    let client = HttpClientBuilder::default().build(upstream)?;
+    loop {
+        if ctx.lock().await.exit {
+            log::info!("Terminating the block follower...");
+            return Ok(());
+        }
+        if let Err(e) = sync_blocks(&ctx, &client).await {
+            log::info!("Error while syncing blocks: {}", e);
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    }
+}
+
 pub async fn run_node<K: ContextKvStore + 'static>(
     db: K,
     api_port: u16,
     rpc_port: u16,
-    provider_address: reqwest::Url,
+    networks: Vec<Network>,
+    block_limits: BlockLimits,
     private_key: PrivateKeySigner,
 ) -> Result<()> {
     let signer = SafeSigner::new(private_key);
@@ -64,17 +123,30 @@ pub async fn run_node<K: ContextKvStore + 'static>(
         chain_id: config::CHAIN_ID,
         owner: Some(signer.address()),
         genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address,
+        networks,
     };
 
+    let events = events::channel();
     let ctx = Arc::new(Mutex::new(Context {
         signer: signer.clone(),
         exit: false,
-        tx_queue: TransactionQueue::new(),
-        chain: Owshenchain::new(conf.clone(), db),
+        read_only: false,
+        tx_queue: TransactionQueue::with_events(events.clone()),
+        filters: Filters::new(),
+        events,
+        chain: Owshenchain::new(conf.clone(), db).with_block_limits(block_limits),
     }));
 
+    let network_id = conf
+        .networks
+        .first()
+        .ok_or(anyhow!("At least one network is required!"))?
+        .chain_id;
+    let nonce = {
+        let ctx = ctx.lock().await;
+        ctx.tx_queue
+            .next_custom_nonce(&ctx.chain, signer.address())?
+    };
     let tx = CustomTx::create(
         &mut signer.clone(),
         conf.chain_id,
@@ -84,12 +156,15 @@ pub async fn run_node<K: ContextKvStore + 'static>(
             token: Token::Native,
             amount: U256::from(100),
             address: PrivateKeySigner::random().address(),
+            nonce,
+            chain_id: network_id,
         }),
     )
     .await?;
     ctx.lock().await.tx_queue.enqueue(tx);
 
     let block_producer_fut = block_producer(ctx.clone());
+    let deposit_watcher_fut = deposit_watcher(ctx.clone());
     let api_server_fut = api_server(ctx.clone(), api_port);
     let rpc_server_fut = rpc_server(ctx.clone(), rpc_port);
 
@@ -98,7 +173,143 @@ pub async fn run_node<K: ContextKvStore + 'static>(
         println!("Failed to open web browser. Please navigate to http://{entrypoint} manually");
     }
 
-    tokio::try_join!(block_producer_fut, api_server_fut, rpc_server_fut)?;
+    tokio::try_join!(
+        block_producer_fut,
+        deposit_watcher_fut,
+        api_server_fut,
+        rpc_server_fut
+    )?;
+
+    Ok(())
+}
+
+/// Replicates the chain of the sequencer at `upstream`, signed by `owner`, and
+/// serves it read-only. L1 isn't watched and nothing is signed here, so the
+/// withdrawal endpoints refuse to answer and are left to the sequencer.
+pub async fn run_follower<K: ContextKvStore + 'static>(
+    db: K,
+    api_port: u16,
+    rpc_port: u16,
+    networks: Vec<Network>,
+    upstream: String,
+    owner: Address,
+) -> Result<()> {
+    let conf = Config {
+        chain_id: config::CHAIN_ID,
+        owner: Some(owner),
+        genesis: GENESIS.clone(),
+        networks,
+    };
+
+    let events = events::channel();
+    let ctx = Arc::new(Mutex::new(Context {
+        // Never used, followers produce no blocks
+        signer: SafeSigner::new(PrivateKeySigner::random()),
+        exit: false,
+        read_only: true,
+        tx_queue: TransactionQueue::with_events(events.clone()),
+        filters: Filters::new(),
+        events,
+        chain: Owshenchain::new(conf, db),
+    }));
+
+    tokio::try_join!(
+        block_follower(ctx.clone(), upstream),
+        api_server(ctx.clone(), api_port),
+        rpc_server(ctx.clone(), rpc_port)
+    )?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::RamKvStore;
+
+    fn follower_ctx(owner: Address) -> Arc<Mutex<Context<SafeSigner, RamKvStore>>> {
+        let conf = Config {
+            chain_id: config::CHAIN_ID,
+            owner: Some(owner),
+            genesis: GENESIS.clone(),
+            networks: Vec::new(),
+        };
+        let events = events::channel();
+        Arc::new(Mutex::new(Context {
+            signer: SafeSigner::new(PrivateKeySigner::random()),
+            exit: false,
+            read_only: true,
+            tx_queue: TransactionQueue::with_events(events.clone()),
+            filters: Filters::new(),
+            events,
+            chain: Owshenchain::new(conf, RamKvStore::new()),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_sync_blocks() {
+        let signer = SafeSigner::new(PrivateKeySigner::random());
+        let sequencer = follower_ctx(signer.address());
+        {
+            let mut ctx = sequencer.lock().await;
+            ctx.signer = signer.clone();
+            ctx.read_only = false;
+            for timestamp in 0..3 {
+                let blk = ctx
+                    .chain
+                    .draft_block(&mut TransactionQueue::new(), timestamp)
+                    .unwrap()
+                    .signed(signer.clone())
+                    .await
+                    .unwrap();
+                ctx.chain.push_block(blk).unwrap();
+            }
+        }
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        tokio::spawn(rpc_server(sequencer.clone(), port));
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let client = HttpClientBuilder::default()
+            .build(format!("http://127.0.0.1:{}", port))
+            .unwrap();
+
+        let follower = follower_ctx(signer.address());
+        let mut new_heads = follower.lock().await.events.subscribe();
+        assert_eq!(sync_blocks(&follower, &client).await.unwrap(), 3);
+        assert_eq!(sync_blocks(&follower, &client).await.unwrap(), 0);
+        assert!(matches!(
+            new_heads.recv().await.unwrap(),
+            events::ChainEvent::Block { block, .. } if block.index == 0
+        ));
+        for index in 0..3 {
+            assert_eq!(
+                follower
+                    .lock()
+                    .await
+                    .chain
+                    .get_block(index)
+                    .unwrap()
+                    .hash()
+                    .unwrap(),
+                sequencer
+                    .lock()
+                    .await
+                    .chain
+                    .get_block(index)
+                    .unwrap()
+                    .hash()
+                    .unwrap()
+            );
+        }
+        assert!(follower.lock().await.check_writable().is_err());
+
+        // Blocks not signed by the expected owner are refused
+        let impostor = follower_ctx(PrivateKeySigner::random().address());
+        assert!(sync_blocks(&impostor, &client).await.is_err());
+        assert_eq!(impostor.lock().await.chain.get_height().unwrap(), 0);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::blockchain::{Blockchain, Config, Owshenchain};
 use crate::config;
 use crate::services::{Context, ContextKvStore, ContextSigner}; // Your own services module
-use crate::types::OwshenTransaction;
+use crate::types::{network::Network, OwshenTransaction};
 use crate::{db::RamKvStore, genesis::GENESIS};
 use anyhow::anyhow;
 use axum::response::Html;
@@ -49,8 +49,12 @@ pub async fn block_details_handler<S: ContextSigner, K: ContextKvStore>(
         chain_id: 1387,
         owner: None,
         genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
+        networks: vec![Network {
+            chain_id: 1,
+            owshen: config::OWSHEN_CONTRACT,
+            provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
+            confirmations: 1,
+        }],
     };
 
     let chain: Owshenchain<RamKvStore> = Owshenchain::new(conf, RamKvStore::new());
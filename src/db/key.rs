@@ -22,6 +22,14 @@ pub enum Key {
     BurnId(FixedBytes<32>),
     TokenDecimal(Address),
     TokenSymbol(Address),
+    DepositCursor(u64),
+    WithdrawalExecution(FixedBytes<32>),
+    Receipt(FixedBytes<32>),
+    /// Node of the state tree by level and path, see `blockchain::state`.
+    /// Nodes are overwritten in place, so only proofs against the latest
+    /// state root can be built from them.
+    StateNode(u16, U256),
+    LogIndex(FixedBytes<32>, usize),
 }
 
 impl TryInto<Blob> for Key {
@@ -37,3 +45,10 @@ impl TryInto<Blob> for &Key {
         Ok(Blob(bincode::serialize(&self)?))
     }
 }
+
+impl TryFrom<&Blob> for Key {
+    type Error = anyhow::Error;
+    fn try_from(blob: &Blob) -> anyhow::Result<Key> {
+        Ok(bincode::deserialize(&blob.0)?)
+    }
+}
@@ -16,6 +16,9 @@ impl<'a, K: ContextKvStore> MirrorKvStore<'a, K> {
             overwrite: BTreeMap::new(),
         }
     }
+    pub fn overwrites(&self) -> &BTreeMap<Blob, Option<Blob>> {
+        &self.overwrite
+    }
     pub fn rollback(&self) -> Result<BTreeMap<Blob, Option<Blob>>> {
         let old_vals = self
             .overwrite
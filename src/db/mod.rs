@@ -18,6 +18,12 @@ use std::collections::BTreeMap;
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize, Hash)]
 pub struct Blob(Vec<u8>);
 
+impl AsRef<[u8]> for Blob {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 // TODO‌: Range Search
 // self.db.range(range)
 
@@ -1,5 +1,8 @@
 use super::Blob;
-use crate::types::{BincodableOwshenTransaction, Block, IncludedTransaction, OwshenTransaction};
+use crate::bridge::WithdrawalExecution;
+use crate::types::{
+    BincodableOwshenTransaction, Block, IncludedTransaction, OwshenTransaction, Receipt,
+};
 use alloy::primitives::U256;
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
@@ -17,8 +20,10 @@ pub enum Value {
     Transaction(IncludedTransaction),
     Transactions(Vec<IncludedTransaction>),
     DepositedTransaction(String),
-    Symbol(String)
-
+    Symbol(String),
+    WithdrawalExecution(WithdrawalExecution),
+    Receipt(Receipt),
+    VecUsize(Vec<usize>),
 }
 
 impl TryInto<Blob> for Value {
@@ -75,4 +80,22 @@ impl Value {
             _ => Err(anyhow!("Unexpected type!")),
         }
     }
+    pub fn as_withdrawal_execution(&self) -> Result<WithdrawalExecution> {
+        match self {
+            Value::WithdrawalExecution(v) => Ok(v.clone()),
+            _ => Err(anyhow!("Unexpected type!")),
+        }
+    }
+    pub fn as_receipt(&self) -> Result<Receipt> {
+        match self {
+            Value::Receipt(v) => Ok(v.clone()),
+            _ => Err(anyhow!("Unexpected type!")),
+        }
+    }
+    pub fn as_vec_usize(&self) -> Result<Vec<usize>> {
+        match self {
+            Value::VecUsize(v) => Ok(v.clone()),
+            _ => Err(anyhow!("Unexpected type!")),
+        }
+    }
 }
@@ -1,4 +1,5 @@
 mod blockchain;
+mod bridge;
 mod cli;
 mod client;
 mod config;
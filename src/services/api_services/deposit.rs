@@ -1,154 +1,134 @@
-use alloy::{
-    primitives::{Address, FixedBytes, U256},
-    providers::{Provider, ProviderBuilder},
-};
+use alloy::primitives::FixedBytes;
 use axum::Json;
 use serde::{Deserialize, Serialize};
-use std::{clone, sync::Arc};
+use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use super::Context;
 use crate::{
     blockchain::Blockchain,
-    db::Value,
+    bridge::{enqueue_mint, is_mint_of, DepositVerifier},
+    db::{Key, Value},
     services::{ContextKvStore, ContextSigner},
-    types::{CustomTx, CustomTxMsg, Mint, Token, ERC20},
 };
 
+/// Token, amount and receiver of the mint are all taken from the L1
+/// transaction itself, never from the request.
 #[derive(Debug, Deserialize, Clone)]
 pub struct DepositRequest {
+    /// Network the deposit was made on
+    pub chain_id: u64,
     pub tx_hash: String,
-    pub token: String,
-    pub amount: U256,
-    pub address: Address,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DepositStatus {
+    Pending,
+    Included,
+    Rejected,
 }
 
 #[derive(Serialize, Clone)]
 pub struct DepositResponse {
     pub owshen_tx_hash: Option<FixedBytes<32>>,
     pub success: bool,
+    pub status: DepositStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
 }
 
-pub async fn deposit_handler<S: ContextSigner, K: ContextKvStore>(
-    ctx: Arc<Mutex<Context<S, K>>>,
-    Json(payload): Json<DepositRequest>,
-) -> Result<Json<DepositResponse>, anyhow::Error> {
-    let mut _ctx = ctx.lock().await;
+/// Where the mint of an L1 deposit currently is, if it was ever submitted
+fn deposit_status<S: ContextSigner, K: ContextKvStore>(
+    ctx: &Context<S, K>,
+    user_tx_hash: &str,
+) -> Result<Option<DepositResponse>, anyhow::Error> {
+    if let Some(v) = ctx
+        .chain
+        .db
+        .get(Key::DepositedTransaction(user_tx_hash.to_string()))?
+    {
+        let owshen_tx_hash = match v {
+            Value::DepositedTransaction(hash) => hash.parse().ok(),
+            _ => return Err(anyhow::anyhow!("Unexpected value type")),
+        };
+        return Ok(Some(DepositResponse {
+            owshen_tx_hash,
+            success: true,
+            status: DepositStatus::Included,
+            reason: None,
+        }));
+    }
 
-    let contract_address = _ctx.chain.config().owshen;
-    let t = payload.clone().token;
+    if let Some(tx) = ctx
+        .tx_queue
+        .queue()
+        .iter()
+        .find(|tx| is_mint_of(tx, user_tx_hash))
+    {
+        return Ok(Some(DepositResponse {
+            owshen_tx_hash: Some(tx.hash()?),
+            success: true,
+            status: DepositStatus::Pending,
+            reason: None,
+        }));
+    }
 
-    let token = match t.as_str() {
-        "native" => Token::Native,
-        "erc20" => {
-            let addr = Address::from_slice(&hex::decode(t.trim_start_matches("0x"))?);
-            let decimals = _ctx.chain.get_token_decimal(addr)?;
-            let symbol = _ctx.chain.get_token_symbol(addr)?;
-            Token::Erc20(ERC20 {
-                address: addr,
-                decimals,
-                symbol,
-            })
-        }
-        _ => {
-            return Err(anyhow::anyhow!("Invalid token type: {}", t));
-        }
-    };
+    if let Some((tx, reason)) = ctx
+        .tx_queue
+        .rejected()
+        .iter()
+        .rev()
+        .find(|(tx, _)| is_mint_of(tx, user_tx_hash))
+    {
+        return Ok(Some(DepositResponse {
+            owshen_tx_hash: Some(tx.hash()?),
+            success: false,
+            status: DepositStatus::Rejected,
+            reason: Some(reason.clone()),
+        }));
+    }
 
-    let amount = payload.amount;
-    let address = payload.address;
-    let tx_hash = payload.tx_hash;
-    let tx_hash_bytes = hex::decode(tx_hash.trim_start_matches("0x"))?;
-    let tx_hash_fixed_bytes = alloy::primitives::FixedBytes::from_slice(&tx_hash_bytes);
+    Ok(None)
+}
 
-    let chain_id = _ctx.chain.config().chain_id;
-    let to_addr = match chain_id {
-        1 => {
-            const PROVIDER_API_KEY: &str = "YOUR_INFURA_PROJECT_ID";
-            let provider_url =
-                format!("https://mainnet.infura.io/v3/{}", PROVIDER_API_KEY).parse()?;
-            let provider = ProviderBuilder::new().on_http(provider_url);
-            let res = match Provider::get_transaction_by_hash(&provider, tx_hash_fixed_bytes).await
-            {
-                Ok(Some(transaction)) => transaction,
-                Ok(None) => {
-                    return Err(anyhow::anyhow!("Transaction not found"));
-                }
-                Err(e) => {
-                    return Err(anyhow::anyhow!(
-                        "Failed to get transaction by hash: {:?}",
-                        e
-                    ));
-                }
-            };
+fn is_settled(status: &DepositResponse) -> bool {
+    status.status != DepositStatus::Rejected
+}
 
-            let to_address = match res.to {
-                Some(address) => address,
-                None => {
-                    return Err(anyhow::anyhow!("Transaction does not have a 'to' address"));
-                }
-            };
-            to_address
-        }
-        1387 => contract_address,
-        _ => {
-            return Err(anyhow::anyhow!("Unsupported chain_id: {}", chain_id));
+pub async fn deposit_handler<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    Json(payload): Json<DepositRequest>,
+) -> Result<Json<DepositResponse>, anyhow::Error> {
+    let tx_hash_fixed_bytes: FixedBytes<32> = payload.tx_hash.parse()?;
+    let tx_hash = tx_hash_fixed_bytes.to_string();
+
+    let verifier = {
+        let _ctx = ctx.lock().await;
+        // Rejections might be transient, like a gap in the nonces of the node,
+        // so rejected deposits are verified and minted again
+        if let Some(status) = deposit_status(&_ctx, &tx_hash)?.filter(is_settled) {
+            return Ok(Json(status));
         }
+        _ctx.check_writable()?;
+        DepositVerifier::new(_ctx.chain.config().network(payload.chain_id)?)
     };
 
-    if to_addr == contract_address {
-        let raw_tx_bytes = hex::decode(tx_hash.trim_start_matches("0x"))?;
-        let hah: &[u8] = raw_tx_bytes.as_ref();
-        let tx = CustomTx::create(
-            &mut _ctx.signer.clone(),
-            chain_id,
-            CustomTxMsg::MintTx(Mint {
-                tx_hash: hah.to_vec(),
-                user_tx_hash: tx_hash.clone(),
-                token,
-                amount,
-                address,
-            }),
-        )
-        .await?;
-
-        if _ctx
-            .chain
-            .db
-            .get(crate::db::Key::DepositedTransaction(tx_hash.clone()))?
-            .is_some()
-        {
-            return Err(anyhow::anyhow!("Transaction already exists"));
-        }
-
-        _ctx.chain.db.put(
-            crate::db::Key::DepositedTransaction(tx_hash.clone()),
-            Some(Value::DepositedTransaction(tx_hash)),
-        )?;
-
-        let queue = &mut _ctx.tx_queue.queue();
+    // Don't hold the context while waiting on the L1 provider
+    let deposit = verifier.verify(tx_hash_fixed_bytes).await?;
 
-        if queue.iter().any(|t| match t.hash() {
-            Ok(hash) => hash == tx.hash().unwrap_or_default(),
-            Err(_) => false,
-        }) {
-            return Err(anyhow::anyhow!("Transaction already exists"));
-        }
+    let mut _ctx = ctx.lock().await;
+    // The same deposit might have been submitted in the meantime
+    if let Some(status) = deposit_status(&_ctx, &tx_hash)?.filter(is_settled) {
+        return Ok(Json(status));
+    }
 
-        if _ctx
-            .chain
-            .db
-            .get(crate::db::Key::TransactionHash(tx.hash()?))?
-            .is_some()
-        {
-            return Err(anyhow::anyhow!("Transaction already exists"));
-        }
+    let owshen_tx_hash = enqueue_mint(&mut _ctx, &deposit).await?;
 
-        return Ok(Json(DepositResponse {
-            owshen_tx_hash: Some(tx.hash()?),
-            success: true,
-        }));
-    } else {
-        return Err(anyhow::anyhow!("Transaction is invalid!"));
-    }
+    Ok(Json(DepositResponse {
+        owshen_tx_hash: Some(owshen_tx_hash),
+        success: true,
+        status: DepositStatus::Pending,
+        reason: None,
+    }))
 }
@@ -2,6 +2,7 @@ pub mod deposit;
 pub mod explorer;
 pub mod test;
 pub mod withdraw;
+pub mod withdrawal_certificate;
 pub mod withdrawals;
 
 use super::{Context, ContextKvStore, ContextSigner};
@@ -29,6 +30,7 @@ use tokio::sync::Mutex;
 
 use utils::handle_error;
 use withdraw::{withdraw_handler, WithdrawRequest};
+use withdrawal_certificate::{withdrawal_certificate_handler, WithdrawalCertificateRequest};
 use withdrawals::{withdrawals_handler, WithdrawalsRequest};
 
 pub async fn css_handler_endpoint() -> impl IntoResponse {
@@ -117,6 +119,17 @@ where
                 }
             }),
         )
+        .route(
+            "/withdrawal-certificate",
+            get({
+                let ctx = ctx.clone();
+                move |Json(req): Json<WithdrawalCertificateRequest>| async move {
+                    handle_error(
+                        withdrawal_certificate_handler(ctx.clone(), extract::Json(req)).await,
+                    )
+                }
+            }),
+        )
         .nest("/explorer", explorer_routes(ctx.clone()))
         .route(
             "/explorer",
@@ -1,7 +1,5 @@
 use alloy::{
-    network::{EthereumWallet, TransactionBuilder},
     primitives::{FixedBytes, U256},
-    rpc::types::TransactionRequest,
     signers::{local::PrivateKeySigner, Signer},
 };
 use axum::{
@@ -16,19 +14,25 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use tower::{Service, ServiceExt};
 
+#[cfg(test)]
+use crate::bridge::{
+    mock_provider::{self, MockL1},
+    push_burn_block, WithdrawalCertificate, WithdrawalExecution,
+};
+
 use crate::{
     blockchain::{
-        tx::owshen_airdrop::babyjubjub::PrivateKey, Blockchain, Config, Owshenchain,
+        events, tx::owshen_airdrop::babyjubjub::PrivateKey, Blockchain, Config, Owshenchain,
         TransactionQueue,
     },
     config::{self, CHAIN_ID},
     db::{DiskKvStore, Key, KvStore, RamKvStore, Value},
     genesis::GENESIS,
     safe_signer::{self, SafeSigner},
-    services::{api_services::api_routes, Context},
+    services::{api_services::api_routes, filters::Filters, Context},
     types::{
         network::Network, BincodableOwshenTransaction, Burn, CustomTx, CustomTxMsg,
-        IncludedTransaction, Mint, OwshenTransaction, Token,
+        IncludedTransaction, Mint, OwshenTransaction, Token, WithdrawCalldata,
     },
 };
 
@@ -44,10 +48,11 @@ async fn withdrawal_test() {
         123,
         CustomTxMsg::BurnTx(Burn {
             burn_id: FixedBytes::from([1u8; 32]),
-            network: Network::ETH,
+            chain_id: 1,
             token: Token::Native,
             amount: value,
             calldata: None,
+            nonce: U256::from(0),
         }),
     )
     .await
@@ -82,43 +87,67 @@ async fn withdrawal_test() {
     }
 
     let address_str = format!("{}", signer.address());
-
-    let response = app
-        .oneshot(
-            Request::builder()
-                .method(http::Method::GET)
-                .uri("/Withdrawals")
-                .header("Content-Type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&json!({
-                        "address": address_str.to_lowercase(),
-                    }))
-                    .unwrap(),
-                ))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
-
-    assert_eq!(response.status(), StatusCode::OK);
-    let body = response.into_body().collect().await.unwrap().to_bytes();
-    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
-    assert_eq!(
-        body,
+    let withdrawals = |app: Router| {
+        let address_str = address_str.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .uri("/Withdrawals")
+                        .header("Content-Type", "application/json")
+                        .body(Body::from(
+                            serde_json::to_vec(&json!({
+                                "address": address_str.to_lowercase(),
+                            }))
+                            .unwrap(),
+                        ))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        }
+    };
+    let expected = |status: &str| {
         json!({
             "withdrawals": [{
                 "amount": format!("{:#x}", value),
                 "block_number": block_number,
-                "network": format!("{:?}", Network::ETH),
+                "burn_id": format!("{}", FixedBytes::from([1u8; 32])),
+                "chain_id": 1,
                 "signature": {
                     "r": format!("{:#x}", signature.r()),
                     "s": format!("{:#x}", signature.s()),
                     "yParity": format!("{:#x}", signature.v().to_u64()),
                 },
+                "status": status,
                 "token": format!("{:?}", Token::Native),
             }]
         })
-    );
+    };
+
+    assert_eq!(withdrawals(app.clone()).await, expected("pending"));
+
+    let l1_tx_hash = FixedBytes::from([2u8; 32]);
+    ctx.lock()
+        .await
+        .chain
+        .db
+        .put(
+            Key::WithdrawalExecution(FixedBytes::from([1u8; 32])),
+            Some(Value::WithdrawalExecution(WithdrawalExecution {
+                chain_id: 1,
+                l1_tx_hash,
+                block_number: 10,
+            })),
+        )
+        .unwrap();
+    let mut executed = expected("executed");
+    executed["withdrawals"][0]["l1_tx_hash"] = json!(format!("{}", l1_tx_hash));
+    assert_eq!(withdrawals(app).await, executed);
 }
 
 #[tokio::test]
@@ -156,15 +185,17 @@ async fn withdraw_test() {
 
     let burn_obj = Burn {
         burn_id: FixedBytes::from([1u8; 32]),
-        network: Network::ETH,
+        chain_id: 1,
         token: Token::Native,
         amount: U256::from(100),
-        calldata: None,
+        calldata: Some(WithdrawCalldata::Eth { address }),
+        nonce: U256::from(0),
     };
     let burn_rlp = rlp::encode(&burn_obj);
     let sig = signer.sign_message(&burn_rlp).await.unwrap();
 
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
                 .method(http::Method::GET)
@@ -187,146 +218,343 @@ async fn withdraw_test() {
     let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
     assert_eq!(body.get("success").unwrap(), &serde_json::Value::Bool(true));
+
+    // The queued burn is the user's own transaction, no balance is touched yet
+    {
+        let ctx = ctx.lock().await;
+        let queue = ctx.tx_queue.queue();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(format!("{:?}", queue[0].hash().unwrap()), body["id"]);
+        assert_eq!(queue[0].signer().unwrap(), address);
+        assert_eq!(
+            ctx.chain.get_balance(Token::Native, address).unwrap(),
+            base_value
+        );
+    }
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::GET)
+                .uri("/withdraw")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&json!({
+                        "rlp_burn": burn_rlp,
+                        "sig": sig,
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let mut ctx = ctx.lock().await;
+    let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+    let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+    ctx.tx_queue = tx_queue;
+    ctx.chain.push_block(blk).unwrap();
+
+    assert_eq!(
+        ctx.chain.get_balance(Token::Native, address).unwrap(),
+        U256::ZERO
+    );
+    assert!(ctx
+        .chain
+        .db
+        .get(Key::BurnId(burn_obj.burn_id))
+        .unwrap()
+        .is_some());
 }
 
 #[tokio::test]
-async fn deposit_test() {
+async fn withdraw_malformed_burn_test() {
     let (ctx, app) = test_config().await;
+    let signer = PrivateKeySigner::random();
 
-    let signer: PrivateKeySigner = PrivateKeySigner::random();
-    let chain_id = ctx.lock().await.chain.config().chain_id;
-    let vitalik = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
-        .parse()
+    // A native burn whose id is one byte short
+    let mut stream = rlp::RlpStream::new_list(7);
+    stream.append(&"burn");
+    stream.append(&vec![1u8; 31]);
+    stream.append(&"native");
+    stream.append(&U256::from(100).as_le_bytes().to_vec());
+    stream.append(&1u64);
+    stream.append(&U256::ZERO.as_le_bytes().to_vec());
+    stream.append(&signer.address().to_vec());
+    let burn_rlp = stream.out().to_vec();
+    let sig = signer.sign_message(&burn_rlp).await.unwrap();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(http::Method::GET)
+                .uri("/withdraw")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&json!({
+                        "rlp_burn": burn_rlp,
+                        "sig": sig,
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(ctx.lock().await.tx_queue.queue().is_empty());
+}
+
+#[tokio::test]
+async fn withdrawal_certificate_test() {
+    let (ctx, app) = test_config().await;
+
+    let user = PrivateKeySigner::random();
+    let burn_id = FixedBytes::from([3u8; 32]);
+    let burn = Burn {
+        burn_id,
+        chain_id: 1,
+        token: Token::Native,
+        amount: U256::from(100),
+        calldata: Some(WithdrawCalldata::Eth {
+            address: user.address(),
+        }),
+        nonce: U256::from(0),
+    };
+    let certificate = {
+        let mut ctx = ctx.lock().await;
+        push_burn_block(&mut ctx.chain, &user, burn.clone())
+            .await
+            .unwrap();
+        WithdrawalCertificate::sign(&ctx.signer, &burn)
+            .await
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::GET)
+                .uri("/withdrawal-certificate")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&json!({ "burn_id": burn_id })).unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
         .unwrap();
-    let tx = TransactionRequest::default()
-        .with_to(vitalik)
-        .with_deploy_code(vec![0, 0, 0, 0])
-        .with_nonce(1)
-        .with_gas_limit(100)
-        .with_max_fee_per_gas(100)
-        .with_max_priority_fee_per_gas(100)
-        .with_chain_id(chain_id)
-        .with_value(U256::from(100_000_000_000_000_000u128));
-    let wallet = EthereumWallet::new(signer.clone());
-    let user_tx_hash = tx
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!({ "certificate": certificate, "success": true }));
+    assert_eq!(body["certificate"]["to"], json!(user.address()));
+
+    let response = app
         .clone()
-        .build(&wallet)
+        .oneshot(
+            Request::builder()
+                .method(http::Method::GET)
+                .uri("/withdrawal-certificate")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&json!({ "burn_id": FixedBytes::from([4u8; 32]) })).unwrap(),
+                ))
+                .unwrap(),
+        )
         .await
-        .unwrap()
-        .tx_hash()
-        .to_string();
-    let tx = OwshenTransaction::Eth(tx.build(&wallet).await.unwrap());
-    let hash_result = tx.hash();
-    let fixed_bytes_tx_hash = hash_result.as_ref().unwrap();
-    let vec8_tx_hash = fixed_bytes_tx_hash.to_vec();
-    let token = Token::Native;
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    // Followers leave certificates to the sequencer
+    ctx.lock().await.read_only = true;
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(http::Method::GET)
+                .uri("/withdrawal-certificate")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&json!({ "burn_id": burn_id })).unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn deposit_test() {
+    let l1 = MockL1::new(100);
+    let (provider_address, _handle) = mock_provider::serve(l1.clone()).await.unwrap();
+    let (ctx, app) = test_config_with_provider(provider_address).await;
+
+    let signer: PrivateKeySigner = PrivateKeySigner::random();
+    let chain_id = ctx.lock().await.chain.config().chain_id;
     let amount = U256::from(100_000_000_000_000_000u128);
     let address = signer.address();
+    let (l1_tx_hash, unrelated_tx_hash) = {
+        let mut l1 = l1.lock().unwrap();
+        (
+            l1.native_deposit(90, address, config::OWSHEN_CONTRACT, amount),
+            l1.native_deposit(90, address, address, amount),
+        )
+    };
+    let user_tx_hash = l1_tx_hash.to_string();
 
     let txx = CustomTx::create(
         &mut ctx.lock().await.signer,
         chain_id,
         CustomTxMsg::MintTx(Mint {
-            tx_hash: vec8_tx_hash.clone(),
+            tx_hash: l1_tx_hash.to_vec(),
             user_tx_hash: user_tx_hash.clone(),
-            token,
+            token: Token::Native,
             amount,
             address,
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
     .unwrap();
 
-    let response = app.clone()
-        .oneshot(
-            Request::builder()
-                .method(http::Method::POST)
-                .uri("/deposit")
-                .header("Content-Type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&json!({"tx_hash": user_tx_hash, "token": "native", "amount": amount, "address": address})).unwrap(),
-                ))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
-
-    assert_eq!(response.status(), StatusCode::OK);
-
-    let body = response.into_body().collect().await.unwrap().to_bytes();
-    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    // Not a deposit into the Owshen contract
+    let (status, _) = post_deposit(&app, unrelated_tx_hash.to_string()).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
 
+    let (status, body) = post_deposit(&app, user_tx_hash.clone()).await;
+    assert_eq!(status, StatusCode::OK);
     assert_eq!(
         body,
-        json!({"owshen_tx_hash": format!("{:?}", txx.hash().unwrap()), "success": true})
+        json!({"owshen_tx_hash": format!("{:?}", txx.hash().unwrap()), "success": true, "status": "pending"})
     );
+    assert_eq!(ctx.lock().await.tx_queue.queue().len(), 1);
 
-    let bincodable_tx = txx.clone().try_into().unwrap();
+    // Asking again doesn't mint twice
+    let (_, body) = post_deposit(&app, user_tx_hash.clone()).await;
+    assert_eq!(body["status"], "pending");
+    assert_eq!(ctx.lock().await.tx_queue.queue().len(), 1);
 
-    let block_number = 4321;
-    let included_tx = IncludedTransaction {
-        tx: bincodable_tx,
-        block_hash: FixedBytes::from([0u8; 32]),
-        block_number,
-        transaction_index: 1,
-    };
-    let mut transactions: Vec<IncludedTransaction> = Vec::new();
-    transactions.push(included_tx.clone());
+    {
+        let mut ctx = ctx.lock().await;
+        let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+        let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+        ctx.tx_queue = tx_queue;
+        ctx.chain.push_block(blk).unwrap();
+        assert_eq!(
+            ctx.chain.get_balance(Token::Native, address).unwrap(),
+            amount
+        );
+    }
 
+    let (status, body) = post_deposit(&app, user_tx_hash.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({"owshen_tx_hash": format!("{:?}", txx.hash().unwrap()), "success": true, "status": "included"})
+    );
+
+    // A mint that can't make it into a block is reported as rejected
+    let second_tx_hash =
+        l1.lock()
+            .unwrap()
+            .native_deposit(90, address, config::OWSHEN_CONTRACT, amount);
+    let (_, body) = post_deposit(&app, second_tx_hash.to_string()).await;
+    assert_eq!(body["status"], "pending");
     {
-        let mut ctx_guard = ctx.lock().await;
-        ctx_guard
-            .chain
+        let mut ctx = ctx.lock().await;
+        let node = ctx.signer.address();
+        ctx.chain
             .db
-            .put(
-                Key::TransactionHash(txx.hash().unwrap()),
-                Some(Value::Transaction(included_tx.clone())),
-            )
+            .put(Key::NonceCustom(node), Some(Value::U256(U256::from(5))))
             .unwrap();
+        let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+        let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+        ctx.tx_queue = tx_queue;
+        assert!(blk.txs.is_empty());
     }
+    let rejected = ctx.lock().await.tx_queue.rejected().clone();
+    assert_eq!(rejected.len(), 1);
+
+    // Submitting it again mints it with a fresh nonce
+    let (_, body) = post_deposit(&app, second_tx_hash.to_string()).await;
+    assert_eq!(body["status"], "pending");
+    assert_ne!(
+        body["owshen_tx_hash"],
+        format!("{:?}", rejected[0].0.hash().unwrap())
+    );
+    {
+        let mut ctx = ctx.lock().await;
+        let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+        let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+        ctx.tx_queue = tx_queue;
+        assert_eq!(blk.txs.len(), 1);
+        ctx.chain.push_block(blk).unwrap();
+    }
+    let (_, body) = post_deposit(&app, second_tx_hash.to_string()).await;
+    assert_eq!(body["status"], "included");
+}
 
+#[cfg(test)]
+async fn post_deposit(app: &Router, tx_hash: String) -> (StatusCode, serde_json::Value) {
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
                 .method(http::Method::POST)
                 .uri("/deposit")
                 .header("Content-Type", "application/json")
                 .body(Body::from(
-                    serde_json::to_vec(&json!({"tx_hash": user_tx_hash, "token": "native", "amount": amount, "address": address})).unwrap(),
+                    serde_json::to_vec(&json!({ "chain_id": 1, "tx_hash": tx_hash })).unwrap(),
                 ))
                 .unwrap(),
         )
         .await
         .unwrap();
-
-    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
-
+    let status = response.status();
     let body = response.into_body().collect().await.unwrap().to_bytes();
-    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
-
-    assert_eq!(
-        body,
-        json!({"message": "Transaction already exists", "error": true})
-    );
+    (status, serde_json::from_slice(&body).unwrap())
 }
 
 async fn test_config() -> (
     Arc<tokio::sync::Mutex<Context<SafeSigner, RamKvStore>>>,
     Router,
+) {
+    test_config_with_provider("http://127.0.0.1:8888".parse().expect("faild to parse")).await
+}
+
+async fn test_config_with_provider(
+    provider_address: reqwest::Url,
+) -> (
+    Arc<tokio::sync::Mutex<Context<SafeSigner, RamKvStore>>>,
+    Router,
 ) {
     let conf = Config {
         chain_id: 1387,
         owner: None,
         genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address: "http://127.0.0.1:8888".parse().expect("faild to parse"),
+        networks: vec![Network {
+            chain_id: 1,
+            owshen: config::OWSHEN_CONTRACT,
+            provider_address,
+            confirmations: 1,
+        }],
     };
 
     let owner = SafeSigner::new(PrivateKeySigner::random());
     let ctx = Arc::new(Mutex::new(Context {
         signer: owner.clone(),
         exit: false,
+        read_only: false,
         tx_queue: TransactionQueue::new(),
+        filters: Filters::new(),
+        events: events::channel(),
         chain: Owshenchain::new(conf, RamKvStore::new()),
     }));
 
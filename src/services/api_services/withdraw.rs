@@ -1,17 +1,15 @@
-use alloy::primitives::{keccak256, Signature};
-use alloy::primitives::{FixedBytes, U256};
-use alloy::sol_types::SolValue;
+use alloy::primitives::{FixedBytes, Signature};
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use super::Context;
-use crate::config::CHAIN_ID;
+use crate::blockchain::Blockchain;
 use crate::services::{ContextKvStore, ContextSigner};
-use crate::types::{CustomTx, Token, WithdrawCalldata};
-use crate::{blockchain::Blockchain, types::Burn};
+use crate::types::{CustomTx, CustomTxMsg, OwshenTransaction};
 
+/// A burn signed by its owner, exactly as `CustomTx::create` would sign it
 #[derive(Deserialize, Debug)]
 pub struct WithdrawRequest {
     pub rlp_burn: Vec<u8>,
@@ -29,43 +27,39 @@ pub async fn withdraw_handler<S: ContextSigner, K: ContextKvStore>(
     Json(payload): Json<WithdrawRequest>,
 ) -> Result<Json<WithdrawResponse>, anyhow::Error> {
     let mut _ctx = ctx.lock().await;
+    _ctx.check_writable()?;
 
-    let mut burn: Burn = rlp::decode(&payload.rlp_burn)?;
-    let from_address = payload.sig.recover_address_from_msg(&payload.rlp_burn)?;
-    _ctx.chain.db.put(
-        crate::db::Key::Balance(from_address, Token::Native),
-        Some(crate::db::Value::U256(U256::from(123456789))),
-    )?;
+    let burn = match CustomTxMsg::from_rlp(&payload.rlp_burn)? {
+        CustomTxMsg::BurnTx(burn) => burn,
+        _ => return Err(anyhow::anyhow!("Only burn transactions are accepted!")),
+    };
+    let tx = OwshenTransaction::Custom(CustomTx {
+        chain_id: _ctx.chain.config().chain_id,
+        msg: payload.rlp_burn,
+        sig: payload.sig,
+    });
 
-    let burn_id = burn.burn_id.clone();
+    let burn_id = burn.burn_id;
     if _ctx
         .chain
         .db
-        .get(crate::db::Key::BurnId(burn_id.clone()))?
+        .get(crate::db::Key::BurnId(burn_id))?
         .is_some()
     {
         return Err(anyhow::anyhow!("Burn id already used!"));
     }
+    if _ctx.tx_queue.queue().iter().any(|t| match t {
+        OwshenTransaction::Custom(custom_tx) => matches!(
+            custom_tx.msg(),
+            Ok(CustomTxMsg::BurnTx(b)) if b.burn_id == burn_id
+        ),
+        _ => false,
+    }) {
+        return Err(anyhow::anyhow!("Burn id already used!"));
+    }
 
-    let calldata = WithdrawCalldata::Eth {
-        address: from_address,
-    };
-
-    burn.calldata = Some(calldata);
-
-    let tx = CustomTx::create(
-        &mut _ctx.signer,
-        CHAIN_ID,
-        crate::types::CustomTxMsg::BurnTx(burn),
-    )
-    .await?;
-
-    let id = tx.hash()?;
-    _ctx.tx_queue.enqueue(tx);
-    _ctx.chain.db.put(
-        crate::db::Key::BurnId(burn_id),
-        Some(crate::db::Value::Void),
-    )?;
+    let _ctx = &mut *_ctx;
+    let id = _ctx.tx_queue.add(&_ctx.chain, tx)?;
 
     Ok(Json(WithdrawResponse { id, success: true }))
 }
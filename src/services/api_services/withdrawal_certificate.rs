@@ -0,0 +1,38 @@
+use alloy::primitives::FixedBytes;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::{
+    bridge::{certify_withdrawal, WithdrawalCertificate},
+    services::{ContextKvStore, ContextSigner},
+};
+
+#[derive(Deserialize)]
+pub struct WithdrawalCertificateRequest {
+    pub burn_id: FixedBytes<32>,
+}
+
+#[derive(Serialize)]
+pub struct WithdrawalCertificateResponse {
+    pub certificate: WithdrawalCertificate,
+    pub success: bool,
+}
+
+pub async fn withdrawal_certificate_handler<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    Json(payload): Json<WithdrawalCertificateRequest>,
+) -> Result<Json<WithdrawalCertificateResponse>, anyhow::Error> {
+    let ctx_guard = ctx.lock().await;
+    ctx_guard.check_bridge()?;
+
+    let certificate =
+        certify_withdrawal(&ctx_guard.chain, &ctx_guard.signer, payload.burn_id).await?;
+
+    Ok(Json(WithdrawalCertificateResponse {
+        certificate,
+        success: true,
+    }))
+}
@@ -1,4 +1,4 @@
-use alloy::primitives::{Address, Signature, U256};
+use alloy::primitives::{Address, FixedBytes, Signature, U256};
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -7,8 +7,8 @@ use tokio::sync::Mutex;
 use super::Context;
 use crate::{
     blockchain::Blockchain,
+    db::Key,
     services::{ContextKvStore, ContextSigner},
-    types::network::Network,
     types::Token,
     types::{CustomTxMsg, OwshenTransaction},
 };
@@ -18,13 +18,27 @@ pub struct WithdrawalsRequest {
     pub address: Address,
 }
 
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WithdrawalStatus {
+    /// Burnt on Owshen, not redeemed on L1 yet
+    Pending,
+    /// Redeemed on L1
+    Executed,
+}
+
 #[derive(Serialize)]
 pub struct WithdrawalDetail {
     pub block_number: usize,
     pub signature: Signature,
-    pub network: String,
+    pub burn_id: FixedBytes<32>,
+    pub chain_id: u64,
     pub token: String,
     pub amount: U256,
+    pub status: WithdrawalStatus,
+    /// Transaction redeeming the withdrawal on L1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_tx_hash: Option<FixedBytes<32>>,
 }
 
 #[derive(Serialize)]
@@ -37,6 +51,7 @@ pub async fn withdrawals_handler<S: ContextSigner, K: ContextKvStore>(
     Json(payload): Json<WithdrawalsRequest>,
 ) -> Result<Json<WithdrawalsResponse>, anyhow::Error> {
     let ctx_guard = ctx.lock().await;
+    ctx_guard.check_bridge()?;
     let blockchain = &ctx_guard.chain;
 
     let withdrawals = blockchain.get_user_withdrawals(payload.address)?;
@@ -48,23 +63,33 @@ pub async fn withdrawals_handler<S: ContextSigner, K: ContextKvStore>(
 
             if let Ok(OwshenTransaction::Custom(custom_tx)) = tx {
                 if let Ok(CustomTxMsg::BurnTx(burn_data)) = custom_tx.msg() {
-                    let network = match burn_data.network {
-                        Network::ETH => "ETH".to_string(),
-                        Network::BSC => "BSC".to_string(),
-                    };
                     let token = match burn_data.token {
                         Token::Native => "Native".to_string(),
                         Token::Erc20(address) => format!("ERC20: {:?}", address),
                     };
                     let amount = burn_data.amount;
                     let signature = custom_tx.sig.clone();
+                    let execution = blockchain
+                        .db
+                        .get(Key::WithdrawalExecution(burn_data.burn_id))
+                        .ok()
+                        .flatten()
+                        .and_then(|v| v.as_withdrawal_execution().ok());
+                    let status = if execution.is_some() {
+                        WithdrawalStatus::Executed
+                    } else {
+                        WithdrawalStatus::Pending
+                    };
 
                     return Some(WithdrawalDetail {
                         block_number: included_tx.block_number,
                         signature,
-                        network,
+                        burn_id: burn_data.burn_id,
+                        chain_id: burn_data.chain_id,
                         token,
                         amount,
+                        status,
+                        l1_tx_hash: execution.map(|e| e.l1_tx_hash),
                     });
                 }
             }
@@ -0,0 +1,237 @@
+use alloy::primitives::{Address, FixedBytes, U256};
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::blockchain::{logs::LogFilter, Blockchain};
+
+/// Filters nobody polled for this long are uninstalled, like Geth does
+pub const FILTER_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockTag {
+    Earliest,
+    Latest,
+    Number(usize),
+}
+
+impl BlockTag {
+    fn parse(value: Option<&serde_json::Value>) -> Result<Self> {
+        match value.and_then(|v| v.as_str()) {
+            None | Some("latest") | Some("pending") | Some("safe") | Some("finalized") => {
+                Ok(BlockTag::Latest)
+            }
+            Some("earliest") => Ok(BlockTag::Earliest),
+            Some(number) => match number.strip_prefix("0x") {
+                Some(hex) => Ok(BlockTag::Number(usize::from_str_radix(hex, 16)?)),
+                None => Err(anyhow!("Invalid block number!")),
+            },
+        }
+    }
+
+    fn resolve(&self, height: usize) -> usize {
+        match self {
+            BlockTag::Earliest => 0,
+            BlockTag::Latest => height.saturating_sub(1),
+            BlockTag::Number(n) => *n,
+        }
+    }
+}
+
+/// The filter object of `eth_getLogs` and `eth_newFilter`. Block tags are kept
+/// as given, so that `latest` follows the chain in installed filters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogQuery {
+    pub from_block: BlockTag,
+    pub to_block: BlockTag,
+    pub block_hash: Option<FixedBytes<32>>,
+    pub addresses: Vec<Address>,
+    pub topics: Vec<Option<Vec<FixedBytes<32>>>>,
+}
+
+impl LogQuery {
+    pub fn parse(value: &serde_json::Value) -> Result<Self> {
+        let addresses = match value.get("address") {
+            None | Some(serde_json::Value::Null) => Vec::new(),
+            Some(serde_json::Value::Array(addresses)) => addresses
+                .iter()
+                .map(|a| Ok(serde_json::from_value(a.clone())?))
+                .collect::<Result<Vec<_>>>()?,
+            Some(address) => vec![serde_json::from_value(address.clone())?],
+        };
+        let topics = match value.get("topics") {
+            None | Some(serde_json::Value::Null) => Vec::new(),
+            Some(serde_json::Value::Array(topics)) => topics
+                .iter()
+                .map(|topic| {
+                    Ok(match topic {
+                        serde_json::Value::Null => None,
+                        serde_json::Value::Array(any) if any.is_empty() => None,
+                        serde_json::Value::Array(any) => Some(
+                            any.iter()
+                                .map(|t| Ok(serde_json::from_value(t.clone())?))
+                                .collect::<Result<Vec<_>>>()?,
+                        ),
+                        topic => Some(vec![serde_json::from_value(topic.clone())?]),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Some(_) => return Err(anyhow!("Invalid topics!")),
+        };
+        if topics.len() > 4 {
+            return Err(anyhow!("Logs have at most 4 topics!"));
+        }
+        let block_hash = match value.get("blockHash") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(hash) => Some(serde_json::from_value(hash.clone())?),
+        };
+        if block_hash.is_some()
+            && (value.get("fromBlock").is_some() || value.get("toBlock").is_some())
+        {
+            return Err(anyhow!(
+                "blockHash can't be combined with fromBlock/toBlock!"
+            ));
+        }
+
+        Ok(LogQuery {
+            from_block: BlockTag::parse(value.get("fromBlock"))?,
+            to_block: BlockTag::parse(value.get("toBlock"))?,
+            block_hash,
+            addresses,
+            topics,
+        })
+    }
+
+    /// Block range of the query against the current chain
+    pub fn resolve<B: Blockchain>(&self, chain: &B) -> Result<LogFilter> {
+        let (from_block, to_block) = match self.block_hash {
+            Some(hash) => {
+                let index = chain.get_block_by_hash(hash)?.index;
+                (index, index)
+            }
+            None => {
+                let height = chain.get_height()?;
+                (
+                    self.from_block.resolve(height),
+                    self.to_block.resolve(height),
+                )
+            }
+        };
+        Ok(LogFilter {
+            from_block,
+            to_block,
+            addresses: self.addresses.clone(),
+            topics: self.topics.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FilterKind {
+    Logs(LogQuery),
+    Blocks,
+    PendingTransactions,
+}
+
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub kind: FilterKind,
+    /// Height of the chain when the filter was last polled, the changes start
+    /// from there
+    pub height: usize,
+    /// Pending transactions already reported
+    pub seen: HashSet<FixedBytes<32>>,
+    last_poll: Instant,
+}
+
+/// Filters installed through the RPC, polled with `eth_getFilterChanges`
+#[derive(Debug, Default)]
+pub struct Filters {
+    last_id: u64,
+    filters: HashMap<U256, Filter>,
+}
+
+impl Filters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn prune(&mut self) {
+        self.filters
+            .retain(|_, filter| filter.last_poll.elapsed() < FILTER_TIMEOUT);
+    }
+
+    pub fn install(&mut self, kind: FilterKind, height: usize) -> U256 {
+        self.prune();
+        self.last_id += 1;
+        let id = U256::from(self.last_id);
+        self.filters.insert(
+            id,
+            Filter {
+                kind,
+                height,
+                seen: HashSet::new(),
+                last_poll: Instant::now(),
+            },
+        );
+        id
+    }
+
+    /// The filter with the given id, which counts as it being polled
+    pub fn poll(&mut self, id: U256) -> Result<&mut Filter> {
+        self.prune();
+        let filter = self
+            .filters
+            .get_mut(&id)
+            .ok_or(anyhow!("Filter not found!"))?;
+        filter.last_poll = Instant::now();
+        Ok(filter)
+    }
+
+    pub fn uninstall(&mut self, id: U256) -> bool {
+        self.prune();
+        self.filters.remove(&id).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_log_query() {
+        let query = LogQuery::parse(&json!({
+            "fromBlock": "0x2",
+            "address": "0x0101010101010101010101010101010101010101",
+            "topics": [null, ["0x0202020202020202020202020202020202020202020202020202020202020202"], []],
+        }))
+        .unwrap();
+        assert_eq!(query.from_block, BlockTag::Number(2));
+        assert_eq!(query.to_block, BlockTag::Latest);
+        assert_eq!(query.addresses, vec![Address::from([1; 20])]);
+        assert_eq!(
+            query.topics,
+            vec![None, Some(vec![FixedBytes::from([2; 32])]), None]
+        );
+
+        assert!(LogQuery::parse(&json!({ "topics": [null, null, null, null, null] })).is_err());
+        assert!(LogQuery::parse(&json!({
+            "blockHash": FixedBytes::<32>::ZERO,
+            "fromBlock": "0x0",
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut filters = Filters::new();
+        let id = filters.install(FilterKind::Blocks, 3);
+        assert_ne!(filters.install(FilterKind::PendingTransactions, 3), id);
+        assert_eq!(filters.poll(id).unwrap().height, 3);
+
+        filters.poll(id).unwrap().last_poll = Instant::now() - FILTER_TIMEOUT;
+        assert!(filters.poll(id).is_err());
+        assert!(!filters.uninstall(id));
+    }
+}
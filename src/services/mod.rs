@@ -1,11 +1,19 @@
 use alloy::signers::Signer;
+use anyhow::{anyhow, Result};
 
 use crate::{
-    blockchain::{Owshenchain, TransactionQueue},
+    blockchain::{
+        events::{ChainEvent, EventSender},
+        logs::LogFilter,
+        Blockchain, Owshenchain, TransactionQueue,
+    },
     db::KvStore,
+    types::Block,
 };
+use filters::Filters;
 
 mod api_services;
+pub mod filters;
 mod rpc_services;
 pub mod server;
 
@@ -15,7 +23,44 @@ pub trait ContextKvStore: KvStore + Send + Sync {}
 
 pub struct Context<S: ContextSigner, K: ContextKvStore> {
     pub exit: bool,
+    /// Followers replicate the chain of a sequencer and accept no transactions
+    pub read_only: bool,
     pub signer: S,
     pub tx_queue: TransactionQueue,
+    pub filters: Filters,
+    pub events: EventSender,
     pub chain: Owshenchain<K>,
 }
+
+impl<S: ContextSigner, K: ContextKvStore> Context<S, K> {
+    pub fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(anyhow!("This node is a read-only follower!"));
+        }
+        Ok(())
+    }
+
+    /// Withdrawal executions are watched and certificates are signed by the
+    /// sequencer only, followers would serve stale or bogus answers
+    pub fn check_bridge(&self) -> Result<()> {
+        if self.read_only {
+            return Err(anyhow!("Withdrawals are only served by the sequencer!"));
+        }
+        Ok(())
+    }
+
+    /// Announces a block that was just added to the chain to the subscribers
+    pub fn publish_block(&self, block: &Block) -> Result<()> {
+        let logs = self.chain.get_logs(&LogFilter {
+            from_block: block.index,
+            to_block: block.index,
+            ..Default::default()
+        })?;
+        // Nobody listening is not an error
+        let _ = self.events.send(ChainEvent::Block {
+            block: block.clone(),
+            logs,
+        });
+        Ok(())
+    }
+}
@@ -3,24 +3,32 @@ use std::sync::Arc;
 
 use super::Context;
 use crate::blockchain::Blockchain;
-use crate::db::{Key, KvStore, Value};
-use crate::services::rpc_services::test_config;
 use crate::services::{ContextKvStore, ContextSigner};
 use crate::types::{Token, ERC20};
 use alloy::hex::ToHexExt;
-use alloy::primitives::{Address, U256};
+use alloy::primitives::Address;
 use alloy::sol_types::SolValue;
 use anyhow::{anyhow, Result};
-use hex;
 use jsonrpsee::types::Params;
-use serde_json::json;
 use tokio::sync::Mutex;
 
 pub async fn eth_call<S: ContextSigner, K: ContextKvStore>(
-    ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     params: Params<'static>,
 ) -> Result<String> {
-    let first_param: HashMap<String, String> = params.sequence().next()?;
+    let params: Vec<serde_json::Value> = params.parse()?;
+    let first_param: HashMap<String, String> =
+        serde_json::from_value(params.first().ok_or(anyhow!("Call unavailable!"))?.clone())?;
+
+    let ctx = ctx.lock().await;
+    if super::is_pending(params.get(1).and_then(|tag| tag.as_str())) {
+        call(&ctx.chain.pending_state(&ctx.tx_queue)?, &first_param)
+    } else {
+        call(&ctx.chain, &first_param)
+    }
+}
+
+fn call<B: Blockchain>(chain: &B, first_param: &HashMap<String, String>) -> Result<String> {
     let data = first_param
         .get("data")
         .ok_or(anyhow!("Data unavailable!"))?;
@@ -28,6 +36,18 @@ pub async fn eth_call<S: ContextSigner, K: ContextKvStore>(
         .get("to")
         .ok_or(anyhow!("Contract address unavailable!"))?
         .parse()?;
+
+    let code = chain.get_code(contract_address)?;
+    if !code.is_empty() {
+        let caller: Address = match first_param.get("from") {
+            Some(from) => from.parse()?,
+            None => Address::ZERO,
+        };
+        let input = hex::decode(data.trim_start_matches("0x"))?;
+        let ret = chain.call_contract(caller, contract_address, input)?;
+        return Ok(format!("0x{}", hex::encode(ret)));
+    }
+
     let method_hash = &data[0..10];
     match method_hash {
         "0x01ffc9a7" => {
@@ -36,25 +56,25 @@ pub async fn eth_call<S: ContextSigner, K: ContextKvStore>(
         // balanceOf(address)
         "0x70a08231" => {
             let address: Address = data[10..].parse()?;
-            let decimals = ctx.lock().await.chain.get_token_decimal(contract_address)?;
-            let symbol = ctx.lock().await.chain.get_token_symbol(contract_address)?;
+            let decimals = chain.get_token_decimal(contract_address)?;
+            let symbol = chain.get_token_symbol(contract_address)?;
             let token = Token::Erc20(ERC20 {
                 address: contract_address,
                 decimals,
                 symbol,
             });
 
-            let balance = ctx.lock().await.chain.get_balance(token, address)?;
+            let balance = chain.get_balance(token, address)?;
             return Ok((balance).abi_encode().encode_hex());
         }
         // decimals()
         "0x313ce567" => {
-            let decimals = ctx.lock().await.chain.get_token_decimal(contract_address)?;
+            let decimals = chain.get_token_decimal(contract_address)?;
             return Ok((decimals).abi_encode().encode_hex());
         }
         // symbol()
         "0x95d89b41" => {
-            let symbol = ctx.lock().await.chain.get_token_symbol(contract_address)?;
+            let symbol = chain.get_token_symbol(contract_address)?;
             return Ok((symbol).abi_encode().encode_hex());
         }
         _ => {
@@ -64,130 +84,188 @@ pub async fn eth_call<S: ContextSigner, K: ContextKvStore>(
     Ok(format!("0x{:x}", 0))
 }
 
-#[tokio::test]
-async fn test_eth_call() {
-    let _ctx = test_config().await;
-
-    let contract_address: Address = Address::from([7; 20]);
-    let balance = U256::from(100);
-    let token_symbol = "USDT".to_owned();
-    let token_decimals = U256::from(18);
-
-    _ctx.lock()
-        .await
-        .chain
-        .db
-        .put(
-            Key::TokenDecimal(contract_address),
-            Some(Value::U256(token_decimals)),
-        )
-        .unwrap();
-
-    _ctx.lock()
-        .await
-        .chain
-        .db
-        .put(
-            Key::TokenSymbol(contract_address),
-            Some(Value::Symbol(token_symbol.clone())),
-        )
-        .unwrap();
-
-    {
-        let method_hash = "0x70a08231";
-        let address: Address = Address::from([8; 20]);
-        let data = format!("{}{}", method_hash, hex::encode(address));
-        let addr = contract_address.to_string();
-        let param_map = json!([{
-            "to": addr,
-            "data": data
-        }])
-        .to_string();
-        let addr_static: &'static str = Box::leak(param_map.into_boxed_str());
-        let params = Params::new(Some(addr_static));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Key, KvStore, Value};
+    use crate::services::rpc_services::{push_mint_block, queue_call, test_config};
+    use alloy::primitives::U256;
+    use alloy::signers::local::PrivateKeySigner;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_eth_call() {
+        let _ctx = test_config().await;
+
+        let contract_address: Address = Address::from([7; 20]);
+        let balance = U256::from(100);
+        let token_symbol = "USDT".to_owned();
+        let token_decimals = U256::from(18);
 
         _ctx.lock()
             .await
             .chain
             .db
             .put(
-                Key::Balance(
-                    address,
-                    Token::Erc20(ERC20 {
-                        address: contract_address,
-                        decimals: token_decimals,
-                        symbol: token_symbol.clone(),
-                    }),
-                ),
-                Some(Value::U256(balance)),
+                Key::TokenDecimal(contract_address),
+                Some(Value::U256(token_decimals)),
             )
             .unwrap();
 
-        let result = eth_call(_ctx.clone().into(), params).await;
-        assert!(result.is_ok());
-        let expected_result = balance.abi_encode().encode_hex();
-        assert_eq!(result.unwrap(), expected_result);
-    }
+        _ctx.lock()
+            .await
+            .chain
+            .db
+            .put(
+                Key::TokenSymbol(contract_address),
+                Some(Value::Symbol(token_symbol.clone())),
+            )
+            .unwrap();
 
-    {
-        let method_hash = "0x313ce567";
-        let address: Address = Address::from([8; 20]);
-
-        let data = format!("{}{}", method_hash, hex::encode(address));
-        let addr = contract_address.to_string();
-        let param_map = json!([{
-            "to": addr,
-            "data": data
-        }])
-        .to_string();
-        let addr_static: &'static str = Box::leak(param_map.into_boxed_str());
-        let params = Params::new(Some(addr_static));
-
-        let result = eth_call(_ctx.clone().into(), params).await;
-
-        assert!(result.is_ok());
-        let expected_result = token_decimals.abi_encode().encode_hex();
-        assert_eq!(result.unwrap(), expected_result);
-    }
+        {
+            let method_hash = "0x70a08231";
+            let address: Address = Address::from([8; 20]);
+            let data = format!("{}{}", method_hash, hex::encode(address));
+            let addr = contract_address.to_string();
+            let param_map = json!([{
+                "to": addr,
+                "data": data
+            }])
+            .to_string();
+            let addr_static: &'static str = Box::leak(param_map.into_boxed_str());
+            let params = Params::new(Some(addr_static));
 
-    {
-        let method_hash = "0x95d89b41";
-        let address: Address = Address::from([8; 20]);
-
-        let data = format!("{}{}", method_hash, hex::encode(address));
-        let addr = contract_address.to_string();
-        let param_map = json!([{
-            "to": addr,
-            "data": data
-        }])
-        .to_string();
-        let addr_static: &'static str = Box::leak(param_map.into_boxed_str());
-        let params = Params::new(Some(addr_static));
-
-        let result = eth_call(_ctx.clone().into(), params).await;
-
-        assert!(result.is_ok());
-        let expected_result = token_symbol.abi_encode().encode_hex();
-        assert_eq!(result.unwrap(), expected_result);
-    }
+            _ctx.lock()
+                .await
+                .chain
+                .db
+                .put(
+                    Key::Balance(
+                        address,
+                        Token::Erc20(ERC20 {
+                            address: contract_address,
+                            decimals: token_decimals,
+                            symbol: token_symbol.clone(),
+                        }),
+                    ),
+                    Some(Value::U256(balance)),
+                )
+                .unwrap();
+
+            let result = eth_call(_ctx.clone(), params).await;
+            assert!(result.is_ok());
+            let expected_result = balance.abi_encode().encode_hex();
+            assert_eq!(result.unwrap(), expected_result);
+        }
+
+        {
+            let method_hash = "0x313ce567";
+            let address: Address = Address::from([8; 20]);
+
+            let data = format!("{}{}", method_hash, hex::encode(address));
+            let addr = contract_address.to_string();
+            let param_map = json!([{
+                "to": addr,
+                "data": data
+            }])
+            .to_string();
+            let addr_static: &'static str = Box::leak(param_map.into_boxed_str());
+            let params = Params::new(Some(addr_static));
+
+            let result = eth_call(_ctx.clone(), params).await;
+
+            assert!(result.is_ok());
+            let expected_result = token_decimals.abi_encode().encode_hex();
+            assert_eq!(result.unwrap(), expected_result);
+        }
+
+        {
+            let method_hash = "0x95d89b41";
+            let address: Address = Address::from([8; 20]);
+
+            let data = format!("{}{}", method_hash, hex::encode(address));
+            let addr = contract_address.to_string();
+            let param_map = json!([{
+                "to": addr,
+                "data": data
+            }])
+            .to_string();
+            let addr_static: &'static str = Box::leak(param_map.into_boxed_str());
+            let params = Params::new(Some(addr_static));
+
+            let result = eth_call(_ctx.clone(), params).await;
+
+            assert!(result.is_ok());
+            let expected_result = token_symbol.abi_encode().encode_hex();
+            assert_eq!(result.unwrap(), expected_result);
+        }
+
+        {
+            let method_hash = "0x12345678";
+            let address: Address = Address::from([8; 20]);
+
+            let data = format!("{}{}", method_hash, hex::encode(address));
+            let addr = contract_address.to_string();
+            let param_map = json!([{
+                "to": addr,
+                "data": data
+            }])
+            .to_string();
+            let addr_static: &'static str = Box::leak(param_map.into_boxed_str());
+            let params = Params::new(Some(addr_static));
 
-    {
-        let method_hash = "0x12345678";
-        let address: Address = Address::from([8; 20]);
+            let result = eth_call(_ctx.clone(), params).await;
+
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap(), format!("0x{:x}", 0));
+        }
+    }
 
-        let data = format!("{}{}", method_hash, hex::encode(address));
-        let addr = contract_address.to_string();
-        let param_map = json!([{
-            "to": addr,
-            "data": data
-        }])
-        .to_string();
-        let addr_static: &'static str = Box::leak(param_map.into_boxed_str());
-        let params = Params::new(Some(addr_static));
+    #[tokio::test]
+    async fn test_eth_call_pending() {
+        let ctx = test_config().await;
+        let contract_address = Address::from([7; 20]);
+        let signer = PrivateKeySigner::random();
+        let receiver = Address::from([8; 20]);
+        {
+            let mut ctx = ctx.lock().await;
+            let token = Token::Erc20(ERC20 {
+                address: contract_address,
+                decimals: ctx.chain.get_token_decimal(contract_address).unwrap(),
+                symbol: ctx.chain.get_token_symbol(contract_address).unwrap(),
+            });
+            ctx.chain
+                .db
+                .put(
+                    Key::Balance(signer.address(), token),
+                    Some(Value::U256(U256::from(100))),
+                )
+                .unwrap();
+        }
+        push_mint_block(&ctx, signer.address(), 1_000_000_000_000_000_000).await;
 
-        let result = eth_call(_ctx.clone().into(), params).await;
+        // transfer(receiver, 40)
+        let mut input = vec![169, 5, 156, 187];
+        input.extend_from_slice(&receiver.abi_encode());
+        input.extend_from_slice(&U256::from(40).abi_encode());
+        queue_call(&ctx, &signer, 0, contract_address, 0, input).await;
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), format!("0x{:x}", 0));
+        let balance_of = |tag: &str| {
+            let params = json!([{
+                "to": contract_address,
+                "data": format!("0x70a08231{}", hex::encode(receiver)),
+            }, tag])
+            .to_string();
+            let params: &'static str = Box::leak(params.into_boxed_str());
+            eth_call(ctx.clone(), Params::new(Some(params)))
+        };
+        assert_eq!(
+            balance_of("latest").await.unwrap(),
+            U256::ZERO.abi_encode().encode_hex()
+        );
+        assert_eq!(
+            balance_of("pending").await.unwrap(),
+            U256::from(40).abi_encode().encode_hex()
+        );
     }
 }
@@ -6,26 +6,32 @@ use jsonrpsee::types::Params;
 use tokio::sync::Mutex;
 
 use super::Context;
-use crate::services::{rpc_services::test_config, ContextKvStore, ContextSigner};
+use crate::services::{ContextKvStore, ContextSigner};
 
 pub async fn eth_chain_id<S: ContextSigner, K: ContextKvStore>(
-    ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     _params: Params<'static>,
 ) -> Result<String> {
     let chain_id = ctx.lock().await.chain.config().chain_id;
     Ok(format!("0x{:x}", chain_id))
 }
 
-#[tokio::test]
-async fn test_eth_chain_id() {
-    let _ctx = test_config().await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::test_config;
 
-    let params = Params::new(None);
+    #[tokio::test]
+    async fn test_eth_chain_id() {
+        let _ctx = test_config().await;
 
-    let result = eth_chain_id(_ctx.clone().into(), params).await.unwrap();
+        let params = Params::new(None);
 
-    assert_eq!(
-        result,
-        format!("0x{:x}", _ctx.lock().await.chain.config().chain_id)
-    );
+        let result = eth_chain_id(_ctx.clone(), params).await.unwrap();
+
+        assert_eq!(
+            result,
+            format!("0x{:x}", _ctx.lock().await.chain.config().chain_id)
+        );
+    }
 }
@@ -1,34 +1,72 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use alloy::primitives::{Address, TxKind, U256};
 use anyhow::Result;
 use jsonrpsee::types::Params;
-use serde_json::{json, Value};
 use tokio::sync::Mutex;
 
 use super::Context;
-use crate::services::{rpc_services::test_config, ContextKvStore, ContextSigner};
+use crate::blockchain::Blockchain;
+use crate::services::{ContextKvStore, ContextSigner};
 
 pub async fn eth_estimate_gas<S: ContextSigner, K: ContextKvStore>(
-    _ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     params: Params<'static>,
 ) -> Result<String> {
-    let params: Vec<String> = params.parse()?;
-    let data = params.get(0).map(|s| s.to_owned()).unwrap_or_default();
-    let mut gas_estimate = 0u64;
+    let first_param: HashMap<String, String> = params.sequence().next()?;
+    let from: Address = match first_param.get("from") {
+        Some(from) => from.parse()?,
+        None => Address::ZERO,
+    };
+    let to = match first_param.get("to") {
+        Some(to) => TxKind::Call(to.parse()?),
+        None => TxKind::Create,
+    };
+    let value: U256 = match first_param.get("value") {
+        Some(value) => value.parse()?,
+        None => U256::ZERO,
+    };
+    let input = match first_param.get("data").or(first_param.get("input")) {
+        Some(data) => hex::decode(data.trim_start_matches("0x"))?,
+        None => Vec::new(),
+    };
 
-    if !data.is_empty() {
-        gas_estimate += (data.len() as u64) * 68;
-    }
+    let gas_estimate = ctx
+        .lock()
+        .await
+        .chain
+        .estimate_gas(from, to, value, input)?;
 
     Ok(format!("0x{:x}", gas_estimate))
 }
 
-#[tokio::test]
-async fn test_eth_estimate_gas() {
-    let _ctx = test_config().await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Key, KvStore, Value};
+    use crate::services::rpc_services::test_config;
+    use crate::types::Token;
+
+    #[tokio::test]
+    async fn test_eth_estimate_gas() {
+        let _ctx = test_config().await;
+        let from = Address::from([1; 20]);
+        let _ = _ctx.lock().await.chain.db.put(
+            Key::Balance(from, Token::Native),
+            Some(Value::U256(U256::from(1000))),
+        );
 
-    let params = Params::new(Some("[]"));
-    let result = eth_estimate_gas(_ctx.clone().into(), params).await.unwrap();
-    assert_eq!(result, "0x0");
+        let params = Params::new(Some(
+            r#"[{"from":"0x0101010101010101010101010101010101010101","to":"0x0202020202020202020202020202020202020202","value":"0x10"}]"#,
+        ));
+        let result = eth_estimate_gas(_ctx.clone(), params).await.unwrap();
+        assert_eq!(result, "0x5208");
 
+        // Transfers more than the sender has
+        let params = Params::new(Some(
+            r#"[{"from":"0x0101010101010101010101010101010101010101","to":"0x0202020202020202020202020202020202020202","value":"0x10000"}]"#,
+        ));
+        assert!(eth_estimate_gas(_ctx.clone(), params).await.is_err());
+    }
 }
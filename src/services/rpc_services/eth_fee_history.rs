@@ -1,82 +1,159 @@
 use std::sync::Arc;
 
-use crate::services::{ContextKvStore, ContextSigner, rpc_services::test_config};
-use anyhow::Result;
+use crate::blockchain::{
+    gas::{self},
+    Blockchain,
+};
+use crate::services::{ContextKvStore, ContextSigner};
+use anyhow::{anyhow, Result};
 use jsonrpsee::types::Params;
 use serde_json::json;
 use tokio::sync::Mutex;
 
 use super::Context;
 
+/// Most blocks a single `eth_feeHistory` call may ask for
+const MAX_BLOCK_COUNT: u64 = 1024;
+
+fn parse_quantity(value: &serde_json::Value) -> Result<u64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().ok_or(anyhow!("Invalid quantity!")),
+        serde_json::Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => Ok(u64::from_str_radix(hex, 16)?),
+            None => Ok(s.parse()?),
+        },
+        _ => Err(anyhow!("Invalid quantity!")),
+    }
+}
+
 pub async fn eth_fee_history<S: ContextSigner, K: ContextKvStore>(
-    _ctx: Arc<Arc<Mutex<Context<S, K>>>>,
-    _params: Params<'static>,
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
 ) -> Result<serde_json::Value> {
-    let _count = 0;
+    let params: Vec<serde_json::Value> = params.parse()?;
+    let block_count = parse_quantity(params.first().ok_or(anyhow!("Block count unavailable!"))?)?
+        .min(MAX_BLOCK_COUNT);
+    let percentiles: Vec<f64> = match params.get(2) {
+        Some(p) => serde_json::from_value(p.clone())?,
+        None => Vec::new(),
+    };
+
+    let ctx = ctx.lock().await;
+    let height = ctx.chain.get_height()? as u64;
+    if height == 0 || block_count == 0 {
+        return Ok(json!({
+            "oldestBlock": "0x0",
+            "baseFeePerGas": [format!("0x{:x}", ctx.chain.pending_base_fee()?)],
+            "gasUsedRatio": [],
+        }));
+    }
+    let newest_block = match params.get(1).and_then(|b| b.as_str()) {
+        None | Some("latest") | Some("pending") | Some("safe") | Some("finalized") => height - 1,
+        Some("earliest") => 0,
+        Some(_) => parse_quantity(&params[1])?,
+    };
 
-    Ok(json!({
-        "baseFeePerGas": [
-            "0",
-            "0",
-            "0",
-            "0",
-            "0",
-            "0"
-        ],
-        "gasUsedRatio": [
-            0,
-            0,
-            0,
-            0,
-            0
-        ],
-        "oldestBlock": "0xfab8ac",
-        "reward": [
-            [
-                "0",
-                "0"
-            ],
-            [
-                "0",
-                "0"
-            ],
-            [
-                "0",
-                "0"
-            ],
-            [
-                "0",
-                "0"
-            ],
-            [
-                "0",
-                "0"
-            ]
-        ]
-    }))
+    let history = gas::fee_history(&ctx.chain, block_count, newest_block, &percentiles)?;
+    let mut result = json!({
+        "oldestBlock": format!("0x{:x}", history.oldest_block),
+        "baseFeePerGas": history
+            .base_fee_per_gas
+            .iter()
+            .map(|fee| format!("0x{:x}", fee))
+            .collect::<Vec<_>>(),
+        "gasUsedRatio": history.gas_used_ratio,
+    });
+    if !percentiles.is_empty() {
+        result["reward"] = json!(history
+            .reward
+            .iter()
+            .map(|tips| tips
+                .iter()
+                .map(|tip| format!("0x{:x}", tip))
+                .collect::<Vec<_>>())
+            .collect::<Vec<_>>());
+    }
+    Ok(result)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::gas::{next_base_fee, BLOCK_GAS_LIMIT, INITIAL_BASE_FEE};
+    use crate::blockchain::TransactionQueue;
+    use crate::db::{Key, KvStore, Value};
+    use crate::services::rpc_services::test_config;
+    use crate::types::{OwshenTransaction, Token};
+    use alloy::consensus::{TxEip1559, TypedTransaction};
+    use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+    use alloy::primitives::{Address, Bytes, TxKind, U256};
+    use alloy::signers::local::PrivateKeySigner;
 
-#[tokio::test]
-async fn test_eth_fee_history() {
-    let _ctx = test_config().await;
-    let params = Params::new(None);
-    let result = eth_fee_history(_ctx.into(), params).await;
-    match result {
-        Ok(value) => {
-            assert_eq!(value["baseFeePerGas"], json!(["0", "0", "0", "0", "0", "0"]));
-            assert_eq!(value["gasUsedRatio"], json!([0, 0, 0, 0, 0]));
-            assert_eq!(value["oldestBlock"], "0xfab8ac");
-            assert_eq!(value["reward"], json!([
-                ["0", "0"],
-                ["0", "0"],
-                ["0", "0"],
-                ["0", "0"],
-                ["0", "0"]
-            ]));
-        }
-        Err(e) => {
-            panic!("eth_fee_history failed: {:?}", e);
+    #[tokio::test]
+    async fn test_eth_fee_history() {
+        let _ctx = test_config().await;
+        let wallet = EthereumWallet::new(PrivateKeySigner::random());
+        let sender = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+        let chain_id = _ctx.lock().await.chain.config().chain_id;
+        let _ = _ctx.lock().await.chain.db.put(
+            Key::Balance(sender, Token::Native),
+            Some(Value::U256(U256::from(1_000_000_000_000_000u64))),
+        );
+
+        // A block with two transfers tipping differently, then an empty one
+        for txs in [vec![(0, 1_000), (1, 5_000)], vec![]] {
+            let mut ctx = _ctx.lock().await;
+            for (nonce, tip) in txs {
+                let tx = TxEip1559 {
+                    nonce,
+                    gas_limit: 21_000,
+                    to: TxKind::Call(Address::from([6; 20])),
+                    value: U256::from(1),
+                    input: Bytes::new(),
+                    chain_id,
+                    max_priority_fee_per_gas: tip,
+                    max_fee_per_gas: 2_000_000_000,
+                    access_list: Default::default(),
+                };
+                let tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+                    &wallet,
+                    TypedTransaction::Eip1559(tx),
+                )
+                .await
+                .unwrap();
+                ctx.tx_queue.enqueue(OwshenTransaction::Eth(tx));
+            }
+            let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+            let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+            ctx.chain.push_block(blk).unwrap();
         }
+
+        let params = Params::new(Some(r#"["0x5", "latest", [25, 75]]"#));
+        let result = eth_fee_history(_ctx.clone(), params).await.unwrap();
+
+        let second_base_fee = next_base_fee(INITIAL_BASE_FEE, 42_000);
+        assert_eq!(result["oldestBlock"], "0x0");
+        assert_eq!(
+            result["baseFeePerGas"],
+            json!([
+                format!("0x{:x}", INITIAL_BASE_FEE),
+                format!("0x{:x}", second_base_fee),
+                format!("0x{:x}", next_base_fee(second_base_fee, 0)),
+            ])
+        );
+        assert_eq!(
+            result["gasUsedRatio"],
+            json!([42_000.0 / BLOCK_GAS_LIMIT as f64, 0.0])
+        );
+        assert_eq!(
+            result["reward"],
+            json!([["0x3e8", "0x1388"], ["0x0", "0x0"]])
+        );
+
+        let params = Params::new(Some(r#"[1, "0x0"]"#));
+        let result = eth_fee_history(_ctx, params).await.unwrap();
+        assert_eq!(result["oldestBlock"], "0x0");
+        assert_eq!(result["baseFeePerGas"].as_array().unwrap().len(), 2);
+        assert!(result.get("reward").is_none());
     }
 }
@@ -1,60 +1,88 @@
-use std::borrow::Cow;
 use std::sync::Arc;
 
 use crate::blockchain::Blockchain;
-use crate::db::{Key, KvStore, Value};
-use alloy::primitives::utils::parse_units;
 use alloy::primitives::Address;
-use alloy::signers::local::PrivateKeySigner;
-use alloy::signers::Signer;
 use anyhow::{anyhow, Result};
 use jsonrpsee::types::Params;
-use serde_json::json;
 use tokio::sync::Mutex;
 
 use super::Context;
-use crate::services::{rpc_services::test_config, ContextKvStore, ContextSigner};
+use crate::services::{ContextKvStore, ContextSigner};
 use crate::types::Token;
 
 pub async fn eth_get_balance<S: ContextSigner, K: ContextKvStore>(
-    ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     params: Params<'static>,
 ) -> Result<String> {
     let params: Vec<String> = params.parse()?;
     let addr: Address = params
-        .get(0)
+        .first()
         .ok_or(anyhow!("Address unavailable!"))?
         .parse()?;
-    let balance = ctx.lock().await.chain.get_balance(Token::Native, addr)?;
+    let ctx = ctx.lock().await;
+    let balance = if super::is_pending(params.get(1).map(String::as_str)) {
+        ctx.chain
+            .pending_state(&ctx.tx_queue)?
+            .get_balance(Token::Native, addr)?
+    } else {
+        ctx.chain.get_balance(Token::Native, addr)?
+    };
     Ok(format!("0x{:x}", balance))
 }
 
-#[tokio::test]
-async fn test_eth_get_balance() {
-    let _ctx = test_config().await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Key, KvStore, Value};
+    use crate::services::rpc_services::{push_mint_block, queue_call, test_config};
+    use alloy::primitives::utils::parse_units;
+    use alloy::signers::local::PrivateKeySigner;
+    use serde_json::json;
 
-    let address: Address = Address::from([2; 20]);
-    let amount = parse_units("2", 18).unwrap().into();
+    #[tokio::test]
+    async fn test_eth_get_balance() {
+        let _ctx = test_config().await;
 
-    _ctx.lock()
-        .await
-        .chain
-        .db
-        .put(
-            Key::Balance(address, Token::Native),
-            Some(Value::U256(amount)),
-        )
-        .unwrap();
+        let address: Address = Address::from([2; 20]);
+        let amount = parse_units("2", 18).unwrap().into();
 
-    let addr = address.to_string();
-    let j = json!([addr, "latest"]).to_string();
-    let addr_static: &'static str = Box::leak(j.into_boxed_str());
-    let params = Params::new(Some(addr_static));
+        _ctx.lock()
+            .await
+            .chain
+            .db
+            .put(
+                Key::Balance(address, Token::Native),
+                Some(Value::U256(amount)),
+            )
+            .unwrap();
 
-    let result = eth_get_balance(_ctx.into(), params).await;
+        let addr = address.to_string();
+        let j = json!([addr, "latest"]).to_string();
+        let addr_static: &'static str = Box::leak(j.into_boxed_str());
+        let params = Params::new(Some(addr_static));
 
-    assert!(result.is_ok());
+        let result = eth_get_balance(_ctx, params).await;
 
-    let balance = result.unwrap();
-    assert_eq!(balance, format!("0x{:x}", amount));
+        assert!(result.is_ok());
+
+        let balance = result.unwrap();
+        assert_eq!(balance, format!("0x{:x}", amount));
+    }
+
+    #[tokio::test]
+    async fn test_eth_get_balance_pending() {
+        let ctx = test_config().await;
+        let signer = PrivateKeySigner::random();
+        let to = PrivateKeySigner::random().address();
+        push_mint_block(&ctx, signer.address(), 1_000_000_000_000_000_000).await;
+        queue_call(&ctx, &signer, 0, to, 1000, vec![]).await;
+
+        let get_balance = |tag: &str| {
+            let params = json!([to, tag]).to_string();
+            let params: &'static str = Box::leak(params.into_boxed_str());
+            eth_get_balance(ctx.clone(), Params::new(Some(params)))
+        };
+        assert_eq!(get_balance("latest").await.unwrap(), "0x0");
+        assert_eq!(get_balance("pending").await.unwrap(), "0x3e8");
+    }
 }
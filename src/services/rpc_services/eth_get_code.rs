@@ -1,22 +1,24 @@
 use std::sync::Arc;
 
+use alloy::primitives::Address;
 use anyhow::{Ok, Result};
 use jsonrpsee::types::Params;
 use tokio::sync::Mutex;
 
 use super::Context;
-use crate::services::{ContextKvStore, ContextSigner};
+use crate::{
+    blockchain::Blockchain,
+    services::{ContextKvStore, ContextSigner},
+};
 
 pub async fn eth_get_code<S: ContextSigner, K: ContextKvStore>(
-    _ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     params: Params<'static>,
 ) -> Result<String> {
     let params: Vec<String> = params.parse()?;
-    let _address = &params[0];
+    let address: Address = params[0].parse()?;
 
-    //TODO: Handle the get code
+    let code = ctx.lock().await.chain.get_code(address)?;
 
-    let code = "".to_string();
-
-    Ok(code)
+    Ok(format!("0x{}", hex::encode(code)))
 }
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use alloy::primitives::{FixedBytes, U256};
+use anyhow::{anyhow, Result};
+use jsonrpsee::types::Params;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::{logs::LogFilter, Blockchain};
+use crate::services::{filters::FilterKind, ContextKvStore, ContextSigner};
+
+/// What happened since the filter was last polled: new logs, hashes of new
+/// blocks or hashes of new pending transactions, depending on the filter
+pub async fn eth_get_filter_changes<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+) -> Result<serde_json::Value> {
+    let params: Vec<U256> = params.parse()?;
+    let id = *params.first().ok_or(anyhow!("Filter id unavailable!"))?;
+
+    let mut guard = ctx.lock().await;
+    let ctx = &mut *guard;
+    let height = ctx.chain.get_height()?;
+    let filter = ctx.filters.poll(id)?;
+    let since = filter.height;
+
+    let changes = match &filter.kind {
+        FilterKind::Logs(query) => {
+            let filter = query.resolve(&ctx.chain)?;
+            let filter = LogFilter {
+                from_block: filter.from_block.max(since),
+                ..filter
+            };
+            json!(ctx
+                .chain
+                .get_logs(&filter)?
+                .iter()
+                .map(super::log_json)
+                .collect::<Vec<_>>())
+        }
+        FilterKind::Blocks => json!((since..height)
+            .map(|index| Ok(format!("0x{:x}", ctx.chain.get_block(index)?.hash()?)))
+            .collect::<Result<Vec<_>>>()?),
+        FilterKind::PendingTransactions => {
+            let queued = ctx
+                .tx_queue
+                .queue()
+                .iter()
+                .map(|tx| tx.hash())
+                .collect::<Result<Vec<_>>>()?;
+            let new = queued
+                .iter()
+                .filter(|hash| !filter.seen.contains(*hash))
+                .map(|hash| format!("0x{:x}", hash))
+                .collect::<Vec<_>>();
+            filter.seen = queued.into_iter().collect::<HashSet<FixedBytes<32>>>();
+            json!(new)
+        }
+    };
+    // Only moved once the changes are built, failed polls are reported again
+    filter.height = height;
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::{
+        eth_new_block_filter, eth_new_filter, eth_new_pending_transaction_filter, push_mint_block,
+        test_config,
+    };
+    use alloy::primitives::Address;
+
+    #[tokio::test]
+    async fn test_eth_get_filter_changes() {
+        let ctx = test_config().await;
+        let alice = Address::from([1; 20]);
+        push_mint_block(&ctx, alice, 10).await;
+
+        let install = |id: Result<String>| {
+            let id = id.unwrap();
+            let params: &'static str = Box::leak(json!([id]).to_string().into_boxed_str());
+            params
+        };
+        let changes =
+            |params: &'static str| eth_get_filter_changes(ctx.clone(), Params::new(Some(params)));
+
+        let log_filter = install(
+            eth_new_filter(
+                ctx.clone(),
+                Params::new(Some(
+                    r#"[{"address": "0x0000000000000000000000000000000000000000"}]"#,
+                )),
+            )
+            .await,
+        );
+        let block_filter = install(eth_new_block_filter(ctx.clone(), Params::new(None)).await);
+        let pending_filter =
+            install(eth_new_pending_transaction_filter(ctx.clone(), Params::new(None)).await);
+
+        // Nothing happened since the filters were installed
+        assert_eq!(changes(log_filter).await.unwrap(), json!([]));
+        assert_eq!(changes(block_filter).await.unwrap(), json!([]));
+        assert_eq!(changes(pending_filter).await.unwrap(), json!([]));
+
+        let tx_hash = push_mint_block(&ctx, alice, 20).await;
+        let block_hash = ctx.lock().await.chain.get_block(1).unwrap().hash().unwrap();
+        let logs = changes(log_filter).await.unwrap();
+        assert_eq!(logs.as_array().unwrap().len(), 1);
+        assert_eq!(logs[0]["transactionHash"], format!("0x{:x}", tx_hash));
+        assert_eq!(
+            changes(block_filter).await.unwrap(),
+            json!([format!("0x{:x}", block_hash)])
+        );
+
+        // Changes are only reported once
+        assert_eq!(changes(log_filter).await.unwrap(), json!([]));
+        assert_eq!(changes(block_filter).await.unwrap(), json!([]));
+
+        let queued = ctx.lock().await.chain.get_block(0).unwrap().txs[0].clone();
+        let queued: crate::types::OwshenTransaction = queued.try_into().unwrap();
+        ctx.lock().await.tx_queue.enqueue(queued.clone());
+        assert_eq!(
+            changes(pending_filter).await.unwrap(),
+            json!([format!("0x{:x}", queued.hash().unwrap())])
+        );
+        assert_eq!(changes(pending_filter).await.unwrap(), json!([]));
+
+        assert!(changes(r#"["0x99"]"#).await.is_err());
+    }
+}
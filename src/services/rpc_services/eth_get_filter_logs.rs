@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use alloy::primitives::U256;
+use anyhow::{anyhow, Result};
+use jsonrpsee::types::Params;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::Blockchain;
+use crate::services::{filters::FilterKind, ContextKvStore, ContextSigner};
+
+/// Every log matching an installed log filter, regardless of earlier polls
+pub async fn eth_get_filter_logs<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+) -> Result<serde_json::Value> {
+    let params: Vec<U256> = params.parse()?;
+    let id = *params.first().ok_or(anyhow!("Filter id unavailable!"))?;
+
+    let mut guard = ctx.lock().await;
+    let ctx = &mut *guard;
+    let query = match &ctx.filters.poll(id)?.kind {
+        FilterKind::Logs(query) => query.clone(),
+        _ => return Err(anyhow!("Not a log filter!")),
+    };
+    let filter = query.resolve(&ctx.chain)?;
+    Ok(json!(ctx
+        .chain
+        .get_logs(&filter)?
+        .iter()
+        .map(super::log_json)
+        .collect::<Vec<_>>()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::{
+        eth_new_block_filter, eth_new_filter, push_mint_block, test_config,
+    };
+    use alloy::primitives::Address;
+
+    #[tokio::test]
+    async fn test_eth_get_filter_logs() {
+        let ctx = test_config().await;
+        let alice = Address::from([1; 20]);
+        let bob = Address::from([2; 20]);
+        push_mint_block(&ctx, alice, 10).await;
+
+        let filter = json!([{ "fromBlock": "earliest", "topics": [null, bob.into_word()] }]);
+        let filter: &'static str = Box::leak(filter.to_string().into_boxed_str());
+        let id = eth_new_filter(ctx.clone(), Params::new(Some(filter)))
+            .await
+            .unwrap();
+        let id: &'static str = Box::leak(json!([id]).to_string().into_boxed_str());
+
+        let logs =
+            |params: &'static str| eth_get_filter_logs(ctx.clone(), Params::new(Some(params)));
+        assert_eq!(logs(id).await.unwrap(), json!([]));
+
+        push_mint_block(&ctx, bob, 20).await;
+        push_mint_block(&ctx, bob, 30).await;
+        assert_eq!(logs(id).await.unwrap().as_array().unwrap().len(), 2);
+        // Unlike the changes, the logs can be fetched again
+        assert_eq!(logs(id).await.unwrap().as_array().unwrap().len(), 2);
+
+        let block_filter = eth_new_block_filter(ctx.clone(), Params::new(None))
+            .await
+            .unwrap();
+        let block_filter: &'static str =
+            Box::leak(json!([block_filter]).to_string().into_boxed_str());
+        assert!(logs(block_filter).await.is_err());
+    }
+}
@@ -5,13 +5,36 @@ use jsonrpsee::types::Params;
 use tokio::sync::Mutex;
 
 use super::Context;
+use crate::blockchain::{
+    gas::{self},
+    Blockchain,
+};
 use crate::services::{ContextKvStore, ContextSigner};
 
+/// Base fee of the next block plus the suggested tip
 pub async fn eth_get_gas_price<S: ContextSigner, K: ContextKvStore>(
-    _ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     _params: Params<'static>,
 ) -> Result<String> {
-    let gas_price = 0x0;
+    let ctx = ctx.lock().await;
+    let gas_price = ctx.chain.pending_base_fee()? as u128 + gas::suggest_priority_fee(&ctx.chain)?;
 
     Ok(format!("0x{:x}", gas_price))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::gas::{DEFAULT_PRIORITY_FEE, INITIAL_BASE_FEE};
+    use crate::services::rpc_services::test_config;
+
+    #[tokio::test]
+    async fn test_eth_get_gas_price() {
+        let _ctx = test_config().await;
+        let result = eth_get_gas_price(_ctx, Params::new(None)).await.unwrap();
+        assert_eq!(
+            result,
+            format!("0x{:x}", INITIAL_BASE_FEE + DEFAULT_PRIORITY_FEE)
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use jsonrpsee::types::Params;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::Blockchain;
+use crate::services::{filters::LogQuery, ContextKvStore, ContextSigner};
+
+pub async fn eth_get_logs<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+) -> Result<serde_json::Value> {
+    let params: Vec<serde_json::Value> = params.parse()?;
+    let query = LogQuery::parse(params.first().ok_or(anyhow!("Filter unavailable!"))?)?;
+
+    let ctx = ctx.lock().await;
+    let filter = query.resolve(&ctx.chain)?;
+    Ok(json!(ctx
+        .chain
+        .get_logs(&filter)?
+        .iter()
+        .map(super::log_json)
+        .collect::<Vec<_>>()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::{push_mint_block, test_config};
+    use alloy::primitives::{keccak256, Address};
+
+    #[tokio::test]
+    async fn test_eth_get_logs() {
+        let ctx = test_config().await;
+        let alice = Address::from([1; 20]);
+        let bob = Address::from([2; 20]);
+        let first = push_mint_block(&ctx, alice, 10).await;
+        push_mint_block(&ctx, bob, 20).await;
+        let third = push_mint_block(&ctx, alice, 30).await;
+
+        let get_logs = |filter: serde_json::Value| {
+            let params: &'static str = Box::leak(json!([filter]).to_string().into_boxed_str());
+            eth_get_logs(ctx.clone(), Params::new(Some(params)))
+        };
+
+        let mint = keccak256("Mint(address,uint256)");
+        let logs = get_logs(json!({
+            "fromBlock": "earliest",
+            "topics": [mint, alice.into_word()],
+        }))
+        .await
+        .unwrap();
+        let logs = logs.as_array().unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0]["transactionHash"], format!("0x{:x}", first));
+        assert_eq!(logs[0]["blockNumber"], "0x0");
+        assert_eq!(logs[0]["logIndex"], "0x0");
+        assert_eq!(logs[1]["transactionHash"], format!("0x{:x}", third));
+        assert_eq!(logs[1]["blockNumber"], "0x2");
+
+        // Either recipient, restricted to a block range
+        let logs = get_logs(json!({
+            "fromBlock": "0x1",
+            "toBlock": "0x1",
+            "address": Address::ZERO,
+            "topics": [null, [alice.into_word(), bob.into_word()]],
+        }))
+        .await
+        .unwrap();
+        assert_eq!(logs.as_array().unwrap().len(), 1);
+        assert_eq!(logs[0]["topics"][1], json!(bob.into_word()));
+
+        // Without a range only the latest block is searched
+        let logs = get_logs(json!({ "address": Address::ZERO })).await.unwrap();
+        assert_eq!(logs.as_array().unwrap().len(), 1);
+        assert_eq!(logs[0]["blockNumber"], "0x2");
+
+        let block_hash = ctx.lock().await.chain.get_block(1).unwrap().hash().unwrap();
+        let logs = get_logs(json!({ "blockHash": block_hash })).await.unwrap();
+        assert_eq!(logs[0]["blockHash"], format!("0x{:x}", block_hash));
+
+        let logs = get_logs(json!({ "fromBlock": "earliest", "address": alice }))
+            .await
+            .unwrap();
+        assert_eq!(logs, json!([]));
+    }
+}
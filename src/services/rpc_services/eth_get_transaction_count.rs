@@ -1,55 +1,95 @@
 use std::sync::Arc;
 
-use alloy::primitives::{Address, Uint, U256};
+use alloy::primitives::{Address, Uint};
 use anyhow::{anyhow, Result};
 use jsonrpsee::types::Params;
-use serde_json::json;
 use tokio::sync::Mutex;
 
 use super::Context;
 use crate::{
     blockchain::Blockchain,
-    db::{Key, KvStore, Value},
-    services::{rpc_services::test_config, ContextKvStore, ContextSigner},
+    services::{ContextKvStore, ContextSigner},
 };
 
 pub async fn eth_get_transaction_count<S: ContextSigner, K: ContextKvStore>(
-    ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     params: Params<'static>,
 ) -> Result<Uint<256, 4>, anyhow::Error> {
     let params: Vec<String> = params.parse()?;
     let addr: Address = params
-        .get(0)
+        .first()
         .ok_or(anyhow!("Address unavailable!"))?
         .parse()?;
 
-    let nonce = ctx.lock().await.chain.get_eth_nonce(addr).unwrap();
-
-    Ok(nonce)
+    let ctx = ctx.lock().await;
+    // Every queued transaction counts, even those not fitting in the next
+    // block, so that wallets don't reuse their nonces
+    if super::is_pending(params.get(1).map(String::as_str)) {
+        return ctx.tx_queue.next_eth_nonce(&ctx.chain, addr);
+    }
+    ctx.chain.get_eth_nonce(addr)
 }
 
-#[tokio::test]
-async fn test_eth_get_transaction_count() {
-    let _ctx = test_config().await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::gas::{BlockLimits, MAX_BLOCK_SIZE};
+    use crate::blockchain::Owshenchain;
+    use crate::db::{Key, KvStore, RamKvStore, Value};
+    use crate::services::rpc_services::{push_mint_block, queue_call, test_config};
+    use alloy::primitives::U256;
+    use alloy::signers::local::PrivateKeySigner;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_eth_get_transaction_count() {
+        let _ctx = test_config().await;
+
+        let address: Address = Address::from([2; 20]);
+
+        _ctx.lock()
+            .await
+            .chain
+            .db
+            .put(Key::NonceEth(address), Some(Value::U256(U256::from(10))))
+            .unwrap();
 
-    let address: Address = Address::from([2; 20]);
+        let addr = address.to_string();
+        let j = json!([addr, "latest"]).to_string();
+        let addr_static: &'static str = Box::leak(j.into_boxed_str());
+        let params = Params::new(Some(addr_static));
 
-    _ctx.lock()
-        .await
-        .chain
-        .db
-        .put(Key::NonceEth(address), Some(Value::U256(U256::from(10))))
-        .unwrap();
+        let result = eth_get_transaction_count(_ctx, params).await;
 
-    let addr = address.to_string();
-    let j = json!([addr, "latest"]).to_string();
-    let addr_static: &'static str = Box::leak(j.into_boxed_str());
-    let params = Params::new(Some(addr_static));
+        assert!(result.is_ok());
 
-    let result = eth_get_transaction_count(_ctx.into(), params).await;
+        let nonce = result.unwrap();
+        assert_eq!(nonce, U256::from(10));
+    }
 
-    assert!(result.is_ok());
+    #[tokio::test]
+    async fn test_eth_get_transaction_count_pending() {
+        let ctx = test_config().await;
+        {
+            // Only one of the queued calls fits in the next block
+            let mut ctx = ctx.lock().await;
+            let conf = ctx.chain.config().clone();
+            ctx.chain = Owshenchain::new(conf, RamKvStore::new()).with_block_limits(BlockLimits {
+                gas: 150_000,
+                size: MAX_BLOCK_SIZE,
+            });
+        }
+        let signer = PrivateKeySigner::random();
+        push_mint_block(&ctx, signer.address(), 1_000_000_000_000_000_000).await;
+        queue_call(&ctx, &signer, 0, Address::from([2; 20]), 1, vec![]).await;
+        queue_call(&ctx, &signer, 1, Address::from([2; 20]), 1, vec![]).await;
 
-    let nonce = result.unwrap();
-    assert_eq!(nonce, U256::from(10));
+        let get_count = |tag: &str| {
+            let params = json!([signer.address(), tag]).to_string();
+            let params: &'static str = Box::leak(params.into_boxed_str());
+            eth_get_transaction_count(ctx.clone(), Params::new(Some(params)))
+        };
+        assert_eq!(get_count("latest").await.unwrap(), U256::ZERO);
+        assert_eq!(get_count("pending").await.unwrap(), U256::from(2));
+    }
 }
@@ -1,5 +1,4 @@
-use alloy::signers::local::PrivateKeySigner;
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use jsonrpsee::types::Params;
 use serde_json::json;
 use std::str::FromStr;
@@ -7,24 +6,30 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use super::Context;
-use crate::blockchain::Blockchain;
-use crate::db::{Key, KvStore, Value};
-use crate::services::{rpc_services::test_config, ContextKvStore, ContextSigner};
-use crate::types::network::Network;
-use alloy::consensus::{Transaction, TxEip1559, TypedTransaction};
-use alloy::hex::ToHexExt;
-use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
-use alloy::primitives::{Address, Bytes, FixedBytes, Uint, B256, U256};
-use alloy::rpc::types::{AccessList, AccessListItem};
-use std::result;
-
-use crate::types::{
-    BincodableOwshenTransaction, Burn, CustomTx, CustomTxMsg, IncludedTransaction,
-    OwshenTransaction, Token,
-};
+use crate::blockchain::{logs::LogEntry, tx::effective_gas_price, Blockchain};
+use crate::services::{ContextKvStore, ContextSigner};
+use alloy::consensus::Transaction;
+use alloy::primitives::{Bloom, FixedBytes, TxKind};
+
+use crate::types::OwshenTransaction;
+
+/// A log as the RPC reports it
+pub fn log_json(entry: &LogEntry) -> serde_json::Value {
+    json!({
+        "address": entry.log.address.to_string(),
+        "topics": entry.log.topics,
+        "data": format!("0x{}", alloy::hex::encode(&entry.log.data)),
+        "blockHash": format!("0x{:x}", entry.block_hash),
+        "blockNumber": format!("0x{:x}", entry.block_number),
+        "transactionHash": format!("0x{:x}", entry.tx_hash),
+        "transactionIndex": format!("0x{:x}", entry.transaction_index),
+        "logIndex": format!("0x{:x}", entry.log_index),
+        "removed": false,
+    })
+}
 
 pub async fn eth_get_transaction_receipt<S: ContextSigner, K: ContextKvStore>(
-    ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     params: Params<'static>,
 ) -> Result<String> {
     let params: Vec<String> = params.parse().unwrap_or_default();
@@ -34,32 +39,43 @@ pub async fn eth_get_transaction_receipt<S: ContextSigner, K: ContextKvStore>(
         Err(_) => return Err(anyhow::Error::msg("Invalid transaction hash")),
     };
 
-    let tx = ctx
-        .lock()
-        .await
-        .chain
-        .get_transaction_by_hash(tx_hash)
-        .unwrap();
-
-    let owshen_tx: OwshenTransaction = tx.tx.try_into()?;
-
-    let (gas_used, effective_gas_price, sender, to, contract_address) = match owshen_tx {
-        OwshenTransaction::Eth(tx) => {
-            let gas_used = tx.gas_limit();
-            let effective_gas_price = tx.as_eip1559().unwrap().tx().effective_gas_price(None);
-            let sender = tx.recover_signer()?.to_string();
-            let to = match tx.to() {
-                alloy::primitives::TxKind::Create => serde_json::Value::Null,
-                alloy::primitives::TxKind::Call(address) => {
-                    serde_json::Value::String(address.to_string())
-                }
+    let ctx = ctx.lock().await;
+    let tx = ctx.chain.get_transaction_by_hash(tx_hash)?;
+    let block = ctx.chain.get_block(tx.block_number)?;
+    let receipt = ctx.chain.get_receipt(tx_hash)?;
+
+    // Logs of the block are numbered across its transactions
+    let first_log_index: usize = ctx.chain.get_block_receipts(tx.block_number)?
+        [..tx.transaction_index]
+        .iter()
+        .map(|r| r.logs.len())
+        .sum();
+    let mut logs_bloom = Bloom::default();
+    for log in receipt.logs.iter() {
+        logs_bloom.accrue_raw_log(log.address, &log.topics);
+    }
+
+    let owshen_tx: OwshenTransaction = tx.tx.clone().try_into()?;
+    let sender = owshen_tx.signer()?;
+    // Custom transactions carry no gas price and have no recipient
+    let (tx_type, effective_gas_price, to, contract_address) = match &owshen_tx {
+        OwshenTransaction::Eth(eth_tx) => {
+            let (to, contract_address) = match eth_tx.to() {
+                TxKind::Create if receipt.success => (
+                    serde_json::Value::Null,
+                    json!(sender.create(eth_tx.nonce()).to_string()),
+                ),
+                TxKind::Create => (serde_json::Value::Null, serde_json::Value::Null),
+                TxKind::Call(address) => (json!(address.to_string()), serde_json::Value::Null),
             };
-            let contract_address = serde_json::Value::Null;
-            (gas_used, effective_gas_price, sender, to, contract_address)
-        }
-        OwshenTransaction::Custom(_) => {
-            return Err(anyhow::Error::msg("Not an Ethereum transaction!"))
+            (
+                eth_tx.ty(),
+                effective_gas_price(eth_tx, block.base_fee_per_gas),
+                to,
+                contract_address,
+            )
         }
+        OwshenTransaction::Custom(_) => (0, 0, serde_json::Value::Null, serde_json::Value::Null),
     };
 
     let receipt_json = json!({
@@ -67,95 +83,213 @@ pub async fn eth_get_transaction_receipt<S: ContextSigner, K: ContextKvStore>(
         "transactionIndex": format!("0x{:x}", tx.transaction_index),
         "blockHash": format!("0x{:x}", tx.block_hash),
         "blockNumber": format!("0x{:x}", tx.block_number),
-        "gasUsed": format!("0x{:x}", gas_used),
+        "gasUsed": format!("0x{:x}", receipt.gas_used),
         "effectiveGasPrice": format!("0x{:x}", effective_gas_price),
-        "from": sender,
+        "from": sender.to_string(),
         "to": to,
         "contractAddress": contract_address,
-        "logs": [],
-        "cumulativeGasUsed": "0x1b4",
-        "status": "0x1",
-        "logsBloom": "0x".to_string() + &"0".repeat(512),
-        "type": "0x2",
+        "logs": receipt
+            .logs
+            .iter()
+            .enumerate()
+            .map(|(i, log)| log_json(&LogEntry {
+                log: log.clone(),
+                block_number: tx.block_number,
+                block_hash: tx.block_hash,
+                tx_hash,
+                transaction_index: tx.transaction_index,
+                log_index: first_log_index + i,
+            }))
+            .collect::<Vec<_>>(),
+        "cumulativeGasUsed": format!("0x{:x}", receipt.cumulative_gas_used),
+        "status": if receipt.success { "0x1" } else { "0x0" },
+        "logsBloom": logs_bloom,
+        "type": format!("0x{:x}", tx_type),
     })
     .to_string();
 
     Ok(receipt_json)
 }
 
-#[tokio::test]
-async fn test_eth_get_transaction_receipt() {
-    let _ctx = test_config().await;
-
-    let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
-    let msg_sender: Address =
-        <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
-
-    let tx = TxEip1559 {
-        nonce: 0,
-        gas_limit: 21_000,
-        to: alloy::primitives::TxKind::Call(Address::from([6; 20])),
-        value: Uint::<256, 4>::from(0),
-        input: Bytes::from("hello"),
-        chain_id: _ctx.lock().await.chain.config().chain_id,
-        max_priority_fee_per_gas: 3_000_000,
-        max_fee_per_gas: 300_000_000,
-        access_list: AccessList(vec![AccessListItem {
-            address: Address::ZERO,
-            storage_keys: vec![B256::ZERO],
-        }]),
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::gas::INITIAL_BASE_FEE;
+    use crate::blockchain::TransactionQueue;
+    use crate::db::{Key, KvStore, Value};
+    use crate::services::rpc_services::test_config;
+
+    use crate::types::Token;
+    use alloy::consensus::{TxEip1559, TypedTransaction};
+
+    use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+    use alloy::primitives::{Address, Bytes, Uint, U256};
+    use alloy::rpc::types::AccessList;
+    use alloy::signers::local::PrivateKeySigner;
+
+    #[tokio::test]
+    async fn test_eth_get_transaction_receipt() {
+        let _ctx = test_config().await;
+
+        let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+        let msg_sender: Address =
+            <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+
+        let _ = _ctx.lock().await.chain.db.put(
+            Key::Balance(msg_sender, Token::Native),
+            Some(Value::U256(U256::from(1_000_000_000_000_000u64))),
+        );
+
+        let tx = TxEip1559 {
+            nonce: 0,
+            gas_limit: 21_000,
+            to: alloy::primitives::TxKind::Call(Address::from([6; 20])),
+            value: Uint::<256, 4>::from(1),
+            input: Bytes::new(),
+            chain_id: _ctx.lock().await.chain.config().chain_id,
+            max_priority_fee_per_gas: 3_000_000,
+            max_fee_per_gas: 2_000_000_000,
+            access_list: AccessList::default(),
+        };
+
+        let typed_tx = TypedTransaction::Eip1559(tx);
+
+        let signed_tx =
+            <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(&wallet, typed_tx)
+                .await
+                .unwrap();
 
-    let typed_tx = TypedTransaction::Eip1559(tx);
+        let eth_tx = OwshenTransaction::Eth(signed_tx.clone());
 
-    let signed_tx =
-        <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(&wallet, typed_tx)
+        let tx_hash = eth_tx.hash().unwrap();
+
+        let included_tx = {
+            let mut ctx = _ctx.lock().await;
+            ctx.tx_queue.enqueue(eth_tx);
+            let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+            let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+            ctx.chain.push_block(blk).unwrap();
+            ctx.chain.get_transaction_by_hash(tx_hash).unwrap()
+        };
+
+        let tx_hash_str = tx_hash.to_string();
+        let j = json!([tx_hash_str, "latest"]).to_string();
+        let hash_static: &'static str = Box::leak(j.into_boxed_str());
+        let params = Params::new(Some(hash_static));
+
+        let result = eth_get_transaction_receipt(_ctx, params).await;
+
+        assert!(result.is_ok());
+
+        let receipt_json: serde_json::Value =
+            serde_json::from_str(result.as_ref().unwrap()).unwrap();
+
+        let expected_json = json!({
+            "transactionHash": tx_hash_str,
+            "transactionIndex":  format!("0x{:x}",included_tx.transaction_index),
+            "blockHash": format!("0x{:x}", included_tx.block_hash),
+            "blockNumber": format!("0x{:x}", included_tx.block_number),
+            "gasUsed":  format!("0x{:x}",21000),
+            "effectiveGasPrice": format!("0x{:x}", INITIAL_BASE_FEE + 3_000_000),
+            "from": msg_sender.to_string(),
+            "to": Address::from([6; 20]).to_string(),
+            "contractAddress": serde_json::Value::Null,
+            "logs": [],
+            "cumulativeGasUsed": format!("0x{:x}",21000),
+            "status": "0x1",
+            "logsBloom": "0x".to_string() + &"0".repeat(512),
+            "type": "0x2",
+        });
+
+        assert_eq!(receipt_json, expected_json);
+    }
+
+    #[tokio::test]
+    async fn test_eth_get_transaction_receipt_logs_and_failure() {
+        let _ctx = test_config().await;
+
+        let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+        let msg_sender: Address =
+            <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+        let token_contract = Address::from([6; 20]);
+        let spender = Address::from([7; 20]);
+
+        let _ = _ctx.lock().await.chain.db.put(
+            Key::Balance(msg_sender, Token::Native),
+            Some(Value::U256(U256::from(1_000_000_000_000_000u64))),
+        );
+
+        let chain_id = _ctx.lock().await.chain.config().chain_id;
+        let mut tx_hashes = Vec::new();
+        // An approval followed by a transfer of tokens the sender doesn't have
+        for (nonce, selector) in [[9, 94, 167, 179], [169, 5, 156, 187]].iter().enumerate() {
+            let mut data = selector.to_vec();
+            data.extend_from_slice(spender.into_word().as_slice());
+            data.extend_from_slice(&U256::from(10).to_be_bytes::<32>());
+            let tx = TxEip1559 {
+                nonce: nonce as u64,
+                gas_limit: 100_000,
+                to: TxKind::Call(token_contract),
+                value: U256::ZERO,
+                input: Bytes::from(data),
+                chain_id,
+                max_priority_fee_per_gas: 3_000_000,
+                max_fee_per_gas: 2_000_000_000,
+                access_list: AccessList::default(),
+            };
+            let signed_tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+                &wallet,
+                TypedTransaction::Eip1559(tx),
+            )
             .await
             .unwrap();
+            let eth_tx = OwshenTransaction::Eth(signed_tx);
+            tx_hashes.push(eth_tx.hash().unwrap());
+            _ctx.lock().await.tx_queue.enqueue(eth_tx);
+        }
 
-    let eth_tx = OwshenTransaction::Eth(signed_tx.clone());
+        {
+            let mut ctx = _ctx.lock().await;
+            let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+            let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+            assert_eq!(blk.txs.len(), 2);
+            ctx.chain.push_block(blk).unwrap();
+        }
 
-    let tx_hash = eth_tx.hash().unwrap();
+        let receipt = |tx_hash: FixedBytes<32>| {
+            let j = json!([tx_hash.to_string()]).to_string();
+            let params_static: &'static str = Box::leak(j.into_boxed_str());
+            let ctx = _ctx.clone();
+            async move {
+                let result = eth_get_transaction_receipt(ctx, Params::new(Some(params_static)))
+                    .await
+                    .unwrap();
+                serde_json::from_str::<serde_json::Value>(&result).unwrap()
+            }
+        };
 
-    let included_tx: IncludedTransaction = IncludedTransaction {
-        tx: eth_tx.try_into().unwrap(),
-        block_hash: FixedBytes::from([0u8; 32]),
-        block_number: 4321,
-        transaction_index: 1,
-    };
+        let approve = receipt(tx_hashes[0]).await;
+        assert_eq!(approve["status"], "0x1");
+        let logs = approve["logs"].as_array().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0]["address"], token_contract.to_string());
+        assert_eq!(logs[0]["logIndex"], "0x0");
+        assert_eq!(logs[0]["topics"][1], json!(msg_sender.into_word()));
+        assert_eq!(logs[0]["topics"][2], json!(spender.into_word()));
+        let bloom: Bloom = serde_json::from_value(approve["logsBloom"].clone()).unwrap();
+        assert!(bloom.contains_input(alloy::primitives::BloomInput::Raw(
+            token_contract.as_slice()
+        )));
 
-    let _ = _ctx.lock().await.chain.db.put(
-        Key::TransactionHash(tx_hash),
-        Some(Value::Transaction(included_tx.clone())),
-    );
-
-    let tx_hash_str = tx_hash.to_string();
-    let j = json!([tx_hash_str, "latest"]).to_string();
-    let hash_static: &'static str = Box::leak(j.into_boxed_str());
-    let params = Params::new(Some(hash_static));
-
-    let result = eth_get_transaction_receipt(_ctx.into(), params).await;
-
-    assert!(result.is_ok());
-
-    let receipt_json: serde_json::Value = serde_json::from_str(result.as_ref().unwrap()).unwrap();
-
-    let expected_json = json!({
-        "transactionHash": tx_hash_str,
-        "transactionIndex":  format!("0x{:x}",included_tx.transaction_index),
-        "blockHash": format!("0x{:x}", included_tx.block_hash),
-        "blockNumber": format!("0x{:x}", included_tx.block_number),
-        "gasUsed":  format!("0x{:x}",21000),
-        "effectiveGasPrice": format!("0x{:x}",300000000),
-        "from": msg_sender.to_string(),
-        "to": Address::from([6; 20]).to_string(),
-        "contractAddress": serde_json::Value::Null,
-        "logs": [],
-        "cumulativeGasUsed": "0x1b4",
-        "status": "0x1",
-        "logsBloom": "0x".to_string() + &"0".repeat(512),
-        "type": "0x2",
-    });
-
-    assert_eq!(receipt_json, expected_json);
+        // The failed transfer is still part of the block and pays for its gas
+        let transfer = receipt(tx_hashes[1]).await;
+        assert_eq!(transfer["status"], "0x0");
+        assert_eq!(transfer["logs"], json!([]));
+        assert_eq!(transfer["transactionIndex"], "0x1");
+        assert_ne!(transfer["gasUsed"], "0x0");
+        assert_eq!(
+            _ctx.lock().await.chain.get_eth_nonce(msg_sender).unwrap(),
+            U256::from(2)
+        );
+    }
 }
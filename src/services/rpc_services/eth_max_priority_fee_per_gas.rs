@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use jsonrpsee::types::Params;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::gas::{self};
+use crate::services::{ContextKvStore, ContextSigner};
+
+pub async fn eth_max_priority_fee_per_gas<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    _params: Params<'static>,
+) -> Result<String> {
+    let tip = gas::suggest_priority_fee(&ctx.lock().await.chain)?;
+
+    Ok(format!("0x{:x}", tip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::gas::DEFAULT_PRIORITY_FEE;
+    use crate::services::rpc_services::test_config;
+
+    #[tokio::test]
+    async fn test_eth_max_priority_fee_per_gas() {
+        let _ctx = test_config().await;
+        let result = eth_max_priority_fee_per_gas(_ctx, Params::new(None))
+            .await
+            .unwrap();
+        assert_eq!(result, format!("0x{:x}", DEFAULT_PRIORITY_FEE));
+    }
+}
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use jsonrpsee::types::Params;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::Blockchain;
+use crate::services::{
+    filters::{FilterKind, LogQuery},
+    ContextKvStore, ContextSigner,
+};
+
+/// Installs a log filter, `eth_getFilterChanges` then reports the matching
+/// logs of the blocks included after this call
+pub async fn eth_new_filter<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+) -> Result<String> {
+    let params: Vec<serde_json::Value> = params.parse()?;
+    let query = LogQuery::parse(params.first().ok_or(anyhow!("Filter unavailable!"))?)?;
+
+    let mut ctx = ctx.lock().await;
+    let height = ctx.chain.get_height()?;
+    let id = ctx.filters.install(FilterKind::Logs(query), height);
+    Ok(format!("0x{:x}", id))
+}
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use jsonrpsee::types::Params;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::Blockchain;
+use crate::services::{filters::FilterKind, ContextKvStore, ContextSigner};
+
+pub async fn eth_new_pending_transaction_filter<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    _params: Params<'static>,
+) -> Result<String> {
+    let mut ctx = ctx.lock().await;
+    let height = ctx.chain.get_height()?;
+    let id = ctx.filters.install(FilterKind::PendingTransactions, height);
+    // Only transactions arriving from now on are reported
+    let queued = ctx
+        .tx_queue
+        .queue()
+        .iter()
+        .map(|tx| tx.hash())
+        .collect::<Result<_>>()?;
+    ctx.filters.poll(id)?.seen = queued;
+    Ok(format!("0x{:x}", id))
+}
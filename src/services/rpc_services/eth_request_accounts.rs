@@ -8,7 +8,7 @@ use super::Context;
 use crate::services::{ContextKvStore, ContextSigner};
 
 pub async fn eth_request_accounts<S: ContextSigner, K: ContextKvStore>(
-    _ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    _ctx: Arc<Mutex<Context<S, K>>>,
     _params: Params<'static>,
 ) -> Result<String> {
     //TODO: Handle the request accounts
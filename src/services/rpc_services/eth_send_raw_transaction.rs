@@ -1,78 +1,228 @@
 use std::sync::Arc;
 
-use alloy::consensus::{TxEip1559, TypedTransaction};
-use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
-use alloy::primitives::{Address, Bytes, Uint, B256};
 use alloy::rlp::Decodable;
-use alloy::rpc::types::{AccessList, AccessListItem};
-use alloy::signers::local::PrivateKeySigner;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use jsonrpsee::types::Params;
-use serde_json::json;
 use tokio::sync::Mutex;
 
 use super::Context;
-use crate::blockchain::Blockchain;
-use crate::services::{rpc_services::test_config, ContextKvStore, ContextSigner};
+use crate::blockchain::mempool::InvalidTransaction;
+use crate::services::{ContextKvStore, ContextSigner};
 use crate::types::OwshenTransaction;
 
+/// Validates a signed transaction and queues it, returning its hash
 pub async fn eth_send_raw_transaction<S: ContextSigner, K: ContextKvStore>(
-    ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     params: Params<'static>,
 ) -> Result<String> {
-    let params: Vec<String> = params.parse().unwrap_or_default();
-    let raw_tx = &params[0];
-    let raw_tx_bytes = hex::decode(raw_tx.trim_start_matches("0x"))?;
-    let mut hah = raw_tx_bytes.as_ref();
-    let tx = OwshenTransaction::Eth(alloy::consensus::TxEnvelope::decode(&mut hah).unwrap());
-    ctx.lock().await.tx_queue.enqueue(tx.clone());
-
-    Ok("Transaction sent successfully".to_string())
+    let params: Vec<String> = params.parse()?;
+    let raw_tx = params
+        .first()
+        .ok_or(anyhow!("Raw transaction unavailable!"))?;
+    let raw_tx_bytes = hex::decode(raw_tx.trim_start_matches("0x"))
+        .map_err(|e| InvalidTransaction(e.to_string()))?;
+    let envelope = alloy::consensus::TxEnvelope::decode(&mut raw_tx_bytes.as_ref())
+        .map_err(|e| InvalidTransaction(e.to_string()))?;
+    let tx = OwshenTransaction::Eth(envelope);
+
+    let mut ctx = ctx.lock().await;
+    ctx.check_writable()?;
+    let ctx = &mut *ctx;
+    let hash = ctx.tx_queue.add(&ctx.chain, tx)?;
+
+    Ok(format!("0x{:x}", hash))
 }
 
-#[tokio::test]
-async fn test_eth_send_raw_transaction() {
-    let _ctx = test_config().await;
-
-    let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
-    let tx = TxEip1559 {
-        nonce: 0,
-        gas_limit: 21_000,
-        to: alloy::primitives::TxKind::Call(Address::from([6; 20])),
-        value: Uint::<256, 4>::from(0),
-        input: Bytes::from("hello"),
-        chain_id: _ctx.lock().await.chain.config().chain_id,
-        max_priority_fee_per_gas: 3_000_000,
-        max_fee_per_gas: 300_000_000,
-        access_list: AccessList(vec![AccessListItem {
-            address: Address::ZERO,
-            storage_keys: vec![B256::ZERO],
-        }]),
-    };
-
-    let typed_tx = TypedTransaction::Eip1559(tx);
-    let signed_tx =
-        <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(&wallet, typed_tx)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::tx::NonceError;
+    use crate::blockchain::Blockchain;
+    use crate::services::rpc_services::{push_mint_block, test_config};
+    use crate::services::server::anyhow_to_rpc_error;
+    use alloy::consensus::{TxEip1559, TxEip4844, TxEip4844Variant, TypedTransaction};
+    use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+    use alloy::primitives::{Address, Bytes, Uint, B256};
+    use alloy::rpc::types::{AccessList, AccessListItem};
+    use alloy::signers::local::PrivateKeySigner;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_eth_send_raw_transaction() {
+        let _ctx = test_config().await;
+
+        let signer = PrivateKeySigner::random();
+        push_mint_block(&_ctx, signer.address(), 1_000_000_000_000_000_000).await;
+        let wallet: EthereumWallet = EthereumWallet::new(signer);
+        let tx = TxEip1559 {
+            nonce: 0,
+            gas_limit: 30_000,
+            to: alloy::primitives::TxKind::Call(Address::from([6; 20])),
+            value: Uint::<256, 4>::from(0),
+            input: Bytes::from("hello"),
+            chain_id: _ctx.lock().await.chain.config().chain_id,
+            max_priority_fee_per_gas: 3_000_000,
+            max_fee_per_gas: 3_000_000_000,
+            access_list: AccessList(vec![AccessListItem {
+                address: Address::ZERO,
+                storage_keys: vec![B256::ZERO],
+            }]),
+        };
+
+        let typed_tx = TypedTransaction::Eip1559(tx);
+        let signed_tx =
+            <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(&wallet, typed_tx)
+                .await
+                .unwrap();
+
+        let raw_tx_bytes = alloy::rlp::encode(&signed_tx);
+        let raw_tx = hex::encode(raw_tx_bytes);
+
+        let j = json!([format!("0x{}", raw_tx)]);
+        let raw_tx_static: &'static str = Box::leak(j.to_string().into_boxed_str());
+        let params = Params::new(Some(raw_tx_static));
+
+        let result = eth_send_raw_transaction(_ctx.clone(), params).await;
+
+        assert_eq!(result.unwrap(), format!("0x{:x}", signed_tx.tx_hash()));
+
+        let ctx = _ctx.lock().await;
+        let tx_queue = ctx.tx_queue.queue();
+        assert_eq!(tx_queue.len(), 1);
+        let queued_tx = tx_queue[0].clone();
+        let eth_tx: OwshenTransaction = OwshenTransaction::Eth(signed_tx);
+
+        assert_eq!(queued_tx, eth_tx);
+    }
+
+    #[tokio::test]
+    async fn test_eth_send_raw_transaction_bad_nonce() {
+        let _ctx = test_config().await;
+
+        let signer = PrivateKeySigner::random();
+        let address = signer.address();
+        push_mint_block(&_ctx, address, 1_000_000_000_000_000_000).await;
+        let wallet: EthereumWallet = EthereumWallet::new(signer);
+        let chain_id = _ctx.lock().await.chain.config().chain_id;
+        let rpc_error = |err| {
+            let err = anyhow_to_rpc_error(err);
+            (err.code(), err.message().to_string())
+        };
+        let raw_tx_params = |signed_tx| {
+            let raw_tx = hex::encode(alloy::rlp::encode(&signed_tx));
+            let j = json!([format!("0x{}", raw_tx)]);
+            let raw_tx_static: &'static str = Box::leak(j.to_string().into_boxed_str());
+            Params::new(Some(raw_tx_static))
+        };
+
+        let mut signed_txs = Vec::new();
+        for (value, nonce) in [(0, 0), (1, 0), (1, 2)] {
+            let tx = TxEip1559 {
+                nonce,
+                gas_limit: 21_000,
+                to: alloy::primitives::TxKind::Call(Address::from([6; 20])),
+                value: Uint::<256, 4>::from(value),
+                input: Bytes::new(),
+                chain_id,
+                max_priority_fee_per_gas: 3_000_000,
+                max_fee_per_gas: 3_000_000_000,
+                access_list: AccessList::default(),
+            };
+            signed_txs.push(
+                <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+                    &wallet,
+                    TypedTransaction::Eip1559(tx),
+                )
+                .await
+                .unwrap(),
+            );
+        }
+
+        let result =
+            eth_send_raw_transaction(_ctx.clone(), raw_tx_params(signed_txs[0].clone())).await;
+        assert!(result.is_ok());
+        let err = eth_send_raw_transaction(_ctx.clone(), raw_tx_params(signed_txs[0].clone()))
             .await
-            .unwrap();
+            .unwrap_err();
+        assert_eq!(
+            rpc_error(err),
+            (-32000, "Transaction already known!".to_string())
+        );
 
-    let raw_tx_bytes = alloy::rlp::encode(&signed_tx);
-    let raw_tx = hex::encode(raw_tx_bytes);
+        // Once included, the nonce can't be used again
+        {
+            let mut ctx = _ctx.lock().await;
+            let ctx = &mut *ctx;
+            let blk = ctx.chain.draft_block(&mut ctx.tx_queue, 0).unwrap();
+            assert_eq!(blk.txs.len(), 1);
+            ctx.chain.push_block(blk).unwrap();
+        }
+        let err = eth_send_raw_transaction(_ctx.clone(), raw_tx_params(signed_txs[1].clone()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NonceError>(),
+            Some(NonceError::TooLow { .. })
+        ));
+        assert_eq!(
+            rpc_error(err),
+            (
+                -32000,
+                format!("nonce too low: address {}, tx: 0 state: 1", address)
+            )
+        );
 
-    let j = json!([format!("0x{}", raw_tx)]);
-    let raw_tx_static: &'static str = Box::leak(j.to_string().into_boxed_str());
-    let params = Params::new(Some(raw_tx_static));
+        let err = eth_send_raw_transaction(_ctx.clone(), raw_tx_params(signed_txs[2].clone()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NonceError>(),
+            Some(NonceError::TooHigh { .. })
+        ));
+        assert_eq!(
+            rpc_error(err),
+            (
+                -32000,
+                format!("nonce too high: address {}, tx: 2 state: 1", address)
+            )
+        );
 
-    let result = eth_send_raw_transaction(_ctx.clone().into(), params).await;
+        assert!(_ctx.lock().await.tx_queue.queue().is_empty());
+    }
 
-    assert!(result.is_ok());
-    assert_eq!(result.unwrap(), "Transaction sent successfully");
+    #[tokio::test]
+    async fn test_eth_send_raw_transaction_blob() {
+        let _ctx = test_config().await;
 
-    let ctx = _ctx.lock().await;
-    let tx_queue = ctx.tx_queue.queue();
-    assert_eq!(tx_queue.len(), 1);
-    let queued_tx = tx_queue[0].clone();
-    let eth_tx: OwshenTransaction = OwshenTransaction::Eth(signed_tx);
+        let wallet: EthereumWallet = EthereumWallet::new(PrivateKeySigner::random());
+        let tx = TxEip4844 {
+            chain_id: _ctx.lock().await.chain.config().chain_id,
+            gas_limit: 21_000,
+            max_fee_per_gas: 3_000_000_000,
+            max_fee_per_blob_gas: 1,
+            to: Address::from([6; 20]),
+            blob_versioned_hashes: vec![B256::ZERO],
+            ..Default::default()
+        };
+        let signed_tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+            &wallet,
+            TypedTransaction::Eip4844(TxEip4844Variant::TxEip4844(tx)),
+        )
+        .await
+        .unwrap();
 
-    assert_eq!(queued_tx, eth_tx);
+        let raw_tx = hex::encode(alloy::rlp::encode(&signed_tx));
+        let j = json!([format!("0x{}", raw_tx)]);
+        let raw_tx_static: &'static str = Box::leak(j.to_string().into_boxed_str());
+        let err = eth_send_raw_transaction(_ctx.clone(), Params::new(Some(raw_tx_static)))
+            .await
+            .unwrap_err();
+        let err = anyhow_to_rpc_error(err);
+        assert_eq!(err.code(), -32000);
+        assert_eq!(
+            err.message(),
+            "Blob transactions (EIP-4844) are not supported!"
+        );
+        assert!(_ctx.lock().await.tx_queue.queue().is_empty());
+    }
 }
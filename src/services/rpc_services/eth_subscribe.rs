@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use alloy::primitives::Bloom;
+use anyhow::{anyhow, Result};
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::types::{ErrorCode, ErrorObject, Params};
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
+use serde_json::json;
+use tokio::sync::{broadcast::error::RecvError, Mutex};
+
+use super::Context;
+use crate::blockchain::{
+    events::ChainEvent, gas::BLOCK_GAS_LIMIT, logs::LogEntry, logs::LogFilter,
+};
+use crate::services::{filters::LogQuery, ContextKvStore, ContextSigner};
+use crate::types::Block;
+
+enum Subscription {
+    NewHeads,
+    Logs(LogFilter),
+    NewPendingTransactions,
+}
+
+impl Subscription {
+    fn parse(params: Params<'static>) -> Result<Self> {
+        let params: Vec<serde_json::Value> = params.parse()?;
+        match params.first().and_then(|kind| kind.as_str()) {
+            Some("newHeads") => Ok(Subscription::NewHeads),
+            Some("logs") => {
+                // Only logs of new blocks are sent, the block range is ignored
+                let query = match params.get(1) {
+                    Some(filter) => LogQuery::parse(filter)?,
+                    None => LogQuery::parse(&json!({}))?,
+                };
+                Ok(Subscription::Logs(LogFilter {
+                    addresses: query.addresses,
+                    topics: query.topics,
+                    ..Default::default()
+                }))
+            }
+            Some("newPendingTransactions") => Ok(Subscription::NewPendingTransactions),
+            _ => Err(anyhow!("Unsupported subscription!")),
+        }
+    }
+
+    fn notifications(&self, event: &ChainEvent) -> Result<Vec<serde_json::Value>> {
+        Ok(match (self, event) {
+            (Subscription::NewHeads, ChainEvent::Block { block, logs }) => {
+                vec![header_json(block, logs)?]
+            }
+            (Subscription::Logs(filter), ChainEvent::Block { logs, .. }) => logs
+                .iter()
+                .filter(|entry| filter.matches(&entry.log))
+                .map(super::log_json)
+                .collect(),
+            (Subscription::NewPendingTransactions, ChainEvent::PendingTransaction(hash)) => {
+                vec![json!(format!("0x{:x}", hash))]
+            }
+            _ => Vec::new(),
+        })
+    }
+}
+
+/// A block header as `newHeads` reports it
+pub fn header_json(block: &Block, logs: &[LogEntry]) -> Result<serde_json::Value> {
+    let mut logs_bloom = Bloom::default();
+    for entry in logs {
+        logs_bloom.accrue_raw_log(entry.log.address, &entry.log.topics);
+    }
+    Ok(json!({
+        "number": format!("0x{:x}", block.index),
+        "hash": format!("0x{:x}", block.hash()?),
+        "parentHash": format!("0x{:x}", block.prev_hash.unwrap_or_default()),
+        "timestamp": format!("0x{:x}", block.timestamp),
+        "baseFeePerGas": format!("0x{:x}", block.base_fee_per_gas),
+        "gasUsed": format!("0x{:x}", block.gas_used),
+        "gasLimit": format!("0x{:x}", BLOCK_GAS_LIMIT),
+        "stateRoot": format!("0x{:x}", block.state_root),
+        "transactionsRoot": format!("0x{:x}", block.transactions_root),
+        "receiptsRoot": format!("0x{:x}", block.receipts_root),
+        "logsBloom": logs_bloom,
+    }))
+}
+
+/// Streams the chain events the subscriber asked for until it unsubscribes or
+/// the connection closes
+pub async fn eth_subscribe<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+    pending: PendingSubscriptionSink,
+) -> SubscriptionResult {
+    let subscription = match Subscription::parse(params) {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            pending
+                .reject(ErrorObject::owned(
+                    ErrorCode::InvalidParams.code(),
+                    e.to_string(),
+                    None::<()>,
+                ))
+                .await;
+            return Ok(());
+        }
+    };
+    let mut events = ctx.lock().await.events.subscribe();
+    let sink = pending.accept().await?;
+
+    loop {
+        tokio::select! {
+            _ = sink.closed() => break,
+            event = events.recv() => match event {
+                Ok(event) => {
+                    for notification in subscription.notifications(&event)? {
+                        sink.send(SubscriptionMessage::from_json(&notification)?).await?;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    log::warn!("Subscriber missed {} events!", missed);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::Blockchain;
+    use crate::services::rpc_services::{push_mint_block, test_config};
+    use crate::types::{CustomTx, CustomTxMsg, Mint, Token};
+    use alloy::primitives::{keccak256, Address, U256};
+    use alloy::signers::local::PrivateKeySigner;
+    use jsonrpsee::RpcModule;
+
+    #[tokio::test]
+    async fn test_eth_subscribe() {
+        let ctx = test_config().await;
+        let mut module = RpcModule::new(ctx.clone());
+        module
+            .register_subscription(
+                "eth_subscribe",
+                "eth_subscription",
+                "eth_unsubscribe",
+                |params, pending, ctx, _| eth_subscribe((*ctx).clone(), params, pending),
+            )
+            .unwrap();
+
+        let mut heads = module
+            .subscribe_unbounded("eth_subscribe", jsonrpsee::rpc_params!["newHeads"])
+            .await
+            .unwrap();
+        let alice = Address::from([1; 20]);
+        let mut logs = module
+            .subscribe_unbounded(
+                "eth_subscribe",
+                jsonrpsee::rpc_params!["logs", json!({ "topics": [null, alice.into_word()] })],
+            )
+            .await
+            .unwrap();
+        let mut pending = module
+            .subscribe_unbounded(
+                "eth_subscribe",
+                jsonrpsee::rpc_params!["newPendingTransactions"],
+            )
+            .await
+            .unwrap();
+        assert!(module
+            .subscribe_unbounded("eth_subscribe", jsonrpsee::rpc_params!["syncing"])
+            .await
+            .is_err());
+
+        let tx = CustomTx::create(
+            &mut PrivateKeySigner::random(),
+            ctx.lock().await.chain.config().chain_id,
+            CustomTxMsg::MintTx(Mint {
+                tx_hash: vec![7; 32],
+                user_tx_hash: "0x07".to_string(),
+                token: Token::Native,
+                amount: U256::from(1),
+                address: alice,
+                nonce: U256::from(0),
+                chain_id: 1,
+            }),
+        )
+        .await
+        .unwrap();
+        let tx_hash = tx.hash().unwrap();
+        ctx.lock().await.tx_queue.enqueue(tx);
+        let (hash, _) = pending.next::<String>().await.unwrap().unwrap();
+        assert_eq!(hash, format!("0x{:x}", tx_hash));
+
+        // Blocks are announced by whoever pushes them
+        let bob = Address::from([2; 20]);
+        push_mint_block(&ctx, bob, 10).await;
+        push_mint_block(&ctx, alice, 20).await;
+        for index in 0..2 {
+            let blk = ctx.lock().await.chain.get_block(index).unwrap();
+            ctx.lock().await.publish_block(&blk).unwrap();
+            let (head, _) = heads.next::<serde_json::Value>().await.unwrap().unwrap();
+            assert_eq!(head["number"], format!("0x{:x}", index));
+            assert_eq!(head["hash"], format!("0x{:x}", blk.hash().unwrap()));
+        }
+
+        // Only the mint to alice matches the log subscription
+        let (log, _) = logs.next::<serde_json::Value>().await.unwrap().unwrap();
+        assert_eq!(log["blockNumber"], "0x1");
+        assert_eq!(
+            log["topics"],
+            json!([keccak256("Mint(address,uint256)"), alice.into_word()])
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use alloy::primitives::U256;
+use anyhow::{anyhow, Result};
+use jsonrpsee::types::Params;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::services::{ContextKvStore, ContextSigner};
+
+pub async fn eth_uninstall_filter<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let params: Vec<U256> = params.parse()?;
+    let id = *params.first().ok_or(anyhow!("Filter id unavailable!"))?;
+    Ok(ctx.lock().await.filters.uninstall(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::{
+        eth_get_filter_changes, eth_new_block_filter, test_config,
+    };
+
+    #[tokio::test]
+    async fn test_eth_uninstall_filter() {
+        let ctx = test_config().await;
+        let id = eth_new_block_filter(ctx.clone(), Params::new(None))
+            .await
+            .unwrap();
+        let id: &'static str = Box::leak(serde_json::json!([id]).to_string().into_boxed_str());
+
+        assert!(eth_uninstall_filter(ctx.clone(), Params::new(Some(id)))
+            .await
+            .unwrap());
+        assert!(!eth_uninstall_filter(ctx.clone(), Params::new(Some(id)))
+            .await
+            .unwrap());
+        assert!(eth_get_filter_changes(ctx.clone(), Params::new(Some(id)))
+            .await
+            .is_err());
+    }
+}
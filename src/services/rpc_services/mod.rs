@@ -30,57 +30,148 @@ mod eth_fee_history;
 pub use eth_fee_history::*;
 mod eth_get_transaction_by_hash;
 pub use eth_get_transaction_by_hash::*;
+mod eth_max_priority_fee_per_gas;
+pub use eth_max_priority_fee_per_gas::*;
+mod owshen_get_proof;
+pub use owshen_get_proof::*;
+mod owshen_get_transaction_proof;
+pub use owshen_get_transaction_proof::*;
+mod owshen_get_raw_blocks;
+pub use owshen_get_raw_blocks::*;
+mod eth_get_logs;
+pub use eth_get_logs::*;
+mod eth_new_filter;
+pub use eth_new_filter::*;
+mod eth_new_block_filter;
+pub use eth_new_block_filter::*;
+mod eth_new_pending_transaction_filter;
+pub use eth_new_pending_transaction_filter::*;
+mod eth_get_filter_changes;
+pub use eth_get_filter_changes::*;
+mod eth_get_filter_logs;
+pub use eth_get_filter_logs::*;
+mod eth_uninstall_filter;
+pub use eth_uninstall_filter::*;
+mod eth_subscribe;
+pub use eth_subscribe::*;
+mod txpool_content;
+pub use txpool_content::*;
+mod txpool_inspect;
+pub use txpool_inspect::*;
+mod txpool_status;
+pub use txpool_status::*;
 mod todo;
 pub use todo::*;
 
+/// Whether a block tag asks for the pending block
+pub fn is_pending(tag: Option<&str>) -> bool {
+    tag == Some("pending")
+}
 
-use alloy::{
-    primitives::{utils::parse_units, Address, FixedBytes, Signature, U256},
-    signers::{local::PrivateKeySigner, Signer},
-};
-use axum::{
-    body::{Body, HttpBody},
-    http::{self, Request, StatusCode},
-    routing::get,
-    Json, Router,
-};
-use serde::de::Expected;
-use serde_json::json;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tower::{Service, ServiceExt};
+#[cfg(test)]
+use test_utils::*;
 
-use crate::{
-    blockchain::{
-        tx::owshen_airdrop::babyjubjub::PrivateKey, Blockchain, Config, Owshenchain,
-        TransactionQueue,
-    },
-    config,
-    db::{DiskKvStore, Key, KvStore, RamKvStore, Value},
-    genesis::GENESIS,
-    safe_signer::{self, SafeSigner},
-    services::{api_services::api_routes},
-    types::{
-        network::Network, Burn, CustomTx, CustomTxMsg, IncludedTransaction, OwshenTransaction,
-        Token,
-    },
-};
+#[cfg(test)]
+mod test_utils {
+    use super::*;
 
-async fn test_config() -> Arc<tokio::sync::Mutex<Context<SafeSigner, RamKvStore>>> {
-    let conf = Config {
-        chain_id: 1387,
-        owner: None,
-        genesis: GENESIS.clone(),
-        owshen: config::OWSHEN_CONTRACT,
-        provider_address:  "http://127.0.0.1:8888".parse().expect("faild to parse"),
+    use alloy::{
+        primitives::{Address, FixedBytes, U256},
+        signers::local::PrivateKeySigner,
     };
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
 
-    let owner = SafeSigner::new(PrivateKeySigner::random());
-    return  Arc::new(Mutex::new(Context {
-        signer: owner.clone(),
-        exit: false,
-        tx_queue: TransactionQueue::new(),
-        chain: Owshenchain::new(conf, RamKvStore::new()),
-    }));
-}
+    use crate::{
+        blockchain::{events, Blockchain, Config, Owshenchain, TransactionQueue},
+        db::RamKvStore,
+        safe_signer::SafeSigner,
+        services::filters::Filters,
+        types::{CustomTx, CustomTxMsg, OwshenTransaction, Token},
+    };
+
+    pub async fn test_config() -> Arc<tokio::sync::Mutex<Context<SafeSigner, RamKvStore>>> {
+        let conf = Config::for_tests();
 
+        let owner = SafeSigner::new(PrivateKeySigner::random());
+        let events = events::channel();
+        Arc::new(Mutex::new(Context {
+            signer: owner.clone(),
+            exit: false,
+            read_only: false,
+            tx_queue: TransactionQueue::with_events(events.clone()),
+            filters: Filters::new(),
+            events,
+            chain: Owshenchain::new(conf, RamKvStore::new()),
+        }))
+    }
+
+    /// Includes a block minting native tokens to `to`, which emits a `Mint` log
+    pub async fn push_mint_block(
+        ctx: &Arc<Mutex<Context<SafeSigner, RamKvStore>>>,
+        to: Address,
+        amount: u64,
+    ) -> FixedBytes<32> {
+        let mut ctx = ctx.lock().await;
+        let chain_id = ctx.chain.config().chain_id;
+        let deposit_hash = rand::random::<[u8; 32]>();
+        let tx = CustomTx::create(
+            &mut PrivateKeySigner::random(),
+            chain_id,
+            CustomTxMsg::MintTx(crate::types::Mint {
+                tx_hash: deposit_hash.to_vec(),
+                user_tx_hash: format!("0x{}", hex::encode(deposit_hash)),
+                token: Token::Native,
+                amount: U256::from(amount),
+                address: to,
+                nonce: U256::from(0),
+                chain_id: 1,
+            }),
+        )
+        .await
+        .unwrap();
+        let tx_hash = tx.hash().unwrap();
+        let mut tx_queue = TransactionQueue::new();
+        tx_queue.enqueue(tx);
+        let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+        assert_eq!(blk.txs.len(), 1);
+        ctx.chain.push_block(blk).unwrap();
+        tx_hash
+    }
+
+    /// Queues a call from `signer` sending `value` wei, paying a 1 gwei tip
+    pub async fn queue_call(
+        ctx: &Arc<Mutex<Context<SafeSigner, RamKvStore>>>,
+        signer: &PrivateKeySigner,
+        nonce: u64,
+        to: Address,
+        value: u64,
+        input: Vec<u8>,
+    ) -> OwshenTransaction {
+        let mut ctx = ctx.lock().await;
+        let tx = alloy::consensus::TxEip1559 {
+            chain_id: ctx.chain.config().chain_id,
+            nonce,
+            gas_limit: 100_000,
+            to: alloy::primitives::TxKind::Call(to),
+            value: U256::from(value),
+            input: input.into(),
+            max_priority_fee_per_gas: 1_000_000_000,
+            max_fee_per_gas: 10_000_000_000,
+            ..Default::default()
+        };
+        let wallet = alloy::network::EthereumWallet::new(signer.clone());
+        let tx = OwshenTransaction::Eth(
+            <alloy::network::EthereumWallet as alloy::network::NetworkWallet<
+                alloy::network::Ethereum,
+            >>::sign_transaction(
+                &wallet, alloy::consensus::TypedTransaction::Eip1559(tx)
+            )
+            .await
+            .unwrap(),
+        );
+        let ctx = &mut *ctx;
+        ctx.tx_queue.add(&ctx.chain, tx.clone()).unwrap();
+        tx
+    }
+}
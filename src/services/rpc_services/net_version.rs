@@ -9,7 +9,7 @@ use super::Context;
 use crate::services::{ContextKvStore, ContextSigner};
 
 pub async fn net_version<S: ContextSigner, K: ContextKvStore>(
-    ctx: Arc<Arc<Mutex<Context<S, K>>>>,
+    ctx: Arc<Mutex<Context<S, K>>>,
     _params: Params<'static>,
 ) -> Result<String> {
     let chain_id = ctx.lock().await.chain.config().chain_id;
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use alloy::primitives::{keccak256, Address, U256};
+use anyhow::{anyhow, Result};
+use jsonrpsee::types::Params;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::Blockchain;
+use crate::db::{Key, Value};
+use crate::services::{ContextKvStore, ContextSigner};
+use crate::types::{Token, ERC20};
+
+fn stored_u256(value: &Option<Value>) -> Result<U256> {
+    match value {
+        Some(v) => v.as_u256(),
+        None => Ok(U256::ZERO),
+    }
+}
+
+/// Proofs of the entries of an address against the latest state root, laid
+/// out after EIP-1186 but not compatible with it. All entries share a single
+/// tree, so `storageHash` repeats the state root and the native balance and
+/// nonce are proven separately. ERC-20 balances of the token contracts listed
+/// in an optional fourth parameter are proven too.
+///
+/// Each proof is a `StateProof`. Bit `i` of `bitmap` is set when the sibling
+/// at level `i`, counting up from the leaf, is not an empty subtree, and
+/// `siblings` lists those non-empty siblings from the leaf up. The leaf sits at
+/// `keccak256` of the bincode-encoded key and holds `keccak256` of the
+/// bincode-encoded value, zero for a missing entry, every parent being
+/// `keccak256(left ++ right)`. `verify_state_proof` does the check.
+pub async fn owshen_get_proof<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+) -> Result<serde_json::Value> {
+    let params: Vec<serde_json::Value> = params.parse()?;
+    let address: Address = serde_json::from_value(
+        params
+            .first()
+            .ok_or(anyhow!("Address unavailable!"))?
+            .clone(),
+    )?;
+    let storage_keys: Vec<U256> = match params.get(1) {
+        Some(keys) => serde_json::from_value(keys.clone())?,
+        None => Vec::new(),
+    };
+    let tokens: Vec<Address> = match params.get(3) {
+        Some(tokens) => serde_json::from_value(tokens.clone())?,
+        None => Vec::new(),
+    };
+
+    let ctx = ctx.lock().await;
+    let chain = &ctx.chain;
+    let height = chain.get_height()? as u64;
+    // Only the nodes of the current tree are kept around
+    let is_latest = match params.get(2).and_then(|b| b.as_str()) {
+        None | Some("latest") => true,
+        Some(block) => match block.strip_prefix("0x") {
+            Some(hex) => height > 0 && u64::from_str_radix(hex, 16)? == height - 1,
+            None => false,
+        },
+    };
+    if !is_latest {
+        return Err(anyhow!("Proofs are only available for the latest block!"));
+    }
+
+    let state_root = chain.get_state_root()?;
+    let (_, account_proof) = chain.get_state_proof(Key::Balance(address, Token::Native))?;
+    let (_, nonce_proof) = chain.get_state_proof(Key::NonceEth(address))?;
+
+    let mut storage_proof = Vec::new();
+    for slot in storage_keys {
+        let (value, proof) = chain.get_state_proof(Key::ContractStorage(address, slot))?;
+        storage_proof.push(json!({
+            "key": format!("0x{:x}", slot),
+            "value": format!("0x{:x}", stored_u256(&value)?),
+            "proof": proof,
+        }));
+    }
+
+    let mut token_proof = Vec::new();
+    for token_address in tokens {
+        let token = Token::Erc20(ERC20 {
+            address: token_address,
+            decimals: chain.get_token_decimal(token_address)?,
+            symbol: chain.get_token_symbol(token_address)?,
+        });
+        let (_, proof) = chain.get_state_proof(Key::Balance(address, token.clone()))?;
+        token_proof.push(json!({
+            "token": token_address,
+            "balance": format!("0x{:x}", chain.get_balance(token, address)?),
+            "proof": proof,
+        }));
+    }
+
+    Ok(json!({
+        "address": address,
+        "balance": format!("0x{:x}", chain.get_balance(Token::Native, address)?),
+        "nonce": format!("0x{:x}", chain.get_eth_nonce(address)?),
+        "codeHash": keccak256(chain.get_code(address)?),
+        "storageHash": state_root,
+        "stateRoot": state_root,
+        "accountProof": account_proof,
+        "nonceProof": nonce_proof,
+        "storageProof": storage_proof,
+        "tokenProof": token_proof,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::state::{verify_state_proof, StateProof};
+    use crate::blockchain::TransactionQueue;
+    use crate::db::KvStore;
+    use crate::genesis::GENESIS;
+    use crate::services::rpc_services::test_config;
+    use crate::types::OwshenTransaction;
+    use alloy::consensus::{TxEip1559, TypedTransaction};
+    use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+    use alloy::primitives::{Bytes, FixedBytes, TxKind};
+    use alloy::signers::local::PrivateKeySigner;
+
+    #[tokio::test]
+    async fn test_owshen_get_proof() {
+        let _ctx = test_config().await;
+        let wallet = EthereumWallet::new(PrivateKeySigner::random());
+        let sender = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+        let chain_id = _ctx.lock().await.chain.config().chain_id;
+        let _ = _ctx.lock().await.chain.db.put(
+            Key::Balance(sender, Token::Native),
+            Some(Value::U256(U256::from(1_000_000_000_000_000u64))),
+        );
+
+        {
+            let mut ctx = _ctx.lock().await;
+            let tx = TxEip1559 {
+                nonce: 0,
+                gas_limit: 21_000,
+                to: TxKind::Call(Address::from([6; 20])),
+                value: U256::from(1),
+                input: Bytes::new(),
+                chain_id,
+                max_priority_fee_per_gas: 1_000,
+                max_fee_per_gas: 2_000_000_000,
+                access_list: Default::default(),
+            };
+            let tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+                &wallet,
+                TypedTransaction::Eip1559(tx),
+            )
+            .await
+            .unwrap();
+            ctx.tx_queue.enqueue(OwshenTransaction::Eth(tx));
+            let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+            let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+            ctx.chain.push_block(blk).unwrap();
+        }
+        let block = _ctx.lock().await.chain.get_block(0).unwrap();
+        let token = Address::from([7; 20]);
+
+        let j = json!([sender, ["0x0"], "latest", [token]]).to_string();
+        let params: &'static str = Box::leak(j.into_boxed_str());
+        let result = owshen_get_proof(_ctx.clone(), Params::new(Some(params)))
+            .await
+            .unwrap();
+        let root: FixedBytes<32> = serde_json::from_value(result["stateRoot"].clone()).unwrap();
+        assert_eq!(root, block.state_root);
+        let proof =
+            |v: &serde_json::Value| -> StateProof { serde_json::from_value(v.clone()).unwrap() };
+
+        let balance: U256 = result["balance"].as_str().unwrap().parse().unwrap();
+        assert!(verify_state_proof(
+            root,
+            &Key::Balance(sender, Token::Native),
+            Some(&Value::U256(balance)),
+            &proof(&result["accountProof"]),
+        )
+        .unwrap());
+        assert!(!verify_state_proof(
+            root,
+            &Key::Balance(sender, Token::Native),
+            Some(&Value::U256(balance + U256::from(1))),
+            &proof(&result["accountProof"]),
+        )
+        .unwrap());
+        assert_eq!(result["nonce"], "0x1");
+        assert!(verify_state_proof(
+            root,
+            &Key::NonceEth(sender),
+            Some(&Value::U256(U256::from(1))),
+            &proof(&result["nonceProof"]),
+        )
+        .unwrap());
+
+        // Entries that were never written are proven absent
+        assert_eq!(result["storageProof"][0]["value"], "0x0");
+        assert!(verify_state_proof(
+            root,
+            &Key::ContractStorage(sender, U256::ZERO),
+            None,
+            &proof(&result["storageProof"][0]["proof"]),
+        )
+        .unwrap());
+        let token = Token::Erc20(ERC20 {
+            address: token,
+            decimals: U256::ZERO,
+            symbol: "Unknown".to_string(),
+        });
+        assert_eq!(result["tokenProof"][0]["balance"], "0x0");
+        assert!(verify_state_proof(
+            root,
+            &Key::Balance(sender, token),
+            None,
+            &proof(&result["tokenProof"][0]["proof"]),
+        )
+        .unwrap());
+
+        // Genesis holders are committed to by the first block
+        let (holder, genesis_balance) = GENESIS.tokens[&Token::Native]
+            .iter()
+            .next()
+            .map(|(holder, balance)| (*holder, *balance))
+            .unwrap();
+        let j = json!([holder]).to_string();
+        let params: &'static str = Box::leak(j.into_boxed_str());
+        let result = owshen_get_proof(_ctx.clone(), Params::new(Some(params)))
+            .await
+            .unwrap();
+        assert_eq!(result["balance"], format!("0x{:x}", genesis_balance));
+        assert!(verify_state_proof(
+            root,
+            &Key::Balance(holder, Token::Native),
+            Some(&Value::U256(genesis_balance)),
+            &proof(&result["accountProof"]),
+        )
+        .unwrap());
+
+        let j = json!([sender, [], "0x5"]).to_string();
+        let params: &'static str = Box::leak(j.into_boxed_str());
+        assert!(owshen_get_proof(_ctx, Params::new(Some(params)))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use jsonrpsee::types::Params;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::Blockchain;
+use crate::services::{ContextKvStore, ContextSigner};
+
+/// Most blocks returned by a single `owshen_getRawBlocks` call
+pub const MAX_RAW_BLOCKS: usize = 100;
+
+fn parse_quantity(quantity: &str) -> Result<usize> {
+    let hex = quantity
+        .strip_prefix("0x")
+        .ok_or(anyhow!("Invalid quantity!"))?;
+    Ok(usize::from_str_radix(hex, 16)?)
+}
+
+/// Signed blocks starting at the given height, bincode encoded the way they
+/// are stored, so that followers can verify and push them as they are
+pub async fn owshen_get_raw_blocks<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+) -> Result<Vec<String>> {
+    let params: Vec<String> = params.parse()?;
+    let from = parse_quantity(params.first().ok_or(anyhow!("Block number unavailable!"))?)?;
+    let count = match params.get(1) {
+        Some(count) => parse_quantity(count)?.min(MAX_RAW_BLOCKS),
+        None => MAX_RAW_BLOCKS,
+    };
+
+    let ctx = ctx.lock().await;
+    let to = ctx.chain.get_height()?.min(from.saturating_add(count));
+    (from..to)
+        .map(|index| {
+            let block = ctx.chain.get_block(index)?;
+            Ok(format!("0x{}", hex::encode(bincode::serialize(&block)?)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::{push_mint_block, test_config};
+    use crate::types::Block;
+    use alloy::primitives::Address;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_owshen_get_raw_blocks() {
+        let ctx = test_config().await;
+        for amount in 1..=3 {
+            push_mint_block(&ctx, Address::from([1; 20]), amount).await;
+        }
+
+        let get_raw_blocks = |params: serde_json::Value| {
+            let params: &'static str = Box::leak(params.to_string().into_boxed_str());
+            owshen_get_raw_blocks(ctx.clone(), Params::new(Some(params)))
+        };
+
+        let raw_blocks = get_raw_blocks(json!(["0x1"])).await.unwrap();
+        assert_eq!(raw_blocks.len(), 2);
+        let block: Block =
+            bincode::deserialize(&hex::decode(raw_blocks[0].trim_start_matches("0x")).unwrap())
+                .unwrap();
+        assert_eq!(
+            block.hash().unwrap(),
+            ctx.lock().await.chain.get_block(1).unwrap().hash().unwrap()
+        );
+
+        assert_eq!(
+            get_raw_blocks(json!(["0x0", "0x1"])).await.unwrap().len(),
+            1
+        );
+        assert!(get_raw_blocks(json!(["0x3"])).await.unwrap().is_empty());
+        assert!(get_raw_blocks(json!(["3"])).await.is_err());
+    }
+}
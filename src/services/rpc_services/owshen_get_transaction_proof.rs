@@ -0,0 +1,102 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use alloy::primitives::FixedBytes;
+use anyhow::{anyhow, Result};
+use jsonrpsee::types::Params;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::blockchain::Blockchain;
+use crate::services::{ContextKvStore, ContextSigner};
+
+/// Merkle proof that a transaction and its receipt are included in a block
+pub async fn owshen_get_transaction_proof<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    params: Params<'static>,
+) -> Result<serde_json::Value> {
+    let params: Vec<String> = params.parse()?;
+    let tx_hash = FixedBytes::<32>::from_str(
+        params
+            .first()
+            .ok_or(anyhow!("Transaction hash unavailable!"))?,
+    )?;
+
+    let proof = ctx.lock().await.chain.get_inclusion_proof(tx_hash)?;
+
+    Ok(serde_json::to_value(proof)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::merkle::InclusionProof;
+    use crate::blockchain::TransactionQueue;
+    use crate::db::{Key, KvStore, Value};
+    use crate::services::rpc_services::test_config;
+    use crate::types::{OwshenTransaction, Token};
+    use alloy::consensus::{TxEip1559, TypedTransaction};
+    use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+    use alloy::primitives::{Address, Bytes, TxKind, U256};
+    use alloy::signers::local::PrivateKeySigner;
+
+    #[tokio::test]
+    async fn test_owshen_get_transaction_proof() {
+        let _ctx = test_config().await;
+        let wallet = EthereumWallet::new(PrivateKeySigner::random());
+        let sender = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+        let chain_id = _ctx.lock().await.chain.config().chain_id;
+        let _ = _ctx.lock().await.chain.db.put(
+            Key::Balance(sender, Token::Native),
+            Some(Value::U256(U256::from(1_000_000_000_000_000u64))),
+        );
+
+        let mut tx_hashes = Vec::new();
+        {
+            let mut ctx = _ctx.lock().await;
+            for nonce in 0..3 {
+                let tx = TxEip1559 {
+                    nonce,
+                    gas_limit: 21_000,
+                    to: TxKind::Call(Address::from([6; 20])),
+                    value: U256::from(1),
+                    input: Bytes::new(),
+                    chain_id,
+                    max_priority_fee_per_gas: 1_000,
+                    max_fee_per_gas: 2_000_000_000,
+                    access_list: Default::default(),
+                };
+                let tx = <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction(
+                    &wallet,
+                    TypedTransaction::Eip1559(tx),
+                )
+                .await
+                .unwrap();
+                let tx = OwshenTransaction::Eth(tx);
+                tx_hashes.push(tx.hash().unwrap());
+                ctx.tx_queue.enqueue(tx);
+            }
+            let mut tx_queue = std::mem::replace(&mut ctx.tx_queue, TransactionQueue::new());
+            let blk = ctx.chain.draft_block(&mut tx_queue, 0).unwrap();
+            ctx.chain.push_block(blk).unwrap();
+        }
+        let block = _ctx.lock().await.chain.get_block(0).unwrap();
+
+        let j = serde_json::json!([tx_hashes[2].to_string()]).to_string();
+        let params: &'static str = Box::leak(j.into_boxed_str());
+        let result = owshen_get_transaction_proof(_ctx.clone(), Params::new(Some(params)))
+            .await
+            .unwrap();
+        let proof: InclusionProof = serde_json::from_value(result).unwrap();
+        assert_eq!(proof.transaction_index, 2);
+        assert_eq!(proof.receipt.gas_used, 21_000);
+        assert_eq!(proof.receipt.cumulative_gas_used, 63_000);
+        assert!(proof.verify(tx_hashes[2], &block).unwrap());
+        assert!(!proof.verify(tx_hashes[1], &block).unwrap());
+
+        let params = Params::new(Some(
+            r#"["0x0000000000000000000000000000000000000000000000000000000000000001"]"#,
+        ));
+        assert!(owshen_get_transaction_proof(_ctx, params).await.is_err());
+    }
+}
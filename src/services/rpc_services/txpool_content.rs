@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use alloy::consensus::{Transaction, TxEnvelope};
+use alloy::primitives::Address;
+use anyhow::Result;
+use jsonrpsee::types::Params;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::services::{ContextKvStore, ContextSigner};
+
+/// A queued transaction as `txpool_content` reports it, the way
+/// `eth_getTransactionByHash` would before it is included
+pub fn pending_tx_json(from: Address, tx: &TxEnvelope) -> Result<serde_json::Value> {
+    let mut json = serde_json::to_value(tx)?;
+    let fields = json
+        .as_object_mut()
+        .ok_or(anyhow::anyhow!("Unexpected transaction encoding!"))?;
+    fields.insert("from".into(), from.to_string().into());
+    fields.insert("hash".into(), format!("0x{:x}", tx.tx_hash()).into());
+    fields.insert("blockHash".into(), serde_json::Value::Null);
+    fields.insert("blockNumber".into(), serde_json::Value::Null);
+    fields.insert("transactionIndex".into(), serde_json::Value::Null);
+    fields.insert("gas".into(), format!("0x{:x}", tx.gas_limit()).into());
+    fields.insert(
+        "gasPrice".into(),
+        format!("0x{:x}", tx.max_fee_per_gas()).into(),
+    );
+    fields.remove("gasLimit");
+    Ok(json)
+}
+
+/// Queued Ethereum transactions by sender and nonce
+pub async fn txpool_content<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    _params: Params<'static>,
+) -> Result<serde_json::Value> {
+    let mut pending = serde_json::Map::new();
+    for (sender, txs) in ctx.lock().await.tx_queue.eth_by_sender() {
+        let txs = txs
+            .iter()
+            .map(|(nonce, tx)| Ok((nonce.to_string(), pending_tx_json(sender, tx)?)))
+            .collect::<Result<serde_json::Map<_, _>>>()?;
+        pending.insert(sender.to_string(), txs.into());
+    }
+    Ok(json!({ "pending": pending, "queued": {} }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::{push_mint_block, queue_call, test_config};
+    use alloy::signers::local::PrivateKeySigner;
+
+    #[tokio::test]
+    async fn test_txpool_content() {
+        let ctx = test_config().await;
+        let signer = PrivateKeySigner::random();
+        push_mint_block(&ctx, signer.address(), 1_000_000_000_000_000_000).await;
+        let first = queue_call(&ctx, &signer, 0, Address::from([2; 20]), 1, vec![]).await;
+        queue_call(&ctx, &signer, 1, Address::from([2; 20]), 2, vec![]).await;
+
+        let content = txpool_content(ctx.clone(), Params::new(None))
+            .await
+            .unwrap();
+        let txs = &content["pending"][signer.address().to_string()];
+        assert_eq!(txs.as_object().unwrap().len(), 2);
+        assert_eq!(txs["0"]["hash"], format!("0x{:x}", first.hash().unwrap()));
+        assert_eq!(txs["0"]["blockHash"], serde_json::Value::Null);
+        assert_eq!(txs["1"]["value"], "0x2");
+        assert_eq!(txs["1"]["gas"], "0x186a0");
+        assert_eq!(content["queued"], json!({}));
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use alloy::consensus::Transaction;
+use alloy::primitives::TxKind;
+use anyhow::Result;
+use jsonrpsee::types::Params;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::services::{ContextKvStore, ContextSigner};
+
+/// One line summaries of the queued Ethereum transactions, formatted like Geth
+pub async fn txpool_inspect<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    _params: Params<'static>,
+) -> Result<serde_json::Value> {
+    let mut pending = serde_json::Map::new();
+    for (sender, txs) in ctx.lock().await.tx_queue.eth_by_sender() {
+        let txs = txs
+            .iter()
+            .map(|(nonce, tx)| {
+                let to = match tx.to() {
+                    TxKind::Call(to) => to.to_string(),
+                    TxKind::Create => "contract creation".to_string(),
+                };
+                let summary = format!(
+                    "{}: {} wei + {} gas × {} wei",
+                    to,
+                    tx.value(),
+                    tx.gas_limit(),
+                    tx.max_fee_per_gas()
+                );
+                (nonce.to_string(), summary.into())
+            })
+            .collect::<serde_json::Map<_, _>>();
+        pending.insert(sender.to_string(), txs.into());
+    }
+    Ok(json!({ "pending": pending, "queued": {} }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::{push_mint_block, queue_call, test_config};
+    use alloy::primitives::Address;
+    use alloy::signers::local::PrivateKeySigner;
+
+    #[tokio::test]
+    async fn test_txpool_inspect() {
+        let ctx = test_config().await;
+        let signer = PrivateKeySigner::random();
+        let to = Address::from([2; 20]);
+        push_mint_block(&ctx, signer.address(), 1_000_000_000_000_000_000).await;
+        queue_call(&ctx, &signer, 0, to, 5, vec![]).await;
+
+        let inspect = txpool_inspect(ctx.clone(), Params::new(None))
+            .await
+            .unwrap();
+        assert_eq!(
+            inspect["pending"][signer.address().to_string()]["0"],
+            format!("{}: 5 wei + 100000 gas × 10000000000 wei", to)
+        );
+    }
+}
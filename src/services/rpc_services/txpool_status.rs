@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use jsonrpsee::types::Params;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::Context;
+use crate::services::{ContextKvStore, ContextSigner};
+
+/// Number of queued Ethereum transactions. Admission keeps every sender's
+/// nonces contiguous, so nothing is ever `queued` in the Geth sense.
+pub async fn txpool_status<S: ContextSigner, K: ContextKvStore>(
+    ctx: Arc<Mutex<Context<S, K>>>,
+    _params: Params<'static>,
+) -> Result<serde_json::Value> {
+    let pending: usize = ctx
+        .lock()
+        .await
+        .tx_queue
+        .eth_by_sender()
+        .values()
+        .map(|txs| txs.len())
+        .sum();
+    Ok(json!({
+        "pending": format!("0x{:x}", pending),
+        "queued": "0x0",
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rpc_services::{push_mint_block, queue_call, test_config};
+    use alloy::primitives::Address;
+    use alloy::signers::local::PrivateKeySigner;
+
+    #[tokio::test]
+    async fn test_txpool_status() {
+        let ctx = test_config().await;
+        let signer = PrivateKeySigner::random();
+        push_mint_block(&ctx, signer.address(), 1_000_000_000_000_000_000).await;
+        for nonce in 0..3 {
+            queue_call(&ctx, &signer, nonce, Address::from([2; 20]), 1, vec![]).await;
+        }
+
+        let status = txpool_status(ctx.clone(), Params::new(None)).await.unwrap();
+        assert_eq!(status, json!({ "pending": "0x3", "queued": "0x0" }));
+    }
+}
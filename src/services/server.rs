@@ -1,11 +1,12 @@
-use crate::blockchain::Blockchain;
+use crate::blockchain::{mempool::InvalidTransaction, tx::NonceError, Blockchain};
 
 use crate::services::api_services::api_routes;
 use crate::services::Context;
 
 use anyhow::Result;
+use jsonrpsee::core::traits::IdProvider;
 use jsonrpsee::server::{RpcModule, Server};
-use jsonrpsee::types::ErrorCode;
+use jsonrpsee::types::{ErrorCode, ErrorObject, ErrorObjectOwned, SubscriptionId};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -31,9 +32,23 @@ pub async fn api_server<S: ContextSigner + 'static, K: ContextKvStore + 'static>
     Ok(())
 }
 
-fn anyhow_to_rpc_error(e: anyhow::Error) -> ErrorCode {
+pub(crate) fn anyhow_to_rpc_error(e: anyhow::Error) -> ErrorObjectOwned {
     log::error!("RPC Error: {}", e);
-    ErrorCode::InternalError
+    if e.is::<NonceError>() || e.is::<InvalidTransaction>() {
+        // Same code Geth uses for transaction pool rejections
+        return ErrorObject::owned(-32000, e.to_string(), None::<()>);
+    }
+    ErrorCode::InternalError.into()
+}
+
+/// Subscription ids in the hex form Ethereum clients expect
+#[derive(Debug)]
+struct HexIdProvider;
+
+impl IdProvider for HexIdProvider {
+    fn next_id(&self) -> SubscriptionId<'static> {
+        SubscriptionId::Str(format!("0x{:x}", rand::random::<u128>()).into())
+    }
 }
 
 pub async fn rpc_server<S: ContextSigner + 'static, K: ContextKvStore + 'static>(
@@ -50,57 +65,58 @@ pub async fn rpc_server<S: ContextSigner + 'static, K: ContextKvStore + 'static>
     let rpc_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port));
     let server = Server::builder()
         .set_http_middleware(middleware)
+        .set_id_provider(HexIdProvider)
         .build(rpc_addr)
         .await?;
     let mut module = RpcModule::new(ctx);
 
     module.register_async_method("net_version", move |params, ctx, _| async move {
         log::info!("net_version! {:?}", params);
-        crate::services::rpc_services::net_version(ctx, params)
+        crate::services::rpc_services::net_version((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
     module.register_async_method("eth_call", move |params, ctx, _| async move {
         log::info!("eth_call! {:?}", params);
-        crate::services::rpc_services::eth_call(ctx, params)
+        crate::services::rpc_services::eth_call((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
     module.register_async_method("eth_blockNumber", move |params, ctx, _| async move {
         log::info!("eth_blockNumber! {:?}", params);
-        crate::services::rpc_services::eth_block_number(ctx, params)
+        crate::services::rpc_services::eth_block_number((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
 
     module.register_async_method("eth_getBalance", move |params, ctx, _| async move {
         log::info!("eth_getBalance! {:?}", params);
-        crate::services::rpc_services::eth_get_balance(ctx, params)
+        crate::services::rpc_services::eth_get_balance((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
 
     module.register_async_method("eth_sendTransaction", move |params, ctx, _| async move {
         log::info!("eth_sendTransaction! {:?}", params);
-        crate::services::rpc_services::todo(ctx, params)
+        crate::services::rpc_services::todo((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
     module.register_async_method("eth_sendRawTransaction", move |params, ctx, _| async move {
         log::info!("eth_sendRawTransaction! {:?}", params);
-        crate::services::rpc_services::eth_send_raw_transaction(ctx, params)
+        crate::services::rpc_services::eth_send_raw_transaction((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
     module.register_async_method("eth_estimateGas", move |params, ctx, _| async move {
         log::info!("eth_estimateGas! {:?}", params);
-        crate::services::rpc_services::eth_estimate_gas(ctx, params)
+        crate::services::rpc_services::eth_estimate_gas((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
     module.register_async_method("eth_gasPrice", move |params, ctx, _| async move {
         log::info!("eth_gasPrice! {:?}", params);
-        crate::services::rpc_services::eth_get_gas_price(ctx, params)
+        crate::services::rpc_services::eth_get_gas_price((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
@@ -108,7 +124,7 @@ pub async fn rpc_server<S: ContextSigner + 'static, K: ContextKvStore + 'static>
         "eth_getTransactionCount",
         move |params, ctx, _| async move {
             log::info!("eth_getTransactionCount! {:?}", params);
-            crate::services::rpc_services::eth_get_transaction_count(ctx, params)
+            crate::services::rpc_services::eth_get_transaction_count((*ctx).clone(), params)
                 .await
                 .map_err(anyhow_to_rpc_error)
         },
@@ -117,47 +133,143 @@ pub async fn rpc_server<S: ContextSigner + 'static, K: ContextKvStore + 'static>
         "eth_getTransactionReceipt",
         move |params, ctx, _| async move {
             log::info!("eth_getTransactionReceipt! {:?}", params);
-            crate::services::rpc_services::eth_get_transaction_receipt(ctx, params)
+            crate::services::rpc_services::eth_get_transaction_receipt((*ctx).clone(), params)
                 .await
                 .map_err(anyhow_to_rpc_error)
         },
     )?;
     module.register_async_method("eth_getBlockByNumber", move |params, ctx, _| async move {
         log::info!("eth_getBlockByNumber! {:?}", params);
-        crate::services::rpc_services::eth_get_block_by_number(ctx, params)
+        crate::services::rpc_services::eth_get_block_by_number((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
     module.register_async_method("eth_chainId", move |params, ctx, _| async move {
         log::info!("eth_chainId! {:?}", params);
-        crate::services::rpc_services::eth_chain_id(ctx, params)
+        crate::services::rpc_services::eth_chain_id((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
     module.register_async_method("eth_requestAccounts", move |params, ctx, _| async move {
         log::info!("eth_requestAccounts! {:?}", params);
-        crate::services::rpc_services::eth_request_accounts(ctx, params)
+        crate::services::rpc_services::eth_request_accounts((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
     module.register_async_method("eth_feeHistory", move |params, ctx, _| async move {
         log::info!("eth_feeHistory! {:?}", params);
-        crate::services::rpc_services::eth_fee_history(ctx, params)
+        crate::services::rpc_services::eth_fee_history((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
+    module.register_async_method(
+        "eth_maxPriorityFeePerGas",
+        move |params, ctx, _| async move {
+            log::info!("eth_maxPriorityFeePerGas! {:?}", params);
+            crate::services::rpc_services::eth_max_priority_fee_per_gas((*ctx).clone(), params)
+                .await
+                .map_err(anyhow_to_rpc_error)
+        },
+    )?;
     module.register_async_method(
         "eth_getTransactionByHash",
         move |params, ctx, _| async move {
             log::info!("eth_getTransactionByHash! {:?}", params);
-            crate::services::rpc_services::eth_get_transaction_by_hash(ctx, params)
+            crate::services::rpc_services::eth_get_transaction_by_hash((*ctx).clone(), params)
+                .await
+                .map_err(anyhow_to_rpc_error)
+        },
+    )?;
+    module.register_async_method("owshen_getProof", move |params, ctx, _| async move {
+        log::info!("owshen_getProof! {:?}", params);
+        crate::services::rpc_services::owshen_get_proof((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method(
+        "owshen_getTransactionProof",
+        move |params, ctx, _| async move {
+            log::info!("owshen_getTransactionProof! {:?}", params);
+            crate::services::rpc_services::owshen_get_transaction_proof((*ctx).clone(), params)
                 .await
                 .map_err(anyhow_to_rpc_error)
         },
     )?;
+    module.register_async_method("owshen_getRawBlocks", move |params, ctx, _| async move {
+        log::info!("owshen_getRawBlocks! {:?}", params);
+        crate::services::rpc_services::owshen_get_raw_blocks((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method("eth_getLogs", move |params, ctx, _| async move {
+        log::info!("eth_getLogs! {:?}", params);
+        crate::services::rpc_services::eth_get_logs((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method("eth_newFilter", move |params, ctx, _| async move {
+        log::info!("eth_newFilter! {:?}", params);
+        crate::services::rpc_services::eth_new_filter((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method("eth_newBlockFilter", move |params, ctx, _| async move {
+        log::info!("eth_newBlockFilter! {:?}", params);
+        crate::services::rpc_services::eth_new_block_filter((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method(
+        "eth_newPendingTransactionFilter",
+        move |params, ctx, _| async move {
+            log::info!("eth_newPendingTransactionFilter! {:?}", params);
+            crate::services::rpc_services::eth_new_pending_transaction_filter(
+                (*ctx).clone(),
+                params,
+            )
+            .await
+            .map_err(anyhow_to_rpc_error)
+        },
+    )?;
+    module.register_async_method("eth_getFilterChanges", move |params, ctx, _| async move {
+        log::info!("eth_getFilterChanges! {:?}", params);
+        crate::services::rpc_services::eth_get_filter_changes((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method("eth_getFilterLogs", move |params, ctx, _| async move {
+        log::info!("eth_getFilterLogs! {:?}", params);
+        crate::services::rpc_services::eth_get_filter_logs((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method("eth_uninstallFilter", move |params, ctx, _| async move {
+        log::info!("eth_uninstallFilter! {:?}", params);
+        crate::services::rpc_services::eth_uninstall_filter((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method("txpool_content", move |params, ctx, _| async move {
+        log::info!("txpool_content! {:?}", params);
+        crate::services::rpc_services::txpool_content((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method("txpool_inspect", move |params, ctx, _| async move {
+        log::info!("txpool_inspect! {:?}", params);
+        crate::services::rpc_services::txpool_inspect((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
+    module.register_async_method("txpool_status", move |params, ctx, _| async move {
+        log::info!("txpool_status! {:?}", params);
+        crate::services::rpc_services::txpool_status((*ctx).clone(), params)
+            .await
+            .map_err(anyhow_to_rpc_error)
+    })?;
     module.register_async_method("eth_get_code", move |params, ctx, _| async move {
         log::info!("eth_getTransactionByHash! {:?}", params);
-        crate::services::rpc_services::eth_get_code(ctx, params)
+        crate::services::rpc_services::eth_get_code((*ctx).clone(), params)
             .await
             .map_err(anyhow_to_rpc_error)
     })?;
@@ -194,32 +306,35 @@ pub async fn rpc_server<S: ContextSigner + 'static, K: ContextKvStore + 'static>
         "eth_getBlockTransactionCountByHash",
         "eth_getBlockTransactionCountByNumber",
         "eth_getCode",
-        "eth_getFilterChanges",
-        "eth_getFilterLogs",
-        "eth_getLogs",
         "eth_getProof",
         "eth_getStorageAt",
         "eth_getTransactionByBlockHashAndIndex",
         "eth_getTransactionByBlockNumberAndIndex",
         "eth_getUncleCountByBlockHash",
         "eth_getUncleCountByBlockNumber",
-        "eth_maxPriorityFeePerGas",
-        "eth_newBlockFilter",
-        "eth_newFilter",
-        "eth_newPendingTransactionFilter",
         "eth_sign",
         "eth_signTransaction",
         "eth_syncing",
-        "eth_uninstallFilter",
     ] {
         module.register_async_method(method_name, move |params, ctx, _| async move {
             log::info!("{}! {:?}", method_name, params);
-            crate::services::rpc_services::todo(ctx, params)
+            crate::services::rpc_services::todo((*ctx).clone(), params)
                 .await
                 .map_err(anyhow_to_rpc_error)
         })?;
     }
 
+    // Served over WebSocket connections, on the same port as HTTP
+    module.register_subscription(
+        "eth_subscribe",
+        "eth_subscription",
+        "eth_unsubscribe",
+        move |params, pending, ctx, _| async move {
+            log::info!("eth_subscribe! {:?}", params);
+            crate::services::rpc_services::eth_subscribe((*ctx).clone(), params, pending).await
+        },
+    )?;
+
     let addr = server.local_addr()?;
     log::info!("Running RPC server on: {} (Chain-id: {})", addr, chain_id);
     let handle = server.start(module);
@@ -20,6 +20,36 @@ pub struct Block {
     pub txs: Vec<BincodableOwshenTransaction>,
     pub sig: Option<alloy::primitives::Signature>,
     pub timestamp: u64,
+    pub base_fee_per_gas: u64,
+    pub gas_used: u64,
+    pub state_root: FixedBytes<32>,
+    pub transactions_root: FixedBytes<32>,
+    pub receipts_root: FixedBytes<32>,
+}
+
+/// Event emitted by a contract, or by the chain itself for built-in tokens
+/// and bridge operations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<FixedBytes<32>>,
+    pub data: Vec<u8>,
+}
+
+/// Outcome of an included transaction, committed to by the receipts root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub tx_hash: FixedBytes<32>,
+    pub success: bool,
+    pub gas_used: u64,
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<Log>,
+}
+
+impl Receipt {
+    pub fn hash(&self) -> Result<FixedBytes<32>> {
+        Ok(keccak256(bincode::serialize(self)?))
+    }
 }
 
 impl Block {
@@ -54,6 +84,16 @@ pub enum Token {
     Erc20(ERC20),
 }
 
+impl Token {
+    /// Contract of the token, the zero address standing for the native token
+    pub fn address(&self) -> Address {
+        match self {
+            Token::Native => Address::ZERO,
+            Token::Erc20(erc20) => erc20.address,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Serialize, Clone, Deserialize)]
 pub struct ERC20 {
     pub address: Address,
@@ -1,14 +1,61 @@
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use anyhow::{anyhow, Result};
+
+/// An EVM chain the Owshen contract is deployed on, which users can deposit
+/// from and withdraw to.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Network {
-    ETH,
-    BSC,
+pub struct Network {
+    pub chain_id: u64,
+    pub owshen: Address,
+    pub provider_address: reqwest::Url,
+    /// Number of blocks a deposit needs before it can be minted
+    pub confirmations: u64,
 }
 
-impl Network {
-    pub fn chain_id(&self) -> u64 {
-        match self {
-            Network::ETH => 1,
-            Network::BSC => 56,
+/// Parses `<chain_id>,<owshen>,<provider_address>,<confirmations>`
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+        if parts.len() != 4 {
+            return Err(anyhow!(
+                "Expected <chain_id>,<owshen>,<provider_address>,<confirmations>, got: {}",
+                s
+            ));
         }
+        Ok(Network {
+            chain_id: parts[0].parse()?,
+            owshen: parts[1].parse()?,
+            provider_address: parts[2].parse()?,
+            confirmations: parts[3].parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_network() {
+        let network: Network =
+            "56,0x0101010101010101010101010101010101010101,https://bsc.example.com,15"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            network,
+            Network {
+                chain_id: 56,
+                owshen: Address::from([1; 20]),
+                provider_address: "https://bsc.example.com".parse().unwrap(),
+                confirmations: 15,
+            }
+        );
+        assert!("56,0x01,https://bsc.example.com"
+            .parse::<Network>()
+            .is_err());
     }
 }
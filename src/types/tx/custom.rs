@@ -10,14 +10,22 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::Read;
 
+use crate::types::Token;
 use crate::types::ERC20;
-use crate::types::{network::Network, Token};
 
 use super::OwshenTransaction;
 
+/// Length-checked `Address::from_slice`, the payloads may come from untrusted users
+fn decode_address(bytes: &[u8]) -> Result<Address, DecoderError> {
+    Address::try_from(bytes).map_err(|_| DecoderError::RlpInvalidLength)
+}
+
+/// Length-checked `U256::from_le_slice`
+fn decode_u256(bytes: &[u8]) -> Result<U256, DecoderError> {
+    U256::try_from_le_slice(bytes).ok_or(DecoderError::RlpInvalidLength)
+}
+
 // TODO: OwshenAirdrop transaction (Should contain "Owshen address" and "Owshen signature")
-// TODO: Mint transaction (Should contain chain_id (Depsotior Network-id) and tx_hash (Deposit TxHash))
-// TODO: Burn transaction (Should contain chain_id (Withdrawer Network-id) and withdraw_sig (Withdrawal signature))
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Mint {
@@ -26,26 +34,31 @@ pub struct Mint {
     pub token: Token,
     pub amount: U256,
     pub address: Address,
+    pub nonce: U256,
+    /// Network the deposit was made on
+    pub chain_id: u64,
 }
 
 impl rlp::Encodable for Mint {
     fn rlp_append(&self, s: &mut RlpStream) {
         match &self.token {
             Token::Native => {
-                s.begin_list(6);
+                s.begin_list(8);
                 s.append(&"mint");
                 s.append(&"native");
                 s.append(&self.tx_hash);
                 s.append(&self.user_tx_hash);
                 s.append(&self.amount.as_le_bytes().to_vec());
                 s.append(&self.address.to_vec());
+                s.append(&self.nonce.as_le_bytes().to_vec());
+                s.append(&self.chain_id);
             }
             Token::Erc20(ERC20 {
                 address,
                 decimals,
                 symbol,
             }) => {
-                s.begin_list(9);
+                s.begin_list(11);
                 s.append(&"mint");
                 s.append(&"erc20");
                 s.append(&self.tx_hash);
@@ -55,6 +68,8 @@ impl rlp::Encodable for Mint {
                 s.append(&address.to_vec());
                 s.append(&decimals.as_le_bytes().to_vec());
                 s.append(&symbol.as_str());
+                s.append(&self.nonce.as_le_bytes().to_vec());
+                s.append(&self.chain_id);
             }
         }
     }
@@ -67,27 +82,36 @@ impl rlp::Decodable for Mint {
         let user_tx_hash: String = rlp.val_at(3)?;
         let amount: Vec<u8> = rlp.val_at(4)?;
         let address: Vec<u8> = rlp.val_at(5)?;
+        let nonce_idx;
         let token = match token_type.as_str() {
-            "native" => Token::Native,
+            "native" => {
+                nonce_idx = 6;
+                Token::Native
+            }
             "erc20" => {
+                nonce_idx = 9;
                 let token_address: Vec<u8> = rlp.val_at(6)?;
                 let token_decimals: Vec<u8> = rlp.val_at(7)?;
                 let token_symbol: String = rlp.val_at(8)?;
-                let token_address = Address::from_slice(&token_address);
+                let token_address = decode_address(&token_address)?;
                 Token::Erc20(ERC20 {
                     address: token_address,
-                    decimals: U256::from_le_slice(&token_decimals),
+                    decimals: decode_u256(&token_decimals)?,
                     symbol: token_symbol,
                 })
             }
-            _ => return Err(rlp::DecoderError::RlpExpectedToBeData),
+            _ => return Err(DecoderError::RlpExpectedToBeData),
         };
+        let nonce: Vec<u8> = rlp.val_at(nonce_idx)?;
+        let chain_id: u64 = rlp.val_at(nonce_idx + 1)?;
         Ok(Mint {
             tx_hash,
             user_tx_hash,
             token,
-            amount: U256::from_le_slice(&amount),
-            address: Address::from_slice(&address),
+            amount: decode_u256(&amount)?,
+            address: decode_address(&address)?,
+            nonce: decode_u256(&nonce)?,
+            chain_id,
         })
     }
 }
@@ -102,10 +126,12 @@ pub enum WithdrawCalldata {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Burn {
     pub burn_id: FixedBytes<32>,
-    pub network: Network,
+    /// Network the funds are withdrawn to
+    pub chain_id: u64,
     pub token: Token,
     pub amount: U256,
     pub calldata: Option<WithdrawCalldata>,
+    pub nonce: U256,
 }
 
 impl rlp::Encodable for Burn {
@@ -113,7 +139,7 @@ impl rlp::Encodable for Burn {
         let dl = if self.calldata.is_some() { 2 } else { 0 };
         match &self.token {
             Token::Native => {
-                s.begin_list(4 + dl);
+                s.begin_list(5 + dl);
                 s.append(&"burn");
                 s.append(&self.burn_id.to_vec());
                 s.append(&"native");
@@ -124,7 +150,7 @@ impl rlp::Encodable for Burn {
                 decimals,
                 symbol,
             }) => {
-                s.begin_list(7 + dl);
+                s.begin_list(8 + dl);
                 s.append(&"burn");
                 s.append(&self.burn_id.to_vec());
                 s.append(&"erc20");
@@ -134,11 +160,8 @@ impl rlp::Encodable for Burn {
                 s.append(&symbol.as_str());
             }
         }
-        let network = match self.network {
-            Network::ETH => "eth",
-            Network::BSC => "bsc",
-        };
-        s.append(&network);
+        s.append(&self.chain_id);
+        s.append(&self.nonce.as_le_bytes().to_vec());
 
         if let Some(calldata) = &self.calldata {
             match calldata {
@@ -155,59 +178,48 @@ impl rlp::Decodable for Burn {
         let burn_id: Vec<u8> = rlp.val_at(1)?;
         let token_type: String = rlp.val_at(2)?;
         let amount: Vec<u8> = rlp.val_at(3)?;
-        let network_idx;
+        let chain_id_idx;
         let calldata_idx;
         let token = match token_type.as_str() {
             "native" => {
-                network_idx = 4;
-                calldata_idx = 5;
+                chain_id_idx = 4;
+                calldata_idx = 6;
                 Token::Native
             }
             "erc20" => {
-                network_idx = 7;
-                calldata_idx = 8;
+                chain_id_idx = 7;
+                calldata_idx = 9;
                 let address: Vec<u8> = rlp.val_at(4)?;
                 let token_decimals: Vec<u8> = rlp.val_at(5)?;
                 let token_symbol: String = rlp.val_at(6)?;
-                let token_address = Address::from_slice(&address);
+                let token_address = decode_address(&address)?;
                 Token::Erc20(ERC20 {
                     address: token_address,
-                    decimals: U256::from_le_slice(&token_decimals),
+                    decimals: decode_u256(&token_decimals)?,
                     symbol: token_symbol,
                 })
             }
-            _ => return Err(rlp::DecoderError::RlpExpectedToBeData),
-        };
-        let network: String = rlp.val_at(network_idx)?;
-        let network = match network.as_str() {
-            "eth" => Network::ETH,
-            "bsc" => Network::BSC,
-            _ => return Err(rlp::DecoderError::RlpExpectedToBeData),
+            _ => return Err(DecoderError::RlpExpectedToBeData),
         };
+        let chain_id: u64 = rlp.val_at(chain_id_idx)?;
+        let nonce: Vec<u8> = rlp.val_at(chain_id_idx + 1)?;
+        let nonce = decode_u256(&nonce)?;
 
-        let address: Result<Vec<u8>, _> = rlp.val_at(calldata_idx);
-        match address {
-            Ok(address) => {
-                let address = Address::from_slice(&address);
-                let calldata = Some(WithdrawCalldata::Eth { address });
-                return Ok(Burn {
-                    burn_id: FixedBytes::from_slice(&burn_id),
-                    network,
-                    token,
-                    amount: U256::from_le_slice(&amount),
-                    calldata,
-                });
-            }
-            Err(_) => {
-                return Ok(Burn {
-                    burn_id: FixedBytes::from_slice(&burn_id),
-                    network,
-                    token,
-                    amount: U256::from_le_slice(&amount),
-                    calldata: None,
-                });
-            }
-        }
+        let calldata = match rlp.val_at::<Vec<u8>>(calldata_idx) {
+            Ok(address) => Some(WithdrawCalldata::Eth {
+                address: decode_address(&address)?,
+            }),
+            Err(_) => None,
+        };
+        Ok(Burn {
+            burn_id: FixedBytes::try_from(burn_id.as_slice())
+                .map_err(|_| DecoderError::RlpInvalidLength)?,
+            chain_id,
+            token,
+            amount: decode_u256(&amount)?,
+            calldata,
+            nonce,
+        })
     }
 }
 
@@ -220,6 +232,19 @@ pub enum CustomTxMsg {
     BurnTx(Burn),
 }
 impl CustomTxMsg {
+    pub fn nonce(&self) -> U256 {
+        match self {
+            CustomTxMsg::MintTx(mint_data) => mint_data.nonce,
+            CustomTxMsg::BurnTx(burn_data) => burn_data.nonce,
+        }
+    }
+    /// Chain id of the L1 network the tokens are bridged from or to
+    pub fn network_id(&self) -> u64 {
+        match self {
+            CustomTxMsg::MintTx(mint_data) => mint_data.chain_id,
+            CustomTxMsg::BurnTx(burn_data) => burn_data.chain_id,
+        }
+    }
     pub fn as_rlp(&self) -> Vec<u8> {
         match self {
             // CustomTxMsg::OwshenAirdrop {
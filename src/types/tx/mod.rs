@@ -72,6 +72,7 @@ pub struct IncludedTransaction {
 impl OwshenTransaction {
     pub fn chain_id(&self) -> Result<u64> {
         Ok(match self {
+            // Legacy transactions must be replay protected (EIP-155)
             Self::Eth(tx) => tx.chain_id().ok_or(anyhow!("Chain-id not provided!"))?,
             Self::Custom(tx) => tx.chain_id,
         })
@@ -18,6 +18,8 @@ async fn test_mint_tx_native() {
             token: Token::Native,
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(7),
+            chain_id: 1,
         }),
     )
     .await
@@ -31,6 +33,7 @@ async fn test_mint_tx_native() {
                 assert_eq!(mint.token, Token::Native);
                 assert_eq!(mint.amount, U256::from(100));
                 assert_eq!(mint.address, signer.address());
+                assert_eq!(mint.nonce, U256::from(7));
             }
             _ => panic!("Invalid tx!"),
         },
@@ -61,6 +64,8 @@ async fn test_mint_tx_erc20() {
             token: token.clone(),
             amount: U256::from(100),
             address: signer.address(),
+            nonce: U256::from(0),
+            chain_id: 1,
         }),
     )
     .await
@@ -90,10 +95,11 @@ async fn test_burn_tx_native() {
         chain_id,
         CustomTxMsg::BurnTx(Burn {
             burn_id: FixedBytes::from([1u8; 32]),
-            network: crate::types::network::Network::BSC,
+            chain_id: 56,
             token: Token::Native,
             amount: U256::from(100),
             calldata: None,
+            nonce: U256::from(0),
         }),
     )
     .await
@@ -126,10 +132,11 @@ async fn test_burn_tx_erc20() {
         chain_id,
         CustomTxMsg::BurnTx(Burn {
             burn_id: FixedBytes::from([1u8; 32]),
-            network: crate::types::network::Network::BSC,
+            chain_id: 56,
             token: token.clone(),
             amount: U256::from(100),
             calldata: None,
+            nonce: U256::from(0),
         }),
     )
     .await